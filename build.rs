@@ -1,11 +1,21 @@
+use std::path::PathBuf;
+
 fn main() {
     let mut config = prost_build::Config::new();
     config.bytes(["."]);
-    config
+
+    // gRPC 反射服务需要的文件描述符集合
+    let descriptor_path =
+        PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("abi_descriptor.bin");
+
+    tonic_build::configure()
         .out_dir("src/pb")
-        .compile_protos(&["abi.proto"], &["."])
+        .file_descriptor_set_path(descriptor_path)
+        .compile_with_config(config, &["abi.proto"], &["."])
         .unwrap();
 
     println!("cargo:rerun-if-changed=build.rs");
     println!("cargo:rerun-if-changed=abi.proto");
+    // 新增迁移文件时重新嵌入
+    println!("cargo:rerun-if-changed=migrations");
 }
@@ -1,45 +1,71 @@
-import React, { useState } from 'react';
+import React, { useState, useEffect } from 'react';
+import { api, getToken } from './api';
+import { User } from './types';
 import HomePage from './pages/HomePage';
+import LoginPage from './pages/LoginPage';
 import ManagePage from './pages/ManagePage';
 
 const App: React.FC = () => {
   const [currentPage, setCurrentPage] = useState<'home' | 'manage'>('home');
-  const [userId] = useState<string>(() => {
-    // Simple user ID generation for demo purposes
-    return localStorage.getItem('userId') || (() => {
-      const id = 'user_' + Math.random().toString(36).substr(2, 9);
-      localStorage.setItem('userId', id);
-      return id;
-    })();
-  });
+  const [user, setUser] = useState<User | null>(null);
+  const [checking, setChecking] = useState(() => getToken() !== null);
+
+  // 已保存的 Token 仍有效时直接恢复登录状态
+  useEffect(() => {
+    if (!getToken()) return;
+    api.me()
+      .then(setUser)
+      .catch(() => setUser(null))
+      .finally(() => setChecking(false));
+  }, []);
+
+  const handleLogout = async () => {
+    try {
+      await api.logout();
+    } finally {
+      setUser(null);
+      setCurrentPage('home');
+    }
+  };
 
   return (
     <div className="app">
       <nav className="navbar">
         <div className="nav-container">
           <h1 className="nav-title">短链接服务</h1>
-          <div className="nav-buttons">
-            <button
-              className={`nav-button ${currentPage === 'home' ? 'active' : ''}`}
-              onClick={() => setCurrentPage('home')}
-            >
-              生成短链接
-            </button>
-            <button
-              className={`nav-button ${currentPage === 'manage' ? 'active' : ''}`}
-              onClick={() => setCurrentPage('manage')}
-            >
-              管理链接
-            </button>
-          </div>
+          {user && (
+            <div className="nav-buttons">
+              <button
+                className={`nav-button ${currentPage === 'home' ? 'active' : ''}`}
+                onClick={() => setCurrentPage('home')}
+              >
+                生成短链接
+              </button>
+              <button
+                className={`nav-button ${currentPage === 'manage' ? 'active' : ''}`}
+                onClick={() => setCurrentPage('manage')}
+              >
+                管理链接
+              </button>
+              <button className="nav-button" onClick={handleLogout}>
+                退出（{user.username}）
+              </button>
+            </div>
+          )}
         </div>
       </nav>
 
       <main className="main-content">
-        {currentPage === 'home' ? (
-          <HomePage userId={userId} />
+        {checking ? (
+          <div className="container">
+            <div className="loading">加载中...</div>
+          </div>
+        ) : !user ? (
+          <LoginPage onLogin={setUser} />
+        ) : currentPage === 'home' ? (
+          <HomePage />
         ) : (
-          <ManagePage userId={userId} />
+          <ManagePage />
         )}
       </main>
     </div>
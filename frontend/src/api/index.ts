@@ -1,47 +1,102 @@
-import { ShortUrl, CreateShortUrlRequest, ApiResponse, QRCodeResponse } from '../types';
+import { ShortUrl, CreateShortUrlRequest, ApiResponse, Credentials, LoginResponse, User, UrlPage, ListUrlsParams, UpdateShortUrlRequest } from '../types';
 
 const API_BASE_URL = 'http://localhost:8080/api';
+const TOKEN_KEY = 'authToken';
 
-export const api = {
-  async createShortUrl(request: CreateShortUrlRequest): Promise<ShortUrl> {
-    const response = await fetch(`${API_BASE_URL}/shorten`, {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-      },
-      body: JSON.stringify(request),
-    });
+export const getToken = (): string | null => localStorage.getItem(TOKEN_KEY);
+
+const setToken = (token: string | null) => {
+  if (token) {
+    localStorage.setItem(TOKEN_KEY, token);
+  } else {
+    localStorage.removeItem(TOKEN_KEY);
+  }
+};
+
+const authHeaders = (): Record<string, string> => {
+  const token = getToken();
+  return token ? { Authorization: `Bearer ${token}` } : {};
+};
+
+async function request<T>(path: string, init: RequestInit = {}): Promise<T> {
+  const response = await fetch(`${API_BASE_URL}${path}`, {
+    ...init,
+    headers: {
+      'Content-Type': 'application/json',
+      ...authHeaders(),
+      ...(init.headers || {}),
+    },
+  });
 
-    const result: ApiResponse<ShortUrl> = await response.json();
+  const result: ApiResponse<T> = await response.json();
 
-    if (!result.success) {
-      throw new Error(result.message);
+  if (!result.success) {
+    // Token 失效时清除本地登录状态
+    if (response.status === 401) {
+      setToken(null);
     }
+    throw new Error(result.message);
+  }
+
+  return result.data!;
+}
 
-    return result.data!;
+export const api = {
+  async register(credentials: Credentials): Promise<User> {
+    return request<User>('/auth/register', {
+      method: 'POST',
+      body: JSON.stringify(credentials),
+    });
   },
 
-  async getUserUrls(userId: string): Promise<ShortUrl[]> {
-    const response = await fetch(`${API_BASE_URL}/urls/${userId}`);
-    const result: ApiResponse<ShortUrl[]> = await response.json();
+  async login(credentials: Credentials): Promise<LoginResponse> {
+    const login = await request<LoginResponse>('/auth/login', {
+      method: 'POST',
+      body: JSON.stringify(credentials),
+    });
+    setToken(login.token);
+    return login;
+  },
 
-    if (!result.success) {
-      throw new Error(result.message);
+  async logout(): Promise<void> {
+    try {
+      await request<void>('/auth/logout', { method: 'POST' });
+    } finally {
+      setToken(null);
     }
+  },
+
+  async me(): Promise<User> {
+    return request<User>('/auth/me');
+  },
 
-    return result.data!;
+  async createShortUrl(body: CreateShortUrlRequest): Promise<ShortUrl> {
+    return request<ShortUrl>('/shorten', {
+      method: 'POST',
+      body: JSON.stringify(body),
+    });
   },
 
-  async deleteShortUrl(id: number, userId: string): Promise<void> {
-    const response = await fetch(`${API_BASE_URL}/urls/${id}/${userId}`, {
-      method: 'DELETE',
+  async getUserUrls(params: ListUrlsParams = {}): Promise<UrlPage> {
+    const query = new URLSearchParams();
+    Object.entries(params).forEach(([key, value]) => {
+      if (value !== undefined && value !== '') {
+        query.set(key, String(value));
+      }
     });
+    const search = query.toString();
+    return request<UrlPage>(search ? `/urls?${search}` : '/urls');
+  },
 
-    const result: ApiResponse<void> = await response.json();
+  async updateShortUrl(id: number, body: UpdateShortUrlRequest): Promise<ShortUrl> {
+    return request<ShortUrl>(`/urls/${id}`, {
+      method: 'PATCH',
+      body: JSON.stringify(body),
+    });
+  },
 
-    if (!result.success) {
-      throw new Error(result.message);
-    }
+  async deleteShortUrl(id: number): Promise<void> {
+    return request<void>(`/urls/${id}`, { method: 'DELETE' });
   },
 
   async getQRCode(shortCode: string): Promise<string> {
@@ -2,11 +2,7 @@ import React, { useState } from 'react';
 import { api } from '../api';
 import { ShortUrl } from '../types';
 
-interface HomePageProps {
-  userId: string;
-}
-
-const HomePage: React.FC<HomePageProps> = ({ userId }) => {
+const HomePage: React.FC = () => {
   const [longUrl, setLongUrl] = useState('');
   const [customCode, setCustomCode] = useState('');
   const [timeout, setTimeout] = useState('');
@@ -25,7 +21,6 @@ const HomePage: React.FC<HomePageProps> = ({ userId }) => {
         long_url: longUrl,
         custom_code: customCode || undefined,
         timeout: timeout ? parseInt(timeout) : undefined,
-        user_id: userId,
       };
 
       const shortUrl = await api.createShortUrl(request);
@@ -100,7 +95,7 @@ const HomePage: React.FC<HomePageProps> = ({ userId }) => {
 
         {result && (
           <div className="result-card">
-            <h3>短链接生成成功！</h3>
+            <h3>{result.reused ? '已存在相同链接，返回已有短链接' : '短链接生成成功！'}</h3>
             <div className="result-item">
               <label>原始链接：</label>
               <span>{result.long_url}</span>
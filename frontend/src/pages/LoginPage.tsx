@@ -0,0 +1,87 @@
+import React, { useState } from 'react';
+import { api } from '../api';
+import { User } from '../types';
+
+interface LoginPageProps {
+  onLogin: (user: User) => void;
+}
+
+const LoginPage: React.FC<LoginPageProps> = ({ onLogin }) => {
+  const [mode, setMode] = useState<'login' | 'register'>('login');
+  const [username, setUsername] = useState('');
+  const [password, setPassword] = useState('');
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState('');
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setLoading(true);
+    setError('');
+
+    try {
+      if (mode === 'register') {
+        await api.register({ username, password });
+      }
+      const login = await api.login({ username, password });
+      onLogin(login.user);
+    } catch (err) {
+      setError(err instanceof Error ? err.message : '登录失败');
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  return (
+    <div className="login-page">
+      <div className="container">
+        <h2>{mode === 'login' ? '登录' : '注册'}</h2>
+
+        <form onSubmit={handleSubmit} className="url-form">
+          <div className="form-group">
+            <label htmlFor="username">用户名</label>
+            <input
+              type="text"
+              id="username"
+              value={username}
+              onChange={(e) => setUsername(e.target.value)}
+              autoComplete="username"
+              required
+            />
+          </div>
+
+          <div className="form-group">
+            <label htmlFor="password">密码</label>
+            <input
+              type="password"
+              id="password"
+              value={password}
+              onChange={(e) => setPassword(e.target.value)}
+              autoComplete={mode === 'login' ? 'current-password' : 'new-password'}
+              required
+            />
+          </div>
+
+          <button type="submit" disabled={loading} className="submit-button">
+            {loading ? '请稍候...' : mode === 'login' ? '登录' : '注册并登录'}
+          </button>
+        </form>
+
+        {error && (
+          <div className="error-message">
+            {error}
+          </div>
+        )}
+
+        <button
+          type="button"
+          className="link-button"
+          onClick={() => setMode(mode === 'login' ? 'register' : 'login')}
+        >
+          {mode === 'login' ? '没有账号？注册' : '已有账号？登录'}
+        </button>
+      </div>
+    </div>
+  );
+};
+
+export default LoginPage;
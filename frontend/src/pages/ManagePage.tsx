@@ -4,25 +4,29 @@ import { ShortUrl } from '../types';
 import QRCode from '../components/QRCode';
 import '../styles/qrcode.css';
 
-interface ManagePageProps {
-  userId: string;
-}
+const PAGE_SIZE = 20;
 
-const ManagePage: React.FC<ManagePageProps> = ({ userId }) => {
+const ManagePage: React.FC = () => {
   const [urls, setUrls] = useState<ShortUrl[]>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState('');
   const [selectedUrl, setSelectedUrl] = useState<string | null>(null);
+  const [total, setTotal] = useState(0);
+  const [nextPageToken, setNextPageToken] = useState<string | undefined>();
+  const [status, setStatus] = useState<'' | 'active' | 'expired'>('');
+  const [loadingMore, setLoadingMore] = useState(false);
 
   useEffect(() => {
     loadUrls();
-  }, [userId]);
+  }, [status]);
 
   const loadUrls = async () => {
     try {
       setLoading(true);
-      const userUrls = await api.getUserUrls(userId);
-      setUrls(userUrls);
+      const page = await api.getUserUrls({ page_size: PAGE_SIZE, status: status || undefined });
+      setUrls(page.urls);
+      setTotal(page.total);
+      setNextPageToken(page.next_page_token);
     } catch (err) {
       setError(err instanceof Error ? err.message : '加载链接失败');
     } finally {
@@ -30,19 +34,54 @@ const ManagePage: React.FC<ManagePageProps> = ({ userId }) => {
     }
   };
 
+  const loadMore = async () => {
+    if (!nextPageToken) return;
+
+    try {
+      setLoadingMore(true);
+      const page = await api.getUserUrls({
+        page_size: PAGE_SIZE,
+        status: status || undefined,
+        page_token: nextPageToken,
+      });
+      setUrls([...urls, ...page.urls]);
+      setTotal(page.total);
+      setNextPageToken(page.next_page_token);
+    } catch (err) {
+      setError(err instanceof Error ? err.message : '加载链接失败');
+    } finally {
+      setLoadingMore(false);
+    }
+  };
+
   const handleDelete = async (id: number) => {
     if (!confirm('确定要删除这个短链接吗？')) {
       return;
     }
 
     try {
-      await api.deleteShortUrl(id, userId);
+      await api.deleteShortUrl(id);
       setUrls(urls.filter(url => url.id !== id));
+      setTotal(total - 1);
     } catch (err) {
       alert(err instanceof Error ? err.message : '删除失败');
     }
   };
 
+  const handleEdit = async (url: ShortUrl) => {
+    const longUrl = prompt('新的原始链接：', url.long_url);
+    if (longUrl === null || longUrl === url.long_url) {
+      return;
+    }
+
+    try {
+      const updated = await api.updateShortUrl(url.id, { long_url: longUrl });
+      setUrls(urls.map(item => (item.id === updated.id ? updated : item)));
+    } catch (err) {
+      alert(err instanceof Error ? err.message : '修改失败');
+    }
+  };
+
   const copyToClipboard = (text: string) => {
     navigator.clipboard.writeText(text).then(() => {
       alert('已复制到剪贴板！');
@@ -69,6 +108,18 @@ const ManagePage: React.FC<ManagePageProps> = ({ userId }) => {
       <div className="container">
         <h2>我的短链接</h2>
 
+        <div className="list-toolbar">
+          <span>共 {total} 条</span>
+          <select
+            value={status}
+            onChange={(e) => setStatus(e.target.value as '' | 'active' | 'expired')}
+          >
+            <option value="">全部</option>
+            <option value="active">未过期</option>
+            <option value="expired">已过期</option>
+          </select>
+        </div>
+
         {error && (
           <div className="error-message">
             {error}
@@ -126,6 +177,13 @@ const ManagePage: React.FC<ManagePageProps> = ({ userId }) => {
                   >
                     {selectedUrl === url.short_code ? '隐藏二维码' : '显示二维码'}
                   </button>
+                  <button
+                    type="button"
+                    onClick={() => handleEdit(url)}
+                    className="edit-button"
+                  >
+                    编辑
+                  </button>
                   <button
                     type="button"
                     onClick={() => handleDelete(url.id)}
@@ -139,6 +197,16 @@ const ManagePage: React.FC<ManagePageProps> = ({ userId }) => {
                 )}
               </div>
             ))}
+            {nextPageToken && (
+              <button
+                type="button"
+                onClick={loadMore}
+                disabled={loadingMore}
+                className="load-more-button"
+              >
+                {loadingMore ? '加载中...' : '加载更多'}
+              </button>
+            )}
           </div>
         )}
       </div>
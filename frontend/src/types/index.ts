@@ -5,13 +5,38 @@ export interface ShortUrl {
   short_url: string;
   created_at: string;
   expires_at?: string;
+  reused?: boolean;
+}
+
+export interface UrlPage {
+  urls: ShortUrl[];
+  next_page_token?: string;
+  total: number;
+}
+
+export interface ListUrlsParams {
+  page_size?: number;
+  page_token?: string;
+  status?: 'active' | 'expired';
+  created_from?: string;
+  created_to?: string;
+  domain?: string;
+  code_prefix?: string;
+  sort?: string;
 }
 
 export interface CreateShortUrlRequest {
   long_url: string;
   custom_code?: string;
   timeout?: number;
-  user_id: string;
+  dedupe?: boolean;
+}
+
+export interface UpdateShortUrlRequest {
+  long_url?: string;
+  short_code?: string;
+  timeout?: number;
+  expires_at?: string | null;
 }
 
 export interface ApiResponse<T> {
@@ -20,6 +45,23 @@ export interface ApiResponse<T> {
   data?: T;
 }
 
+export interface User {
+  id: number;
+  username: string;
+  created_at: string;
+}
+
+export interface Credentials {
+  username: string;
+  password: string;
+}
+
+export interface LoginResponse {
+  token: string;
+  expires_at: string;
+  user: User;
+}
+
 export interface QRCodeResponse {
   qr_code: string;
 }
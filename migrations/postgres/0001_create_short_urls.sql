@@ -0,0 +1,13 @@
+-- Short links, user_id refers to users.username
+-- Timestamps are RFC 3339 strings, same as the SQLite schema
+CREATE TABLE IF NOT EXISTS short_urls (
+    id BIGSERIAL PRIMARY KEY,
+    long_url TEXT NOT NULL,
+    short_code TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL,
+    expires_at TEXT,
+    user_id TEXT NOT NULL
+);
+
+-- Create index on user_id for faster user queries
+CREATE INDEX IF NOT EXISTS idx_user_id ON short_urls(user_id);
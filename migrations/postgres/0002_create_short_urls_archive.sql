@@ -0,0 +1,15 @@
+-- Links removed by the expiry reaper in archive mode, id is the original short_urls.id
+CREATE TABLE IF NOT EXISTS short_urls_archive (
+    id BIGINT PRIMARY KEY,
+    long_url TEXT NOT NULL,
+    short_code TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT,
+    user_id TEXT NOT NULL,
+    archived_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_short_urls_archive_user_id ON short_urls_archive(user_id);
+
+-- Create index on expires_at so the reaper does not scan the whole table
+CREATE INDEX IF NOT EXISTS idx_short_urls_expires_at ON short_urls(expires_at);
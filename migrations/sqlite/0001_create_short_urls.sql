@@ -0,0 +1,15 @@
+-- Short links, user_id refers to users.username
+CREATE TABLE IF NOT EXISTS short_urls (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    long_url TEXT NOT NULL,
+    short_code TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL,
+    expires_at TEXT,
+    user_id TEXT NOT NULL
+);
+
+-- Create index on short_code for faster lookups
+CREATE INDEX IF NOT EXISTS idx_short_code ON short_urls(short_code);
+
+-- Create index on user_id for faster user queries
+CREATE INDEX IF NOT EXISTS idx_user_id ON short_urls(user_id);
@@ -0,0 +1,13 @@
+-- Click log, one row per successful redirect
+CREATE TABLE IF NOT EXISTS clicks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    short_code TEXT NOT NULL,
+    clicked_at TEXT NOT NULL,
+    referrer TEXT,
+    user_agent TEXT,
+    ip TEXT,
+    accept_language TEXT
+);
+
+-- Create index on (short_code, clicked_at) for per-link time range queries
+CREATE INDEX IF NOT EXISTS idx_clicks_short_code ON clicks(short_code, clicked_at);
@@ -0,0 +1,2 @@
+-- Create index on (user_id, created_at) so listing a user's links by page does not sort the whole set
+CREATE INDEX IF NOT EXISTS idx_short_urls_user_id_created_at ON short_urls(user_id, created_at, id);
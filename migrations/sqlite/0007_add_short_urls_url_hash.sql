@@ -0,0 +1,5 @@
+-- SHA-256 of the canonical long_url, used to reuse a user's existing link for the same URL.
+-- Links created before this migration have no hash and are never reused
+ALTER TABLE short_urls ADD COLUMN url_hash TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_short_urls_user_id_url_hash ON short_urls(user_id, url_hash);
@@ -0,0 +1,5 @@
+-- Lowercase host of long_url, used by the domain filter when listing links.
+-- Links created before this migration are filled in by the backfill on startup
+ALTER TABLE short_urls ADD COLUMN host TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_short_urls_user_id_host ON short_urls(user_id, host);
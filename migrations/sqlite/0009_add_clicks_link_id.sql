@@ -0,0 +1,12 @@
+-- Id of the link a click belongs to, so clicks survive renaming the short code.
+-- Clicks recorded before this migration are matched by code here; clicks whose
+-- links live in another backend are filled in by the backfill on startup
+ALTER TABLE clicks ADD COLUMN link_id INTEGER;
+
+UPDATE clicks SET link_id = (
+    SELECT id FROM short_urls
+    WHERE short_urls.short_code = clicks.short_code
+      AND clicks.clicked_at >= short_urls.created_at
+);
+
+CREATE INDEX IF NOT EXISTS idx_clicks_link_id ON clicks(link_id, clicked_at);
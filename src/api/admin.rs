@@ -0,0 +1,256 @@
+use crate::api::auth::AuthUser;
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::pb::ApiResponse;
+use crate::services::allocator::CodeAllocator;
+use crate::services::reaper::ReaperService;
+use crate::services::url_policy::UrlPolicy;
+use crate::store::LinkStore;
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+
+// 管理接口只允许管理员的登录会话访问，API Token 的权限范围不包括管理操作
+
+// 立即执行一次过期链接清理，与后台任务使用相同的配置，后台任务关闭时也可以调用
+#[actix_web::post("/admin/reap")]
+pub async fn reap_expired_links(
+    store: web::Data<dyn LinkStore>,
+    config: web::Data<AppConfig>,
+    user: AuthUser,
+) -> Result<HttpResponse, AppError> {
+    user.require_session()?;
+    user.require_admin(&config)?;
+
+    let report = ReaperService::reap(store.get_ref(), &config.reaper, Utc::now()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(
+        "Expired links reaped successfully",
+        report,
+    )))
+}
+
+// 当前自动生成短码长度下的短码空间使用率和冲突率，在短码耗尽之前发现问题
+#[actix_web::get("/admin/keyspace")]
+pub async fn get_keyspace_stats(
+    store: web::Data<dyn LinkStore>,
+    allocator: web::Data<CodeAllocator>,
+    config: web::Data<AppConfig>,
+    user: AuthUser,
+) -> Result<HttpResponse, AppError> {
+    user.require_session()?;
+    user.require_admin(&config)?;
+
+    let stats = allocator.stats(store.get_ref(), &config.short_code).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(
+        "Keyspace stats retrieved successfully",
+        stats,
+    )))
+}
+
+// 修改 url_policy.blocklist_file 后重新加载，无需重启服务。读取失败时继续使用原来的列表
+#[actix_web::post("/admin/url-policy/reload")]
+pub async fn reload_url_policy(
+    url_policy: web::Data<UrlPolicy>,
+    config: web::Data<AppConfig>,
+    user: AuthUser,
+) -> Result<HttpResponse, AppError> {
+    user.require_session()?;
+    user.require_admin(&config)?;
+
+    let entries = url_policy
+        .reload()
+        .map_err(|e| AppError::Internal(format!("Failed to reload URL blocklist: {}", e)))?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(
+        "URL blocklist reloaded successfully",
+        serde_json::json!({ "entries": entries }),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::pb::abi::CreateApiTokenRequest;
+    use crate::services::api_tokens::ApiTokenService;
+    use crate::services::auth::AuthService;
+    use crate::store::{self, NewLink, SqliteLinkStore};
+    use actix_web::{http, test, App};
+    use sqlx::SqlitePool;
+
+    async fn login(pool: &SqlitePool, config: &AppConfig, username: &str) -> String {
+        AuthService::register(pool, config, username, "correct-horse")
+            .await
+            .expect("Failed to register");
+        AuthService::login(pool, config, username, "correct-horse")
+            .await
+            .expect("Failed to login")
+            .token
+    }
+
+    #[actix_web::test]
+    async fn test_reap_requires_admin() {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+        db::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        let mut config = AppConfig::default();
+        config.auth.admin_users = vec!["admin".to_string()];
+        let admin_token = login(&pool, &config, "admin").await;
+        let user_token = login(&pool, &config, "alice").await;
+
+        let link_store = SqliteLinkStore::new(pool.clone());
+        link_store
+            .insert(NewLink {
+                id: None,
+                long_url: "https://example.com".to_string(),
+                short_code: "stale".to_string(),
+                created_at: "2024-01-01T00:00:00+00:00".to_string(),
+                expires_at: Some("2024-01-02T00:00:00+00:00".to_string()),
+                user_id: "alice".to_string(),
+            })
+            .await
+            .expect("Failed to insert");
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(store::app_data(link_store))
+                .app_data(web::Data::new(CodeAllocator::new()))
+                .app_data(web::Data::new(config))
+                .service(reap_expired_links)
+                .service(get_keyspace_stats),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/admin/reap")
+            .insert_header((
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", user_token),
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        // An admin's API token cannot reach admin endpoints, whatever its scopes
+        let admin = AuthService::authenticate(&pool, &admin_token)
+            .await
+            .unwrap();
+        let api_token = ApiTokenService::create(
+            &pool,
+            admin.id,
+            CreateApiTokenRequest {
+                name: "read-only".to_string(),
+                scopes: vec!["links:read".to_string()],
+                expires_in: None,
+            },
+        )
+        .await
+        .unwrap()
+        .token;
+        let req = test::TestRequest::post()
+            .uri("/admin/reap")
+            .insert_header((http::header::AUTHORIZATION, format!("Bearer {}", api_token)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::post()
+            .uri("/admin/reap")
+            .insert_header((
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", admin_token),
+            ))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["success"], true);
+        assert_eq!(resp["data"]["mode"], "archive");
+        assert_eq!(resp["data"]["removed"], 1);
+        assert_eq!(resp["data"]["short_codes"][0], "stale");
+
+        let req = test::TestRequest::get()
+            .uri("/admin/keyspace")
+            .insert_header((
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", user_token),
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::get()
+            .uri("/admin/keyspace")
+            .insert_header((
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", admin_token),
+            ))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["data"]["length"], 6);
+        assert_eq!(resp["data"]["alphabet_size"], 62);
+        assert_eq!(resp["data"]["used"], 0);
+    }
+
+    #[actix_web::test]
+    async fn test_reload_url_policy() {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+        db::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        let path = std::env::temp_dir().join(format!("admin-blocklist-{}.txt", std::process::id()));
+        std::fs::write(
+            &path,
+            "evil.example
+",
+        )
+        .unwrap();
+
+        let mut config = AppConfig::default();
+        config.auth.admin_users = vec!["admin".to_string()];
+        config.url_policy.blocklist_file = Some(path.to_string_lossy().into_owned());
+        let admin_token = login(&pool, &config, "admin").await;
+        let user_token = login(&pool, &config, "alice").await;
+
+        let url_policy = web::Data::new(UrlPolicy::load(&config).unwrap());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(url_policy.clone())
+                .app_data(web::Data::new(config))
+                .service(reload_url_policy),
+        )
+        .await;
+        let reload = |token: &str| {
+            test::TestRequest::post()
+                .uri("/admin/url-policy/reload")
+                .insert_header((http::header::AUTHORIZATION, format!("Bearer {}", token)))
+                .to_request()
+        };
+
+        std::fs::write(
+            &path,
+            "evil.example
+*.phish.example
+",
+        )
+        .unwrap();
+        let resp = test::call_service(&app, reload(&user_token)).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        assert_eq!(url_policy.blocklist_len(), 1);
+
+        let resp: serde_json::Value =
+            test::call_and_read_body_json(&app, reload(&admin_token)).await;
+        assert_eq!(resp["data"]["entries"], 2);
+        assert!(url_policy.check("https://login.phish.example/").is_err());
+
+        std::fs::remove_file(&path).unwrap();
+        let resp = test::call_service(&app, reload(&admin_token)).await;
+        assert_eq!(resp.status(), http::StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(url_policy.blocklist_len(), 2);
+    }
+}
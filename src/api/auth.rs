@@ -0,0 +1,256 @@
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::pb::abi::Credentials;
+use crate::pb::ApiResponse;
+use crate::services::api_tokens::{ApiTokenService, Scope, API_TOKEN_PREFIX};
+use crate::services::auth::AuthService;
+use actix_web::{
+    dev::Payload, http::header, web, FromRequest, HttpMessage, HttpRequest, HttpResponse,
+};
+use sqlx::SqlitePool;
+use std::future::Future;
+use std::pin::Pin;
+
+// 已认证的用户，由 Authorization: Bearer <token> 解析得到
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub id: i64,
+    pub username: String,
+    // 登录会话拥有全部权限，API Token 只拥有创建时指定的权限
+    pub scopes: Vec<Scope>,
+    // 通过 API Token 认证时为 Token ID
+    pub token_id: Option<i64>,
+}
+
+impl AuthUser {
+    // 解析 Bearer Token，HTTP 和 gRPC 共用
+    pub async fn from_token(pool: &SqlitePool, token: &str) -> Result<Self, AppError> {
+        if token.starts_with(API_TOKEN_PREFIX) {
+            let identity = ApiTokenService::authenticate(pool, token)
+                .await?
+                .ok_or(AppError::Unauthorized("Invalid or expired token"))?;
+            return Ok(AuthUser {
+                id: identity.user.id,
+                username: identity.user.username,
+                scopes: identity.scopes,
+                token_id: Some(identity.token_id),
+            });
+        }
+
+        let user = AuthService::authenticate(pool, token).await?;
+        Ok(AuthUser {
+            id: user.id,
+            username: user.username,
+            scopes: Scope::ALL.to_vec(),
+            token_id: None,
+        })
+    }
+
+    pub fn require_scope(&self, scope: Scope) -> Result<(), AppError> {
+        if self.scopes.contains(&scope) {
+            Ok(())
+        } else {
+            Err(AppError::InsufficientScope(scope.as_str()))
+        }
+    }
+
+    // 用户名在配置 auth.admin_users 中
+    pub fn require_admin(&self, config: &AppConfig) -> Result<(), AppError> {
+        if config.is_admin(&self.username) {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden("Admin privileges required"))
+        }
+    }
+
+    // 只允许登录会话访问，例如管理 API Token 本身
+    pub fn require_session(&self) -> Result<(), AppError> {
+        match self.token_id {
+            None => Ok(()),
+            Some(_) => Err(AppError::Forbidden(
+                "This endpoint requires a login session",
+            )),
+        }
+    }
+}
+
+impl FromRequest for AuthUser {
+    type Error = AppError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        // 限流中间件已经认证过的请求不再重复查询
+        if let Some(user) = req.extensions().get::<AuthUser>().cloned() {
+            return Box::pin(async move { Ok(user) });
+        }
+
+        let token = bearer_token(req);
+        let pool = req.app_data::<web::Data<SqlitePool>>().cloned();
+
+        Box::pin(async move {
+            let token = token.ok_or(AppError::Unauthorized("Authentication required"))?;
+            let pool = pool.ok_or_else(|| AppError::Internal("Database pool missing".into()))?;
+
+            AuthUser::from_token(&pool, &token).await
+        })
+    }
+}
+
+pub fn bearer_token(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+}
+
+#[actix_web::post("/auth/register")]
+pub async fn register(
+    pool: web::Data<SqlitePool>,
+    config: web::Data<AppConfig>,
+    request: web::Json<Credentials>,
+) -> Result<HttpResponse, AppError> {
+    let user = AuthService::register(&pool, &config, &request.username, &request.password).await?;
+    Ok(HttpResponse::Created().json(ApiResponse::success("User registered successfully", user)))
+}
+
+#[actix_web::post("/auth/login")]
+pub async fn login(
+    pool: web::Data<SqlitePool>,
+    config: web::Data<AppConfig>,
+    request: web::Json<Credentials>,
+) -> Result<HttpResponse, AppError> {
+    let login = AuthService::login(&pool, &config, &request.username, &request.password).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Logged in successfully", login)))
+}
+
+#[actix_web::post("/auth/logout")]
+pub async fn logout(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    user: AuthUser,
+) -> Result<HttpResponse, AppError> {
+    user.require_session()?;
+    if let Some(token) = bearer_token(&req) {
+        AuthService::logout(&pool, &token).await?;
+    }
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Logged out successfully", ())))
+}
+
+#[actix_web::get("/auth/me")]
+pub async fn me(pool: web::Data<SqlitePool>, user: AuthUser) -> Result<HttpResponse, AppError> {
+    let user = AuthService::get_user(&pool, user.id).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("OK", user)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use actix_web::{http, test, App};
+    use serde_json::json;
+
+    async fn setup_test_db() -> SqlitePool {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+
+        db::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        pool
+    }
+
+    #[actix_web::test]
+    async fn test_register_login_me_logout() {
+        // Setup
+        let pool = setup_test_db().await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(AppConfig::default()))
+                .service(register)
+                .service(login)
+                .service(logout)
+                .service(me),
+        )
+        .await;
+
+        let credentials = json!({ "username": "alice", "password": "correct-horse" });
+
+        // Register
+        let req = test::TestRequest::post()
+            .uri("/auth/register")
+            .set_json(&credentials)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        // Login
+        let req = test::TestRequest::post()
+            .uri("/auth/login")
+            .set_json(&credentials)
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["data"]["user"]["username"], "alice");
+        let token = resp["data"]["token"].as_str().unwrap().to_string();
+
+        // Current user
+        let req = test::TestRequest::get()
+            .uri("/auth/me")
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["data"]["username"], "alice");
+
+        // Logout
+        let req = test::TestRequest::post()
+            .uri("/auth/logout")
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        // The token no longer works
+        let req = test::TestRequest::get()
+            .uri("/auth/me")
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn test_missing_or_invalid_token() {
+        // Setup
+        let pool = setup_test_db().await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .service(me),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/auth/me").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+
+        let body = test::read_body(resp).await;
+        let response: serde_json::Value =
+            serde_json::from_slice(&body).expect("Failed to parse response");
+        assert_eq!(response["error_code"], "UNAUTHORIZED");
+
+        let req = test::TestRequest::get()
+            .uri("/auth/me")
+            .insert_header((header::AUTHORIZATION, "Bearer not-a-real-token"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::api::auth::AuthUser;
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::pb::ApiResponse;
+use crate::store::LinkCache;
+use actix_web::{web, HttpResponse};
+
+// 重定向缓存的命中统计，反映全站的访问情况，仅限管理员的登录会话查看
+#[actix_web::get("/cache/stats")]
+pub async fn get_cache_stats(
+    cache: web::Data<LinkCache>,
+    config: web::Data<AppConfig>,
+    user: AuthUser,
+) -> Result<HttpResponse, AppError> {
+    user.require_session()?;
+    user.require_admin(&config)?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(
+        "Cache stats retrieved successfully",
+        cache.stats(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::redirect::redirect_to_long_url;
+    use crate::config::CacheConfig;
+    use crate::db;
+    use crate::pb::abi::CreateShortUrlRequest;
+    use crate::services::auth::AuthService;
+    use crate::services::click_log::ClickLog;
+    use crate::services::tests::TestCtx;
+    use crate::store::{CachedLinkStore, LinkStore, SqliteLinkStore};
+    use actix_web::{http, test, App};
+    use sqlx::SqlitePool;
+    use std::sync::Arc;
+
+    #[actix_web::test]
+    async fn test_redirects_are_cached() {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+        db::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        let mut config = AppConfig::default();
+        config.auth.admin_users = vec!["alice".to_string()];
+        let mut tokens = Vec::new();
+        for username in ["alice", "bob"] {
+            AuthService::register(&pool, &config, username, "correct-horse")
+                .await
+                .expect("Failed to register");
+            let login = AuthService::login(&pool, &config, username, "correct-horse")
+                .await
+                .expect("Failed to login");
+            tokens.push(login.token);
+        }
+
+        let cache = Arc::new(LinkCache::new(&CacheConfig::default()));
+        let store: Arc<dyn LinkStore> = Arc::new(CachedLinkStore::new(
+            Arc::new(SqliteLinkStore::new(pool.clone())),
+            cache.clone(),
+        ));
+        let request = CreateShortUrlRequest {
+            long_url: "https://example.com".to_string(),
+            custom_code: Some("cached".to_string()),
+            timeout: None,
+            user_id: "alice".to_string(),
+            dedupe: None,
+        };
+        TestCtx::new(config.clone())
+            .create(store.as_ref(), request)
+            .await
+            .map(|created| created.url)
+            .expect("Failed to create short URL");
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(ClickLog::spawn(
+                    pool.clone(),
+                    &AppConfig::default().clicks,
+                )))
+                .app_data(web::Data::from(store))
+                .app_data(web::Data::from(cache))
+                .app_data(web::Data::new(config))
+                .service(get_cache_stats)
+                .service(redirect_to_long_url),
+        )
+        .await;
+
+        for _ in 0..3 {
+            let req = test::TestRequest::get().uri("/cached").to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::FOUND);
+        }
+
+        // Requires authentication
+        let req = test::TestRequest::get().uri("/cache/stats").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+
+        // Requires an admin
+        let req = test::TestRequest::get()
+            .uri("/cache/stats")
+            .insert_header((http::header::AUTHORIZATION, format!("Bearer {}", tokens[1])))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::get()
+            .uri("/cache/stats")
+            .insert_header((http::header::AUTHORIZATION, format!("Bearer {}", tokens[0])))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["data"]["enabled"], true);
+        assert_eq!(resp["data"]["misses"], 1);
+        assert_eq!(resp["data"]["hits"], 2);
+    }
+}
@@ -1,3 +1,10 @@
+pub mod admin;
+pub mod auth;
+pub mod cache;
+pub mod negotiate;
 pub mod qrcode;
+pub mod rate_limit;
 pub mod redirect;
 pub mod shorten;
+pub mod tokens;
+pub mod transfer;
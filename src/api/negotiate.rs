@@ -0,0 +1,262 @@
+use crate::error::AppError;
+use crate::pb::{ApiResponse, IntoProtoData};
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, HeaderMap, HeaderValue},
+        StatusCode,
+    },
+    middleware::Next,
+    web, FromRequest, HttpRequest, HttpResponse,
+};
+use prost::Message;
+use serde::{de::DeserializeOwned, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+
+pub const PROTOBUF: &str = "application/x-protobuf";
+
+// 响应格式，由 Accept 请求头决定，默认 JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Protobuf,
+}
+
+impl Format {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let accepts_protobuf = headers
+            .get_all(header::ACCEPT)
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|range| {
+                let mut parts = range.split(';').map(str::trim);
+                let media_type = parts.next().unwrap_or_default();
+                // q=0 表示明确不接受
+                let rejected = parts.any(|p| matches!(p, "q=0" | "q=0.0" | "q=0.00" | "q=0.000"));
+                media_type.eq_ignore_ascii_case(PROTOBUF) && !rejected
+            });
+
+        if accepts_protobuf {
+            Format::Protobuf
+        } else {
+            Format::Json
+        }
+    }
+
+    pub fn respond<T>(self, status: StatusCode, message: &str, data: T) -> HttpResponse
+    where
+        T: Serialize + IntoProtoData,
+    {
+        let response = ApiResponse::success(message, data);
+        match self {
+            Format::Json => HttpResponse::build(status).json(response),
+            Format::Protobuf => HttpResponse::build(status)
+                .content_type(PROTOBUF)
+                .body(response.into_proto().encode_to_vec()),
+        }
+    }
+}
+
+impl FromRequest for Format {
+    type Error = AppError;
+    type Future = std::future::Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        std::future::ready(Ok(Format::from_headers(req.headers())))
+    }
+}
+
+// 请求体，Content-Type 为 application/x-protobuf 时按 Protobuf 解码，否则按 JSON 解析
+pub struct Body<T>(pub T);
+
+impl<T> Body<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> FromRequest for Body<T>
+where
+    T: Message + Default + DeserializeOwned + 'static,
+{
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let is_protobuf = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case(PROTOBUF));
+
+        if is_protobuf {
+            let bytes = web::Bytes::from_request(req, payload);
+            Box::pin(async move {
+                let bytes = bytes.await?;
+                T::decode(bytes).map(Body).map_err(|e| {
+                    AppError::InvalidRequest(format!("Invalid protobuf body: {}", e)).into()
+                })
+            })
+        } else {
+            let json = web::Json::<T>::from_request(req, payload);
+            Box::pin(async move { Ok(Body(json.await?.into_inner())) })
+        }
+    }
+}
+
+// 客户端要求 Protobuf 时，把 AppError 的 JSON 错误体替换为 Protobuf 编码的 ErrorResponse
+pub async fn encode_errors(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let format = Format::from_headers(req.headers());
+    let res = next.call(req).await?.map_into_boxed_body();
+
+    if format != Format::Protobuf {
+        return Ok(res);
+    }
+
+    let encoded = res
+        .response()
+        .error()
+        .and_then(|err| err.as_error::<AppError>())
+        .map(|err| err.to_error_response().encode_to_vec());
+
+    Ok(match encoded {
+        Some(bytes) => res.map_body(|head, _| {
+            head.headers_mut()
+                .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROTOBUF));
+            BoxBody::new(bytes)
+        }),
+        None => res,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pb::abi::{self, CreateShortUrlRequest};
+    use actix_web::{middleware::from_fn, test, App};
+
+    #[actix_web::test]
+    async fn test_format_from_accept() {
+        let format = |accept: &str| {
+            let req = test::TestRequest::default()
+                .insert_header((header::ACCEPT, accept))
+                .to_http_request();
+            Format::from_headers(req.headers())
+        };
+
+        assert_eq!(format("application/json"), Format::Json);
+        assert_eq!(format("*/*"), Format::Json);
+        assert_eq!(format(PROTOBUF), Format::Protobuf);
+        assert_eq!(
+            format("application/json;q=0.5, application/x-protobuf"),
+            Format::Protobuf
+        );
+        assert_eq!(format("application/x-protobuf;q=0"), Format::Json);
+
+        let req = test::TestRequest::default().to_http_request();
+        assert_eq!(Format::from_headers(req.headers()), Format::Json);
+    }
+
+    #[actix_web::post("/echo")]
+    async fn echo(
+        format: Format,
+        body: Body<CreateShortUrlRequest>,
+    ) -> Result<HttpResponse, AppError> {
+        let body = body.into_inner();
+        if body.long_url.is_empty() {
+            return Err(AppError::InvalidUrl);
+        }
+        let response = abi::ShortUrlResponse {
+            long_url: body.long_url,
+            short_code: body.custom_code.unwrap_or_default(),
+            ..Default::default()
+        };
+        Ok(format.respond(StatusCode::OK, "OK", response))
+    }
+
+    #[actix_web::test]
+    async fn test_protobuf_round_trip() {
+        let app = test::init_service(App::new().wrap(from_fn(encode_errors)).service(echo)).await;
+
+        let request = CreateShortUrlRequest {
+            long_url: "https://example.com".to_string(),
+            custom_code: Some("proto".to_string()),
+            ..Default::default()
+        };
+        let req = test::TestRequest::post()
+            .uri("/echo")
+            .insert_header((header::CONTENT_TYPE, PROTOBUF))
+            .insert_header((header::ACCEPT, PROTOBUF))
+            .set_payload(request.encode_to_vec())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), PROTOBUF);
+
+        let body = test::read_body(resp).await;
+        let response = abi::ApiResponse::decode(body).expect("Failed to decode response");
+        assert!(response.success);
+        match response.data {
+            Some(abi::api_response::Data::ShortUrl(url)) => {
+                assert_eq!(url.long_url, "https://example.com");
+                assert_eq!(url.short_code, "proto");
+            }
+            other => panic!("Unexpected data: {:?}", other),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_json_still_default() {
+        let app = test::init_service(App::new().wrap(from_fn(encode_errors)).service(echo)).await;
+
+        let req = test::TestRequest::post()
+            .uri("/echo")
+            .set_json(serde_json::json!({ "long_url": "https://example.com" }))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp["success"], true);
+        assert_eq!(resp["data"]["long_url"], "https://example.com");
+    }
+
+    #[actix_web::test]
+    async fn test_protobuf_errors() {
+        let app = test::init_service(App::new().wrap(from_fn(encode_errors)).service(echo)).await;
+
+        // Handler error
+        let req = test::TestRequest::post()
+            .uri("/echo")
+            .insert_header((header::CONTENT_TYPE, PROTOBUF))
+            .insert_header((header::ACCEPT, PROTOBUF))
+            .set_payload(CreateShortUrlRequest::default().encode_to_vec())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), PROTOBUF);
+
+        let body = test::read_body(resp).await;
+        let error = abi::ErrorResponse::decode(body).expect("Failed to decode error");
+        assert_eq!(error.error_code, "INVALID_URL");
+
+        // Undecodable body
+        let req = test::TestRequest::post()
+            .uri("/echo")
+            .insert_header((header::CONTENT_TYPE, PROTOBUF))
+            .insert_header((header::ACCEPT, PROTOBUF))
+            .set_payload(vec![0xff, 0xff, 0xff])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let body = test::read_body(resp).await;
+        let error = abi::ErrorResponse::decode(body).expect("Failed to decode error");
+        assert_eq!(error.error_code, "INVALID_REQUEST");
+    }
+}
@@ -1,30 +1,79 @@
-use actix_web::{error::ErrorInternalServerError, get, web, HttpResponse, Result};
-use sqlx::SqlitePool;
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::services::UrlService;
+use crate::store::LinkStore;
+use crate::utils::qr_renderer::{self, QrOptions};
+use actix_web::{get, http::header, web, HttpResponse};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct QrCodeQuery {
+    pub format: Option<String>,
+    pub size: Option<u32>,
+    pub margin: Option<u32>,
+    pub ec: Option<String>,
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+}
 
+impl QrCodeQuery {
+    fn to_options(&self) -> Result<QrOptions, String> {
+        let defaults = QrOptions::default();
+        let options = QrOptions {
+            format: match &self.format {
+                Some(format) => format.parse()?,
+                None => defaults.format,
+            },
+            size: self.size.unwrap_or(defaults.size),
+            margin: self.margin.unwrap_or(defaults.margin),
+            ec_level: match &self.ec {
+                Some(ec) => qr_renderer::parse_ec_level(ec)?,
+                None => defaults.ec_level,
+            },
+            foreground: match &self.fg {
+                Some(fg) => fg.parse()?,
+                None => defaults.foreground,
+            },
+            background: match &self.bg {
+                Some(bg) => bg.parse()?,
+                None => defaults.background,
+            },
+        };
+        options.validate()?;
+        Ok(options)
+    }
+}
+
+// 返回短链接对应的二维码图片（PNG / SVG）。与重定向一样无需认证：图片只包含公开的短链接地址，
+// 不返回目标地址等链接信息
 #[get("/qrcode/{short_code}")]
-pub async fn redirect_qrcode(
+pub async fn get_qrcode(
     short_code: web::Path<String>,
-    db: web::Data<SqlitePool>,
-) -> Result<HttpResponse> {
-    let code = short_code.as_ref();
-    // 查询长链接
-    let url = sqlx::query!("SELECT long_url FROM short_urls WHERE short_code = ?", code)
-        .fetch_optional(db.get_ref())
-        .await
-        .map_err(ErrorInternalServerError)?;
-
-    match url {
-        Some(url) => Ok(HttpResponse::Found()
-            .append_header(("Location", url.long_url))
-            .finish()),
-        None => Ok(HttpResponse::NotFound().finish()),
-    }
+    query: web::Query<QrCodeQuery>,
+    store: web::Data<dyn LinkStore>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, AppError> {
+    let code = short_code.into_inner();
+    let options = query.to_options().map_err(AppError::InvalidRequest)?;
+
+    // 只为有效的短链接生成二维码
+    UrlService::get_long_url(store.get_ref(), &code).await?;
+
+    let short_url = format!("{}/{}", config.base_url(), code);
+    let qr = qr_renderer::encode(&short_url, &options).map_err(AppError::InvalidRequest)?;
+    let image = qr_renderer::render_code(&qr, &options).map_err(AppError::Internal)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(options.format.content_type())
+        .append_header((header::CACHE_CONTROL, "public, max-age=3600"))
+        .body(image))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::db;
+    use crate::store::{self, SqliteLinkStore};
     use actix_web::{http, test, App};
     use chrono::Utc;
     use sqlx::SqlitePool;
@@ -58,7 +107,7 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn test_redirect_qrcode_success() {
+    async fn test_get_qrcode_png() {
         // Setup
         let pool = setup_test_db().await;
 
@@ -69,7 +118,9 @@ mod tests {
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(pool.clone()))
-                .service(redirect_qrcode),
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                .app_data(web::Data::new(AppConfig::default()))
+                .service(get_qrcode),
         )
         .await;
 
@@ -78,18 +129,80 @@ mod tests {
         let resp = test::call_service(&app, req).await;
 
         // Assert
-        assert_eq!(resp.status(), http::StatusCode::FOUND);
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(http::header::CONTENT_TYPE).unwrap(),
+            "image/png"
+        );
+
+        let body = test::read_body(resp).await;
+        assert_eq!(&body[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[actix_web::test]
+    async fn test_get_qrcode_svg_with_options() {
+        // Setup
+        let pool = setup_test_db().await;
+        insert_test_url(&pool, "qrtest", "https://example.com/qr").await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                .app_data(web::Data::new(AppConfig::default()))
+                .service(get_qrcode),
+        )
+        .await;
 
-        let location = resp
-            .headers()
-            .get(http::header::LOCATION)
-            .expect("No Location header");
+        // Send request
+        let req = test::TestRequest::get()
+            .uri("/qrcode/qrtest?format=svg&size=512&margin=1&ec=H&fg=%23336699&bg=ffffff00")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        // Assert
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(http::header::CONTENT_TYPE).unwrap(),
+            "image/svg+xml"
+        );
+
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains(r#"width="512""#));
+        assert!(body.contains(r##"fill="#336699""##));
+        assert!(body.contains(r##"fill="#ffffff00""##));
+    }
+
+    #[actix_web::test]
+    async fn test_get_qrcode_invalid_options() {
+        // Setup
+        let pool = setup_test_db().await;
+        insert_test_url(&pool, "qrtest", "https://example.com/qr").await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                .app_data(web::Data::new(AppConfig::default()))
+                .service(get_qrcode),
+        )
+        .await;
 
-        assert_eq!(location, "https://example.com/qr");
+        for uri in [
+            "/qrcode/qrtest?format=gif",
+            "/qrcode/qrtest?size=5000",
+            "/qrcode/qrtest?margin=99",
+            "/qrcode/qrtest?ec=Z",
+            "/qrcode/qrtest?fg=red",
+        ] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST, "{}", uri);
+        }
     }
 
     #[actix_web::test]
-    async fn test_redirect_qrcode_not_found() {
+    async fn test_get_qrcode_not_found() {
         // Setup
         let pool = setup_test_db().await;
 
@@ -97,7 +210,9 @@ mod tests {
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(pool.clone()))
-                .service(redirect_qrcode),
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                .app_data(web::Data::new(AppConfig::default()))
+                .service(get_qrcode),
         )
         .await;
 
@@ -0,0 +1,342 @@
+use crate::api::auth::{bearer_token, AuthUser};
+use crate::config::{LimitConfig, RateLimitConfig};
+use crate::error::AppError;
+use crate::utils::rate_limiter::{Decision, TokenBucketLimiter};
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        header::{HeaderName, HeaderValue},
+        Method,
+    },
+    middleware::Next,
+    web, HttpMessage, HttpRequest, ResponseError,
+};
+use sqlx::SqlitePool;
+use std::net::{IpAddr, SocketAddr};
+
+const LIMIT_HEADER: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+const REMAINING_HEADER: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+const RESET_HEADER: HeaderName = HeaderName::from_static("x-ratelimit-reset");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LimitClass {
+    Shorten,
+    Management,
+    Redirect,
+}
+
+impl LimitClass {
+    // 按路径分类，首页等其他请求不限流
+    fn of(method: &Method, path: &str) -> Option<Self> {
+        if (path == "/api/shorten" || path == "/api/shorten/batch") && method == Method::POST {
+            Some(LimitClass::Shorten)
+        } else if path.starts_with("/api/") {
+            Some(LimitClass::Management)
+        } else if method == Method::GET && path.len() > 1 && !path[1..].contains('/') {
+            Some(LimitClass::Redirect)
+        } else {
+            None
+        }
+    }
+}
+
+// 各类请求使用独立的令牌桶
+pub struct RateLimiters {
+    enabled: bool,
+    trust_forwarded_for: bool,
+    shorten: TokenBucketLimiter,
+    management: TokenBucketLimiter,
+    redirect: TokenBucketLimiter,
+}
+
+impl RateLimiters {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        let limiter = |limit: &LimitConfig| TokenBucketLimiter::new(limit.burst, limit.per_minute);
+        Self {
+            enabled: config.enabled,
+            trust_forwarded_for: config.trust_forwarded_for,
+            shorten: limiter(&config.shorten),
+            management: limiter(&config.management),
+            redirect: limiter(&config.redirect),
+        }
+    }
+
+    fn limiter(&self, class: LimitClass) -> &TokenBucketLimiter {
+        match class {
+            LimitClass::Shorten => &self.shorten,
+            LimitClass::Management => &self.management,
+            LimitClass::Redirect => &self.redirect,
+        }
+    }
+
+    fn client_ip(&self, req: &ServiceRequest) -> String {
+        client_ip(req.request(), self.trust_forwarded_for).unwrap_or_else(|| "unknown".to_string())
+    }
+}
+
+// 客户端 IP。只有 trust_forwarded_for 开启（部署在反向代理之后）时才采用 X-Forwarded-For，
+// 否则以连接的对端地址为准，避免被请求头伪造
+pub fn client_ip(req: &HttpRequest, trust_forwarded_for: bool) -> Option<String> {
+    let addr = if trust_forwarded_for {
+        req.connection_info()
+            .realip_remote_addr()
+            .map(str::to_string)
+    } else {
+        req.peer_addr().map(|addr| addr.ip().to_string())
+    }?;
+
+    // realip_remote_addr 可能带端口
+    match addr.parse::<SocketAddr>() {
+        Ok(addr) => Some(addr.ip().to_string()),
+        Err(_) => Some(
+            addr.parse::<IpAddr>()
+                .map(|ip| ip.to_string())
+                .unwrap_or(addr),
+        ),
+    }
+}
+
+// 所有请求先按客户端 IP 计数，超限时直接拒绝，不解析 Token。携带有效 Token 的请求再按用户计数，
+// 两个桶都有剩余时才放行，响应头报告剩余次数较少的一个
+pub async fn rate_limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let limiters = req.app_data::<web::Data<RateLimiters>>().cloned();
+    let Some(limiters) = limiters.filter(|limiters| limiters.enabled) else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    let Some(class) = LimitClass::of(req.method(), req.path()) else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+
+    let limiter = limiters.limiter(class);
+    let mut decision = limiter.check(&format!("ip:{}", limiters.client_ip(&req)));
+    // 重定向是匿名访问，不解析 Token
+    if decision.allowed && class != LimitClass::Redirect {
+        if let Some(user) = authenticate(&req).await {
+            let user_decision = limiter.check(&format!("user:{}", user.id));
+            if !user_decision.allowed || user_decision.remaining < decision.remaining {
+                decision = user_decision;
+            }
+            req.extensions_mut().insert(user);
+        }
+    }
+
+    let mut res = if decision.allowed {
+        next.call(req).await?.map_into_boxed_body()
+    } else {
+        let retry_after = decision.retry_after.as_secs_f64().ceil() as u64;
+        let response = AppError::RateLimited { retry_after }.error_response();
+        req.into_response(response)
+    };
+
+    insert_headers(&mut res, &decision);
+    Ok(res)
+}
+
+async fn authenticate(req: &ServiceRequest) -> Option<AuthUser> {
+    let token = bearer_token(req.request())?;
+    let pool = req.app_data::<web::Data<SqlitePool>>()?;
+    AuthUser::from_token(pool, &token).await.ok()
+}
+
+fn insert_headers(res: &mut ServiceResponse<BoxBody>, decision: &Decision) {
+    let headers = res.headers_mut();
+    let reset = decision.reset_after.as_secs_f64().ceil() as u64;
+    for (name, value) in [
+        (LIMIT_HEADER, decision.limit as u64),
+        (REMAINING_HEADER, decision.remaining as u64),
+        (RESET_HEADER, reset),
+    ] {
+        headers.insert(name, HeaderValue::from(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::db;
+    use crate::services::auth::AuthService;
+    use actix_web::{http, middleware::from_fn, test, App, HttpResponse};
+
+    fn limits(enabled: bool) -> RateLimitConfig {
+        RateLimitConfig {
+            enabled,
+            shorten: LimitConfig {
+                burst: 2,
+                per_minute: 1,
+            },
+            management: LimitConfig {
+                burst: 3,
+                per_minute: 1,
+            },
+            redirect: LimitConfig {
+                burst: 1,
+                per_minute: 1,
+            },
+            ..RateLimitConfig::default()
+        }
+    }
+
+    async fn setup() -> (SqlitePool, String) {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+        db::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        let config = AppConfig::default();
+        AuthService::register(&pool, &config, "alice", "correct-horse")
+            .await
+            .expect("Failed to register");
+        let token = AuthService::login(&pool, &config, "alice", "correct-horse")
+            .await
+            .expect("Failed to login")
+            .token;
+        (pool, token)
+    }
+
+    fn request(method: Method, uri: &str, ip: &str) -> test::TestRequest {
+        test::TestRequest::default()
+            .method(method)
+            .uri(uri)
+            .peer_addr(format!("{}:12345", ip).parse().unwrap())
+    }
+
+    fn header<B>(resp: &ServiceResponse<B>, name: impl AsRef<str>) -> &str {
+        resp.headers()
+            .get(name.as_ref())
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+    }
+
+    #[actix_web::test]
+    async fn test_limit_class() {
+        let class = |method: Method, path: &str| LimitClass::of(&method, path);
+        assert_eq!(
+            class(Method::POST, "/api/shorten"),
+            Some(LimitClass::Shorten)
+        );
+        assert_eq!(
+            class(Method::POST, "/api/shorten/batch"),
+            Some(LimitClass::Shorten)
+        );
+        assert_eq!(
+            class(Method::GET, "/api/shorten"),
+            Some(LimitClass::Management)
+        );
+        assert_eq!(
+            class(Method::DELETE, "/api/urls/1"),
+            Some(LimitClass::Management)
+        );
+        assert_eq!(class(Method::GET, "/abc123"), Some(LimitClass::Redirect));
+        assert_eq!(class(Method::GET, "/"), None);
+        assert_eq!(class(Method::GET, "/static/app.js"), None);
+    }
+
+    #[actix_web::test]
+    async fn test_rate_limit_middleware() {
+        let (pool, token) = setup().await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(RateLimiters::new(&limits(true))))
+                .wrap(from_fn(rate_limit))
+                .route("/api/shorten", web::post().to(HttpResponse::Ok))
+                .route("/api/urls", web::get().to(HttpResponse::Ok))
+                .route("/{code}", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        for remaining in ["1", "0"] {
+            let req = request(Method::POST, "/api/shorten", "10.0.0.1").to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::OK);
+            assert_eq!(header(&resp, "x-ratelimit-limit"), "2");
+            assert_eq!(header(&resp, "x-ratelimit-remaining"), remaining);
+        }
+
+        let req = request(Method::POST, "/api/shorten", "10.0.0.1").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&resp, "retry-after"), "60");
+        assert_eq!(header(&resp, "x-ratelimit-remaining"), "0");
+        assert_eq!(header(&resp, "x-ratelimit-reset"), "120");
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error_code"], "RATE_LIMITED");
+
+        // Other clients and other route classes have their own buckets
+        let req = request(Method::POST, "/api/shorten", "10.0.0.2").to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+
+        let req = request(Method::GET, "/api/urls", "10.0.0.1").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(header(&resp, "x-ratelimit-limit"), "3");
+
+        let req = request(Method::GET, "/abc123", "10.0.0.1").to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+        let req = request(Method::GET, "/other", "10.0.0.1").to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            http::StatusCode::TOO_MANY_REQUESTS
+        );
+
+        // A token does not lift the limit of its address
+        let req = request(Method::POST, "/api/shorten", "10.0.0.1")
+            .insert_header((http::header::AUTHORIZATION, format!("Bearer {}", token)))
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            http::StatusCode::TOO_MANY_REQUESTS
+        );
+
+        // The user is also keyed by account across addresses
+        for (ip, remaining) in [("10.0.0.3", "1"), ("10.0.0.4", "0")] {
+            let req = request(Method::POST, "/api/shorten", ip)
+                .insert_header((http::header::AUTHORIZATION, format!("Bearer {}", token)))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert!(resp.status().is_success());
+            assert_eq!(header(&resp, "x-ratelimit-remaining"), remaining);
+        }
+        let req = request(Method::POST, "/api/shorten", "10.0.0.5")
+            .insert_header((http::header::AUTHORIZATION, format!("Bearer {}", token)))
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            http::StatusCode::TOO_MANY_REQUESTS
+        );
+
+        // Requests with an invalid token only count against their address
+        let req = request(Method::POST, "/api/shorten", "10.0.0.6")
+            .insert_header((http::header::AUTHORIZATION, "Bearer garbage"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(header(&resp, "x-ratelimit-remaining"), "1");
+    }
+
+    #[actix_web::test]
+    async fn test_rate_limit_disabled() {
+        let (pool, _) = setup().await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .app_data(web::Data::new(RateLimiters::new(&limits(false))))
+                .wrap(from_fn(rate_limit))
+                .route("/{code}", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        for _ in 0..5 {
+            let req = request(Method::GET, "/abc123", "10.0.0.1").to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::OK);
+            assert!(!resp.headers().contains_key("x-ratelimit-limit"));
+        }
+    }
+}
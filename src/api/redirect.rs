@@ -1,20 +1,55 @@
+use crate::api::rate_limit::client_ip;
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::pb::abi::{Click, ShortUrl};
+use crate::services::click_log::ClickLog;
 use crate::services::UrlService;
-use actix_web::{web, HttpResponse, Result};
-use sqlx::SqlitePool;
+use crate::store::LinkStore;
+use crate::utils::ip_anonymizer::anonymize_ip;
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use chrono::Utc;
+
+// 单个请求头最多保留的字符数，避免超长请求头写入数据库
+const MAX_HEADER_LEN: usize = 512;
 
 #[actix_web::get("/{short_code}")]
 pub async fn redirect_to_long_url(
-    pool: web::Data<SqlitePool>,
+    req: HttpRequest,
+    store: web::Data<dyn LinkStore>,
+    click_log: web::Data<ClickLog>,
+    config: web::Data<AppConfig>,
     path: web::Path<String>,
-) -> Result<HttpResponse> {
-    let short_code = path.into_inner();
-
-    match UrlService::get_long_url(&pool, &short_code).await {
-        Ok(Some(long_url)) => Ok(HttpResponse::Found()
-            .append_header(("Location", long_url))
-            .finish()),
-        Ok(None) => Ok(HttpResponse::NotFound().body("Short URL not found or expired")),
-        Err(_) => Ok(HttpResponse::InternalServerError().body("Internal server error")),
+) -> Result<HttpResponse, AppError> {
+    let url = UrlService::resolve(store.get_ref(), &config.custom_code, &path.into_inner()).await?;
+
+    // 访问记录由后台任务写入，不等待写入完成，写入失败也不影响跳转
+    click_log.record(build_click(&req, &url, &config));
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", url.long_url))
+        .finish())
+}
+
+fn build_click(req: &HttpRequest, url: &ShortUrl, config: &AppConfig) -> Click {
+    let header_value = |name: header::HeaderName| {
+        req.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.chars().take(MAX_HEADER_LEN).collect::<String>())
+    };
+
+    Click {
+        id: 0,
+        short_code: url.short_code.clone(),
+        clicked_at: Utc::now().to_rfc3339(),
+        referrer: header_value(header::REFERER),
+        user_agent: header_value(header::USER_AGENT),
+        ip: client_ip(req, config.rate_limit.trust_forwarded_for)
+            .as_deref()
+            .and_then(anonymize_ip),
+        accept_language: header_value(header::ACCEPT_LANGUAGE),
+        link_id: url.id,
     }
 }
 
@@ -23,8 +58,10 @@ mod tests {
     use super::*;
     use crate::db;
     use crate::pb::abi::CreateShortUrlRequest;
-    use crate::services::UrlService;
+    use crate::services::tests::{test_ctx, TestCtx};
+    use crate::store::{self, SqliteLinkStore};
     use actix_web::{http, test, App};
+    use sqlx::SqlitePool;
 
     async fn setup_test_db() -> SqlitePool {
         let pool = SqlitePool::connect("sqlite::memory:")
@@ -38,6 +75,10 @@ mod tests {
         pool
     }
 
+    fn click_log(pool: &SqlitePool) -> ClickLog {
+        ClickLog::spawn(pool.clone(), &AppConfig::default().clicks)
+    }
+
     #[actix_web::test]
     async fn test_redirect_to_long_url_success() {
         // Setup
@@ -49,16 +90,21 @@ mod tests {
             custom_code: Some("testcode".to_string()),
             timeout: None,
             user_id: "test_user".to_string(),
+            dedupe: None,
         };
 
-        UrlService::create_short_url(&pool, request)
+        test_ctx()
+            .create(&SqliteLinkStore::new(pool.clone()), request)
             .await
+            .map(|created| created.url)
             .expect("Failed to create test URL");
 
         // Create test app with the route
         let app = test::init_service(
             App::new()
-                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(click_log(&pool)))
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                .app_data(web::Data::new(AppConfig::default()))
                 .service(redirect_to_long_url),
         )
         .await;
@@ -78,6 +124,72 @@ mod tests {
         assert_eq!(location, "https://example.com");
     }
 
+    #[actix_web::test]
+    async fn test_redirect_records_click() {
+        let pool = setup_test_db().await;
+
+        let request = CreateShortUrlRequest {
+            long_url: "https://example.com".to_string(),
+            custom_code: Some("clickme".to_string()),
+            timeout: None,
+            user_id: "test_user".to_string(),
+            dedupe: None,
+        };
+
+        let created = test_ctx()
+            .create(&SqliteLinkStore::new(pool.clone()), request)
+            .await
+            .map(|created| created.url)
+            .expect("Failed to create test URL");
+
+        let click_log = click_log(&pool);
+        let mut config = AppConfig::default();
+        for trust_forwarded_for in [false, true] {
+            config.rate_limit.trust_forwarded_for = trust_forwarded_for;
+            let app = test::init_service(
+                App::new()
+                    .app_data(web::Data::new(click_log.clone()))
+                    .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                    .app_data(web::Data::new(config.clone()))
+                    .service(redirect_to_long_url),
+            )
+            .await;
+
+            let req = test::TestRequest::get()
+                .uri("/clickme")
+                .insert_header((http::header::REFERER, "https://news.example.org/post"))
+                .insert_header((http::header::USER_AGENT, "Mozilla/5.0 (X11; Linux x86_64)"))
+                .insert_header((http::header::ACCEPT_LANGUAGE, "zh-CN,zh;q=0.9"))
+                .insert_header(("X-Forwarded-For", "198.51.100.9"))
+                .peer_addr("203.0.113.77:40000".parse().unwrap())
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::FOUND);
+        }
+
+        // Clicks are written in the background
+        click_log.flush().await;
+        let clicks = UrlService::get_clicks(&pool, "clickme")
+            .await
+            .expect("Failed to get clicks");
+        assert_eq!(clicks.len(), 2);
+
+        // X-Forwarded-For is only used when trusted
+        assert_eq!(clicks[1].ip.as_deref(), Some("203.0.113.0"));
+        assert_eq!(clicks[0].ip.as_deref(), Some("198.51.100.0"));
+
+        let click = &clicks[1];
+        assert_eq!(click.link_id, created.id);
+        assert_eq!(
+            click.referrer.as_deref(),
+            Some("https://news.example.org/post")
+        );
+        assert_eq!(
+            click.user_agent.as_deref(),
+            Some("Mozilla/5.0 (X11; Linux x86_64)")
+        );
+    }
+
     #[actix_web::test]
     async fn test_redirect_to_nonexistent_url() {
         // Setup
@@ -86,7 +198,9 @@ mod tests {
         // Create test app with the route
         let app = test::init_service(
             App::new()
-                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(click_log(&pool)))
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                .app_data(web::Data::new(AppConfig::default()))
                 .service(redirect_to_long_url),
         )
         .await;
@@ -98,4 +212,100 @@ mod tests {
         // Assert
         assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
     }
+
+    #[actix_web::test]
+    async fn test_redirect_to_expired_url() {
+        // Setup
+        let pool = setup_test_db().await;
+
+        sqlx::query(
+            r#"
+            INSERT INTO short_urls (long_url, short_code, created_at, expires_at, user_id)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind("https://example.com")
+        .bind("expired")
+        .bind(Utc::now().to_rfc3339())
+        .bind((Utc::now() - chrono::Duration::seconds(60)).to_rfc3339())
+        .bind("test_user")
+        .execute(&pool)
+        .await
+        .expect("Failed to insert expired URL");
+
+        let click_log = click_log(&pool);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(click_log.clone()))
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                .app_data(web::Data::new(AppConfig::default()))
+                .service(redirect_to_long_url),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/expired").to_request();
+        let resp = test::call_service(&app, req).await;
+
+        // Assert
+        assert_eq!(resp.status(), http::StatusCode::GONE);
+
+        let body = test::read_body(resp).await;
+        let response: serde_json::Value =
+            serde_json::from_slice(&body).expect("Failed to parse response");
+        assert_eq!(response["error_code"], "EXPIRED");
+
+        // Expired links are not counted as clicks
+        click_log.flush().await;
+        let clicks = UrlService::get_clicks(&pool, "expired")
+            .await
+            .expect("Failed to get clicks");
+        assert!(clicks.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_redirect_case_insensitive_codes() {
+        let pool = setup_test_db().await;
+        let mut config = AppConfig::default();
+        config.custom_code.case_insensitive = true;
+
+        let request = CreateShortUrlRequest {
+            long_url: "https://example.com".to_string(),
+            custom_code: Some("Promo".to_string()),
+            timeout: None,
+            user_id: "test_user".to_string(),
+            dedupe: None,
+        };
+        let created = TestCtx::new(config.clone())
+            .create(&SqliteLinkStore::new(pool.clone()), request)
+            .await
+            .expect("Failed to create test URL");
+        assert_eq!(created.url.short_code, "promo");
+
+        let click_log = click_log(&pool);
+        for (case_insensitive, status) in [
+            (true, http::StatusCode::FOUND),
+            (false, http::StatusCode::NOT_FOUND),
+        ] {
+            config.custom_code.case_insensitive = case_insensitive;
+            let app = test::init_service(
+                App::new()
+                    .app_data(web::Data::new(click_log.clone()))
+                    .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                    .app_data(web::Data::new(config.clone()))
+                    .service(redirect_to_long_url),
+            )
+            .await;
+
+            let req = test::TestRequest::get().uri("/PROMO").to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), status);
+        }
+
+        // The click is recorded under the stored code
+        click_log.flush().await;
+        let clicks = UrlService::get_clicks(&pool, "promo")
+            .await
+            .expect("Failed to get clicks");
+        assert_eq!(clicks.len(), 1);
+    }
 }
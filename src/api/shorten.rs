@@ -1,84 +1,224 @@
-use crate::pb::abi::{CreateShortUrlRequest, ShortUrlResponse};
-use crate::pb::ApiResponse;
+use crate::api::auth::AuthUser;
+use crate::api::negotiate::{Body, Format};
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::pb::abi::{
+    BatchCreateShortUrlsRequest, CreateShortUrlRequest, ListShortUrlsRequest, UpdateShortUrlRequest,
+};
+use crate::services::allocator::CodeAllocator;
+use crate::services::api_tokens::Scope;
+use crate::services::stats::{self, Granularity, StatsRange};
+use crate::services::url_policy::UrlPolicy;
 use crate::services::UrlService;
+use crate::store::LinkStore;
 use crate::utils::url_validator::{is_valid_url, normalize_url};
-use actix_web::{web, HttpResponse, Result};
+use actix_web::{http::StatusCode, web, HttpResponse};
+use serde::Deserialize;
 use sqlx::SqlitePool;
 
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    pub granularity: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub top: Option<usize>,
+}
+
+impl StatsQuery {
+    fn to_range(&self) -> Result<StatsRange, String> {
+        let granularity = match &self.granularity {
+            Some(value) => value.parse()?,
+            None => Granularity::Day,
+        };
+        let from = self
+            .from
+            .as_deref()
+            .map(stats::parse_timestamp)
+            .transpose()?;
+        let to = self.to.as_deref().map(stats::parse_timestamp).transpose()?;
+
+        StatsRange::new(granularity, from, to)
+    }
+}
+
 #[actix_web::post("/shorten")]
 pub async fn create_short_url(
-    pool: web::Data<SqlitePool>,
-    request: web::Json<CreateShortUrlRequest>,
-) -> Result<HttpResponse> {
+    store: web::Data<dyn LinkStore>,
+    allocator: web::Data<CodeAllocator>,
+    url_policy: web::Data<UrlPolicy>,
+    config: web::Data<AppConfig>,
+    user: AuthUser,
+    format: Format,
+    request: Body<CreateShortUrlRequest>,
+) -> Result<HttpResponse, AppError> {
+    user.require_scope(Scope::LinksWrite)?;
+
     let mut req = request.into_inner();
+    // 链接归属于当前登录用户
+    req.user_id = user.username;
 
     // 先验证URL是否有效，再规范化
     if !is_valid_url(&req.long_url) && !is_valid_url(&normalize_url(&req.long_url)) {
-        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("Invalid URL format")));
+        return Err(AppError::InvalidUrl);
     }
 
     req.long_url = normalize_url(&req.long_url);
 
-    match UrlService::create_short_url(&pool, req).await {
-        Ok(short_url) => {
-            let response = short_url.to_response("http://localhost:8080");
-            Ok(HttpResponse::Ok().json(ApiResponse::success(
-                "Short URL created successfully",
-                response,
-            )))
+    let created =
+        UrlService::create_short_url(store.get_ref(), &allocator, &url_policy, &config, req)
+            .await?;
+    let message = if created.reused {
+        "Existing short URL reused"
+    } else {
+        "Short URL created successfully"
+    };
+    let response = created.to_response(config.base_url());
+    Ok(format.respond(StatusCode::OK, message, response))
+}
+
+// 每个条目单独返回结果，无效的条目不会使整个请求失败
+#[actix_web::post("/shorten/batch")]
+pub async fn batch_create_short_urls(
+    store: web::Data<dyn LinkStore>,
+    allocator: web::Data<CodeAllocator>,
+    url_policy: web::Data<UrlPolicy>,
+    config: web::Data<AppConfig>,
+    user: AuthUser,
+    format: Format,
+    request: Body<BatchCreateShortUrlsRequest>,
+) -> Result<HttpResponse, AppError> {
+    user.require_scope(Scope::LinksWrite)?;
+
+    let mut req = request.into_inner();
+    // 规范化后仍无效的 URL 保持原样，由 UrlService 报告错误
+    for item in &mut req.items {
+        let normalized = normalize_url(&item.long_url);
+        if is_valid_url(&normalized) {
+            item.long_url = normalized;
         }
-        Err(err) => Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(&err))),
     }
+
+    let result = UrlService::batch_create_short_urls(
+        store.get_ref(),
+        &allocator,
+        &url_policy,
+        &config,
+        &user.username,
+        req,
+    )
+    .await?;
+    let message = if result.committed {
+        "Batch processed"
+    } else {
+        "Batch rolled back, no URLs were created"
+    };
+    let response = result.to_response(config.base_url());
+    Ok(format.respond(StatusCode::OK, message, response))
 }
 
-#[actix_web::get("/urls/{user_id}")]
+// 支持分页、过滤和排序，参数见 ListShortUrlsRequest
+#[actix_web::get("/urls")]
 pub async fn get_short_urls(
-    pool: web::Data<SqlitePool>,
-    path: web::Path<String>,
-) -> Result<HttpResponse> {
-    let user_id = path.into_inner();
-
-    match UrlService::get_user_urls(&pool, &user_id).await {
-        Ok(urls) => {
-            let responses: Vec<ShortUrlResponse> = urls
-                .into_iter()
-                .map(|url| url.to_response("http://localhost:8080"))
-                .collect();
-            Ok(HttpResponse::Ok().json(ApiResponse::success(
-                "URLs retrieved successfully",
-                responses,
-            )))
+    store: web::Data<dyn LinkStore>,
+    config: web::Data<AppConfig>,
+    user: AuthUser,
+    format: Format,
+    query: web::Query<ListShortUrlsRequest>,
+) -> Result<HttpResponse, AppError> {
+    user.require_scope(Scope::LinksRead)?;
+
+    let page = UrlService::list_user_urls(store.get_ref(), &user.username, &query).await?;
+    let response = page.to_response(config.base_url());
+    Ok(format.respond(StatusCode::OK, "URLs retrieved successfully", response))
+}
+
+// 未出现在请求体中的字段保持不变
+#[actix_web::patch("/urls/{id}")]
+pub async fn update_short_url(
+    store: web::Data<dyn LinkStore>,
+    url_policy: web::Data<UrlPolicy>,
+    config: web::Data<AppConfig>,
+    user: AuthUser,
+    format: Format,
+    path: web::Path<i64>,
+    request: Body<UpdateShortUrlRequest>,
+) -> Result<HttpResponse, AppError> {
+    user.require_scope(Scope::LinksWrite)?;
+
+    let id = path.into_inner();
+    let mut req = request.into_inner();
+    req.id = id;
+    req.user_id = user.username.clone();
+
+    if let Some(long_url) = &req.long_url {
+        if !is_valid_url(long_url) && !is_valid_url(&normalize_url(long_url)) {
+            return Err(AppError::InvalidUrl);
         }
-        Err(err) => Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&err))),
+        req.long_url = Some(normalize_url(long_url));
     }
+
+    let short_url = UrlService::update_short_url(
+        store.get_ref(),
+        &url_policy,
+        &config,
+        id,
+        &user.username,
+        req,
+    )
+    .await?;
+    let response = short_url.to_response(config.base_url());
+    Ok(format.respond(StatusCode::OK, "URL updated successfully", response))
 }
 
-#[actix_web::delete("/urls/{id}/{user_id}")]
+#[actix_web::delete("/urls/{id}")]
 pub async fn delete_short_url(
+    store: web::Data<dyn LinkStore>,
+    user: AuthUser,
+    format: Format,
+    path: web::Path<i64>,
+) -> Result<HttpResponse, AppError> {
+    user.require_scope(Scope::LinksDelete)?;
+
+    let id = path.into_inner();
+
+    UrlService::delete_short_url(store.get_ref(), id, &user.username).await?;
+    Ok(format.respond(StatusCode::OK, "URL deleted successfully", ()))
+}
+
+#[actix_web::get("/urls/{id}/stats")]
+pub async fn get_url_stats(
+    store: web::Data<dyn LinkStore>,
     pool: web::Data<SqlitePool>,
-    path: web::Path<(i64, String)>,
-) -> Result<HttpResponse> {
-    let (id, user_id) = path.into_inner();
-
-    match UrlService::delete_short_url(&pool, id, &user_id).await {
-        Ok(deleted) => {
-            if deleted {
-                Ok(HttpResponse::Ok().json(ApiResponse::success("URL deleted successfully", ())))
-            } else {
-                Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error(
-                    "URL not found or not owned by user",
-                )))
-            }
-        }
-        Err(err) => Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&err))),
+    user: AuthUser,
+    format: Format,
+    path: web::Path<i64>,
+    query: web::Query<StatsQuery>,
+) -> Result<HttpResponse, AppError> {
+    user.require_scope(Scope::StatsRead)?;
+
+    let id = path.into_inner();
+    let range = query.to_range().map_err(AppError::InvalidRequest)?;
+    let top = query.top.unwrap_or(stats::DEFAULT_TOP).min(stats::MAX_TOP);
+
+    // 只能查看自己链接的统计
+    let short_url = UrlService::get_short_url(store.get_ref(), id).await?;
+    if short_url.user_id != user.username {
+        return Err(AppError::Forbidden("URL is not owned by user"));
     }
+
+    let stats = UrlService::get_url_stats(&pool, &short_url, &range, top).await?;
+    Ok(format.respond(StatusCode::OK, "Stats retrieved successfully", stats))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::db;
+    use crate::pb::abi::CreateApiTokenRequest;
     use crate::pb::abi::ShortUrl;
+    use crate::services::api_tokens::ApiTokenService;
+    use crate::services::auth::AuthService;
+    use crate::store::{self, SqliteLinkStore};
     use actix_web::{http, test, App};
     use chrono::Utc;
     use serde_json::json;
@@ -96,6 +236,58 @@ mod tests {
         pool
     }
 
+    fn test_config() -> AppConfig {
+        let mut config = AppConfig::default();
+        config.server.public_base_url = "https://sho.rt".to_string();
+        config
+    }
+
+    // 注册用户并登录，返回 Bearer Token
+    async fn login_as(pool: &SqlitePool, username: &str) -> String {
+        let config = AppConfig::default();
+        AuthService::register(pool, &config, username, "correct-horse")
+            .await
+            .expect("Failed to register");
+        AuthService::login(pool, &config, username, "correct-horse")
+            .await
+            .expect("Failed to login")
+            .token
+    }
+
+    // 为已注册用户创建只包含指定权限的 API Token
+    async fn api_token_for(pool: &SqlitePool, username: &str, scopes: &[&str]) -> String {
+        let user_id: i64 = sqlx::query_scalar("SELECT id FROM users WHERE username = ?")
+            .bind(username)
+            .fetch_one(pool)
+            .await
+            .expect("User not found");
+        let request = CreateApiTokenRequest {
+            name: "test".to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            expires_in: None,
+        };
+        ApiTokenService::create(pool, user_id, request)
+            .await
+            .expect("Failed to create API token")
+            .token
+    }
+
+    // 注册创建和修改短链接的处理函数依赖的共享数据
+    fn app_data(pool: &SqlitePool, config: AppConfig) -> impl FnOnce(&mut web::ServiceConfig) {
+        let pool = pool.clone();
+        move |cfg| {
+            cfg.app_data(web::Data::new(pool.clone()))
+                .app_data(store::app_data(SqliteLinkStore::new(pool)))
+                .app_data(web::Data::new(UrlPolicy::new(&config)))
+                .app_data(web::Data::new(config))
+                .app_data(web::Data::new(CodeAllocator::new()));
+        }
+    }
+
+    fn bearer(token: &str) -> (http::header::HeaderName, String) {
+        (http::header::AUTHORIZATION, format!("Bearer {}", token))
+    }
+
     async fn insert_test_url(
         pool: &SqlitePool,
         id: i64,
@@ -105,8 +297,8 @@ mod tests {
     ) {
         sqlx::query(
             r#"
-            INSERT INTO short_urls (id, long_url, short_code, created_at, user_id)
-            VALUES (?, ?, ?, ?, ?)
+            INSERT INTO short_urls (id, long_url, short_code, created_at, user_id, host)
+            VALUES (?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(id)
@@ -114,6 +306,7 @@ mod tests {
         .bind(short_code)
         .bind(Utc::now().to_rfc3339())
         .bind(user_id)
+        .bind(crate::utils::url_validator::url_host(long_url))
         .execute(pool)
         .await
         .expect("Failed to insert test URL");
@@ -123,11 +316,12 @@ mod tests {
     async fn test_create_short_url_success() {
         // Setup
         let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
 
         // Create test app with the route
         let app = test::init_service(
             App::new()
-                .app_data(web::Data::new(pool.clone()))
+                .configure(app_data(&pool, AppConfig::default()))
                 .service(create_short_url),
         )
         .await;
@@ -135,9 +329,9 @@ mod tests {
         // Create request with test data
         let req = test::TestRequest::post()
             .uri("/shorten")
+            .insert_header(bearer(&token))
             .set_json(json!({
-                "long_url": "https://example.com",
-                "user_id": "test_user"
+                "long_url": "https://example.com"
             }))
             .to_request();
 
@@ -158,15 +352,115 @@ mod tests {
         assert_eq!(response["data"]["long_url"], "https://example.com");
     }
 
+    #[actix_web::test]
+    async fn test_create_short_url_dedupe() {
+        let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
+
+        let app = test::init_service(
+            App::new()
+                .configure(app_data(&pool, AppConfig::default()))
+                .service(create_short_url),
+        )
+        .await;
+
+        let shorten = |body: serde_json::Value| {
+            let req = test::TestRequest::post()
+                .uri("/shorten")
+                .insert_header(bearer(&token))
+                .set_json(body)
+                .to_request();
+            test::call_and_read_body_json::<_, _, serde_json::Value>(&app, req)
+        };
+
+        let first = shorten(json!({ "long_url": "example.com/dedupe" })).await;
+        assert_eq!(first["data"]["reused"], false);
+
+        // Off by default, opted in per request
+        let second = shorten(json!({ "long_url": "example.com/dedupe" })).await;
+        assert_ne!(second["data"]["short_code"], first["data"]["short_code"]);
+
+        let reused =
+            shorten(json!({ "long_url": "HTTP://Example.com/dedupe", "dedupe": true })).await;
+        assert_eq!(reused["message"], "Existing short URL reused");
+        assert_eq!(reused["data"]["reused"], true);
+        assert_eq!(reused["data"]["short_code"], first["data"]["short_code"]);
+    }
+
+    #[actix_web::test]
+    async fn test_create_short_url_ignores_user_id_in_body() {
+        // Setup
+        let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
+
+        let app = test::init_service(
+            App::new()
+                .configure(app_data(&pool, AppConfig::default()))
+                .service(create_short_url),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/shorten")
+            .insert_header(bearer(&token))
+            .set_json(json!({
+                "long_url": "https://example.com",
+                "custom_code": "mine",
+                "user_id": "someone_else"
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        // Assert
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let urls = UrlService::get_user_urls(&SqliteLinkStore::new(pool.clone()), "test_user")
+            .await
+            .unwrap();
+        assert_eq!(urls.len(), 1);
+        assert_eq!(urls[0].short_code, "mine");
+        let others = UrlService::get_user_urls(&SqliteLinkStore::new(pool.clone()), "someone_else")
+            .await
+            .unwrap();
+        assert!(others.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_create_short_url_requires_auth() {
+        // Setup
+        let pool = setup_test_db().await;
+
+        let app = test::init_service(
+            App::new()
+                .configure(app_data(&pool, AppConfig::default()))
+                .service(create_short_url),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/shorten")
+            .set_json(json!({ "long_url": "https://example.com" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        // Assert
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            resp.headers().get(http::header::WWW_AUTHENTICATE).unwrap(),
+            "Bearer"
+        );
+    }
+
     #[actix_web::test]
     async fn test_create_short_url_with_custom_code() {
         // Setup
         let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
 
         // Create test app with the route
         let app = test::init_service(
             App::new()
-                .app_data(web::Data::new(pool.clone()))
+                .configure(app_data(&pool, AppConfig::default()))
                 .service(create_short_url),
         )
         .await;
@@ -174,10 +468,10 @@ mod tests {
         // Create request with custom code
         let req = test::TestRequest::post()
             .uri("/shorten")
+            .insert_header(bearer(&token))
             .set_json(json!({
                 "long_url": "https://example.com",
-                "custom_code": "customcode",
-                "user_id": "test_user"
+                "custom_code": "customcode"
             }))
             .to_request();
 
@@ -195,15 +489,89 @@ mod tests {
         assert_eq!(response["data"]["short_code"], "customcode");
     }
 
+    #[actix_web::test]
+    async fn test_batch_create_short_urls() {
+        // Setup
+        let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
+        insert_test_url(&pool, 1, "taken", "https://example.com", "other_user").await;
+
+        let app = test::init_service(
+            App::new()
+                .configure(app_data(&pool, test_config()))
+                .service(batch_create_short_urls),
+        )
+        .await;
+
+        let batch = |mode: &str| {
+            let req = test::TestRequest::post()
+                .uri("/shorten/batch")
+                .insert_header(bearer(&token))
+                .set_json(json!({
+                    "mode": mode,
+                    "items": [
+                        { "long_url": "example.com/a", "custom_code": "batch-a" },
+                        { "long_url": "https://example.com/b", "custom_code": "taken" },
+                        { "long_url": "https://example.com/c" }
+                    ]
+                }))
+                .to_request();
+            test::call_service(&app, req)
+        };
+
+        let resp = batch("all_or_nothing").await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let response: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(response["data"]["committed"], false);
+        assert_eq!(response["data"]["created"], 0);
+        assert_eq!(response["data"]["results"][0]["status"], "aborted");
+        assert_eq!(response["data"]["results"][1]["error_code"], "CODE_TAKEN");
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM short_urls")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let resp = batch("best_effort").await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let response: serde_json::Value = test::read_body_json(resp).await;
+        let data = &response["data"];
+        assert_eq!(data["committed"], true);
+        assert_eq!(
+            (data["created"].as_u64(), data["failed"].as_u64()),
+            (Some(2), Some(1))
+        );
+        assert_eq!(data["results"][0]["status"], "created");
+        assert_eq!(
+            data["results"][0]["short_url"]["short_url"],
+            "https://sho.rt/batch-a"
+        );
+        assert_eq!(
+            data["results"][0]["short_url"]["long_url"],
+            "http://example.com/a"
+        );
+        assert!(data["results"][0]["error_code"].is_null());
+        assert_eq!(data["results"][1]["status"], "failed");
+        assert_eq!(data["results"][2]["index"], 2);
+
+        let owner: String =
+            sqlx::query_scalar("SELECT user_id FROM short_urls WHERE short_code = 'batch-a'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(owner, "test_user");
+    }
+
     #[actix_web::test]
     async fn test_create_short_url_invalid_url() {
         // Setup
         let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
 
         // Create test app with the route
         let app = test::init_service(
             App::new()
-                .app_data(web::Data::new(pool.clone()))
+                .configure(app_data(&pool, AppConfig::default()))
                 .service(create_short_url),
         )
         .await;
@@ -211,9 +579,9 @@ mod tests {
         // Create request with invalid URL - 使用一个确保无效的URL格式
         let req = test::TestRequest::post()
             .uri("/shorten")
+            .insert_header(bearer(&token))
             .set_json(json!({
-                "long_url": "://invalid-url",
-                "user_id": "test_user"
+                "long_url": "://invalid-url"
             }))
             .to_request();
 
@@ -222,12 +590,158 @@ mod tests {
 
         // Assert
         assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let body = test::read_body(resp).await;
+        let response: serde_json::Value =
+            serde_json::from_slice(&body).expect("Failed to parse response");
+        assert_eq!(response["success"], false);
+        assert_eq!(response["error_code"], "INVALID_URL");
+    }
+
+    #[actix_web::test]
+    async fn test_create_short_url_disallowed_url() {
+        let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
+
+        let app = test::init_service(
+            App::new()
+                .configure(app_data(&pool, AppConfig::default()))
+                .service(create_short_url),
+        )
+        .await;
+
+        for (long_url, message) in [
+            ("ftp://example.com/file", "URL scheme 'ftp' is not allowed"),
+            (
+                "192.168.0.1/admin",
+                "URLs pointing to private or local addresses are not allowed",
+            ),
+            (
+                "http://localhost:8080/abc123",
+                "URLs pointing to this service are not allowed",
+            ),
+        ] {
+            let req = test::TestRequest::post()
+                .uri("/shorten")
+                .insert_header(bearer(&token))
+                .set_json(json!({ "long_url": long_url }))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST, "{}", long_url);
+
+            let response: serde_json::Value = test::read_body_json(resp).await;
+            assert_eq!(response["error_code"], "URL_NOT_ALLOWED");
+            assert_eq!(response["message"], message);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_create_short_url_invalid_custom_code() {
+        let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
+
+        let app = test::init_service(
+            App::new()
+                .configure(app_data(&pool, AppConfig::default()))
+                .service(create_short_url),
+        )
+        .await;
+
+        for (custom_code, message) in [
+            ("qrcode", "custom_code 'qrcode' is reserved"),
+            (
+                "a b/c",
+                "custom_code may only contain letters, digits, '-' and '_'",
+            ),
+            ("", "custom_code must be between 3 and 32 characters"),
+        ] {
+            let req = test::TestRequest::post()
+                .uri("/shorten")
+                .insert_header(bearer(&token))
+                .set_json(json!({
+                    "long_url": "https://example.com",
+                    "custom_code": custom_code
+                }))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+            let response: serde_json::Value = test::read_body_json(resp).await;
+            assert_eq!(response["error_code"], "INVALID_FIELD");
+            assert_eq!(response["field"], "custom_code");
+            assert_eq!(response["message"], message);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_create_short_url_duplicate_code() {
+        // Setup
+        let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
+        insert_test_url(&pool, 1, "taken", "https://example.com", "test_user").await;
+
+        let app = test::init_service(
+            App::new()
+                .configure(app_data(&pool, AppConfig::default()))
+                .service(create_short_url),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/shorten")
+            .insert_header(bearer(&token))
+            .set_json(json!({
+                "long_url": "https://example.com/other",
+                "custom_code": "taken"
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        // Assert
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+
+        let body = test::read_body(resp).await;
+        let response: serde_json::Value =
+            serde_json::from_slice(&body).expect("Failed to parse response");
+        assert_eq!(response["error_code"], "CODE_TAKEN");
+    }
+
+    #[actix_web::test]
+    async fn test_create_short_url_malformed_body() {
+        // Setup
+        let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
+
+        let app = test::init_service(
+            App::new()
+                .configure(app_data(&pool, AppConfig::default()))
+                .app_data(crate::error::json_config())
+                .service(create_short_url),
+        )
+        .await;
+
+        // Missing long_url
+        let req = test::TestRequest::post()
+            .uri("/shorten")
+            .insert_header(bearer(&token))
+            .set_json(json!({ "custom_code": "abcd" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        // Assert
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let body = test::read_body(resp).await;
+        let response: serde_json::Value =
+            serde_json::from_slice(&body).expect("Failed to parse response");
+        assert_eq!(response["error_code"], "INVALID_REQUEST");
     }
 
     #[actix_web::test]
     async fn test_get_short_urls() {
         // Setup
         let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
 
         // Insert test URLs
         insert_test_url(&pool, 1, "code1", "https://example1.com", "test_user").await;
@@ -238,12 +752,17 @@ mod tests {
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(pool.clone()))
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                .app_data(web::Data::new(test_config()))
                 .service(get_short_urls),
         )
         .await;
 
         // Send request
-        let req = test::TestRequest::get().uri("/urls/test_user").to_request();
+        let req = test::TestRequest::get()
+            .uri("/urls")
+            .insert_header(bearer(&token))
+            .to_request();
         let resp = test::call_service(&app, req).await;
 
         // Assert
@@ -255,13 +774,267 @@ mod tests {
             serde_json::from_slice(&body).expect("Failed to parse response");
 
         assert_eq!(response["success"], true);
-        assert_eq!(response["data"].as_array().unwrap().len(), 2);
+        assert_eq!(response["data"]["urls"].as_array().unwrap().len(), 2);
+        assert_eq!(response["data"]["total"], 2);
+        assert!(response["data"]["next_page_token"].is_null());
+        assert!(response["data"]["urls"][0]["short_url"]
+            .as_str()
+            .unwrap()
+            .starts_with("https://sho.rt/"));
+    }
+
+    #[actix_web::test]
+    async fn test_get_short_urls_protobuf() {
+        use crate::api::negotiate::PROTOBUF;
+        use crate::pb::abi;
+        use prost::Message;
+
+        // Setup
+        let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
+        insert_test_url(&pool, 1, "code1", "https://example1.com", "test_user").await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                .app_data(web::Data::new(test_config()))
+                .service(get_short_urls),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/urls")
+            .insert_header(bearer(&token))
+            .insert_header((http::header::ACCEPT, PROTOBUF))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        // Assert
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(http::header::CONTENT_TYPE).unwrap(),
+            PROTOBUF
+        );
+
+        let body = test::read_body(resp).await;
+        let response = abi::ApiResponse::decode(body).expect("Failed to decode response");
+        match response.data {
+            Some(abi::api_response::Data::UserUrls(list)) => {
+                assert_eq!(list.urls.len(), 1);
+                assert_eq!(list.urls[0].short_url, "https://sho.rt/code1");
+            }
+            other => panic!("Unexpected data: {:?}", other),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_get_short_urls_pagination() {
+        // Setup
+        let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
+        insert_test_url(&pool, 1, "code1", "https://example.com/1", "test_user").await;
+        insert_test_url(&pool, 2, "code2", "https://example.com/2", "test_user").await;
+        insert_test_url(&pool, 3, "code3", "https://other.com/3", "test_user").await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                .app_data(web::Data::new(test_config()))
+                .service(get_short_urls),
+        )
+        .await;
+
+        let list = |uri: String| {
+            let req = test::TestRequest::get()
+                .uri(&uri)
+                .insert_header(bearer(&token))
+                .to_request();
+            test::call_service(&app, req)
+        };
+
+        // First page
+        let resp = list("/urls?page_size=2&sort=short_code&domain=example.com".to_string()).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let response: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(response["data"]["total"], 2);
+        let urls = response["data"]["urls"].as_array().unwrap();
+        assert_eq!(urls.len(), 2);
+        assert_eq!(urls[0]["short_code"], "code1");
+
+        // Walk the same listing one link at a time
+        let resp = list("/urls?page_size=1&sort=short_code&domain=example.com".to_string()).await;
+        let response: serde_json::Value = test::read_body_json(resp).await;
+        let token_value = response["data"]["next_page_token"].as_str().unwrap();
+
+        let resp = list(format!(
+            "/urls?page_size=1&sort=short_code&domain=example.com&page_token={}",
+            token_value
+        ))
+        .await;
+        let response: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(response["data"]["urls"][0]["short_code"], "code2");
+        assert!(response["data"]["next_page_token"].is_null());
+
+        // A token can't be reused with a different sort
+        let resp = list(format!("/urls?sort=-short_code&page_token={}", token_value)).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = list("/urls?status=unknown".to_string()).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_get_short_urls_requires_auth() {
+        // Setup
+        let pool = setup_test_db().await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                .app_data(web::Data::new(test_config()))
+                .service(get_short_urls),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/urls").to_request();
+        let resp = test::call_service(&app, req).await;
+
+        // Assert
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn test_get_url_stats() {
+        // Setup
+        let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
+
+        insert_test_url(&pool, 1, "statcode", "https://example.com", "test_user").await;
+        sqlx::query(
+            r#"
+            INSERT INTO clicks (link_id, short_code, clicked_at, referrer, user_agent)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(1)
+        .bind("statcode")
+        .bind(Utc::now().to_rfc3339())
+        .bind("https://twitter.com/some/post")
+        .bind("Mozilla/5.0 (iPhone; CPU iPhone OS 17_1 like Mac OS X) Mobile/15E148 Safari/604.1")
+        .execute(&pool)
+        .await
+        .expect("Failed to insert test click");
+
+        // Create test app with the route
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                .service(get_url_stats),
+        )
+        .await;
+
+        // Send request
+        let req = test::TestRequest::get()
+            .uri("/urls/1/stats?granularity=hour")
+            .insert_header(bearer(&token))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        // Assert
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let body = test::read_body(resp).await;
+        let response: serde_json::Value =
+            serde_json::from_slice(&body).expect("Failed to parse response");
+
+        assert_eq!(response["success"], true);
+        assert_eq!(response["data"]["total_clicks"], 1);
+        assert_eq!(response["data"]["granularity"], "hour");
+        assert!(response["data"]["series"].as_array().unwrap().len() >= 24);
+        assert_eq!(response["data"]["top_referrers"][0]["key"], "twitter.com");
+        assert_eq!(response["data"]["operating_systems"][0]["key"], "iOS");
+        assert_eq!(response["data"]["devices"][0]["key"], "Mobile");
+    }
+
+    #[actix_web::test]
+    async fn test_get_url_stats_invalid_query() {
+        // Setup
+        let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
+        insert_test_url(&pool, 1, "statcode", "https://example.com", "test_user").await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                .service(get_url_stats),
+        )
+        .await;
+
+        for uri in [
+            "/urls/1/stats?granularity=month",
+            "/urls/1/stats?from=2024-02-01&to=2024-01-01",
+            "/urls/1/stats?from=not-a-date",
+        ] {
+            let req = test::TestRequest::get()
+                .uri(uri)
+                .insert_header(bearer(&token))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST, "{}", uri);
+        }
+
+        // Unknown link
+        let req = test::TestRequest::get()
+            .uri("/urls/42/stats")
+            .insert_header(bearer(&token))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_get_url_stats_wrong_user() {
+        // Setup
+        let pool = setup_test_db().await;
+        let token = login_as(&pool, "wrong_user").await;
+        insert_test_url(&pool, 1, "statcode", "https://example.com", "owner_user").await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
+                .service(get_url_stats),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/urls/1/stats")
+            .insert_header(bearer(&token))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        // Assert
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        // Unknown links are reported before any stats are read
+        let req = test::TestRequest::get()
+            .uri("/urls/9999/stats")
+            .insert_header(bearer(&token))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
     }
 
     #[actix_web::test]
     async fn test_delete_short_url_success() {
         // Setup
         let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
 
         // Insert test URL
         insert_test_url(&pool, 1, "deleteme", "https://example.com", "test_user").await;
@@ -270,13 +1043,15 @@ mod tests {
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(pool.clone()))
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
                 .service(delete_short_url),
         )
         .await;
 
         // Send delete request
         let req = test::TestRequest::delete()
-            .uri("/urls/1/test_user")
+            .uri("/urls/1")
+            .insert_header(bearer(&token))
             .to_request();
         let resp = test::call_service(&app, req).await;
 
@@ -303,18 +1078,21 @@ mod tests {
     async fn test_delete_short_url_not_found() {
         // Setup
         let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
 
         // Create test app with the route
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(pool.clone()))
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
                 .service(delete_short_url),
         )
         .await;
 
         // Send delete request for non-existent URL
         let req = test::TestRequest::delete()
-            .uri("/urls/999/test_user")
+            .uri("/urls/999")
+            .insert_header(bearer(&token))
             .to_request();
         let resp = test::call_service(&app, req).await;
 
@@ -326,6 +1104,7 @@ mod tests {
     async fn test_delete_short_url_wrong_user() {
         // Setup
         let pool = setup_test_db().await;
+        let token = login_as(&pool, "wrong_user").await;
 
         // Insert test URL
         insert_test_url(&pool, 1, "usertest", "https://example.com", "owner_user").await;
@@ -334,17 +1113,164 @@ mod tests {
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(pool.clone()))
+                .app_data(store::app_data(SqliteLinkStore::new(pool.clone())))
                 .service(delete_short_url),
         )
         .await;
 
         // Send delete request with wrong user
         let req = test::TestRequest::delete()
-            .uri("/urls/1/wrong_user")
+            .uri("/urls/1")
+            .insert_header(bearer(&token))
             .to_request();
         let resp = test::call_service(&app, req).await;
 
         // Assert
-        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let body = test::read_body(resp).await;
+        let response: serde_json::Value =
+            serde_json::from_slice(&body).expect("Failed to parse response");
+        assert_eq!(response["error_code"], "FORBIDDEN");
+    }
+
+    #[actix_web::test]
+    async fn test_update_short_url() {
+        // Setup
+        let pool = setup_test_db().await;
+        let token = login_as(&pool, "test_user").await;
+        insert_test_url(&pool, 1, "typo", "https://exmaple.com", "test_user").await;
+        insert_test_url(&pool, 2, "taken", "https://example.com", "test_user").await;
+
+        let app = test::init_service(
+            App::new()
+                .configure(app_data(&pool, test_config()))
+                .service(update_short_url),
+        )
+        .await;
+
+        let patch = |body: serde_json::Value| {
+            let req = test::TestRequest::patch()
+                .uri("/urls/1")
+                .insert_header(bearer(&token))
+                .set_json(body)
+                .to_request();
+            test::call_service(&app, req)
+        };
+
+        let resp = patch(json!({
+            "long_url": "example.com/fixed",
+            "short_code": "fixed",
+            "timeout": 3600
+        }))
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let response: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(response["data"]["long_url"], "http://example.com/fixed");
+        assert_eq!(response["data"]["short_url"], "https://sho.rt/fixed");
+        assert!(response["data"]["expires_at"].is_string());
+
+        // null clears the expiry, other fields are left alone
+        let resp = patch(json!({ "expires_at": null })).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let response: serde_json::Value = test::read_body_json(resp).await;
+        assert!(response["data"]["expires_at"].is_null());
+        assert_eq!(response["data"]["short_code"], "fixed");
+
+        let resp = patch(json!({ "short_code": "taken" })).await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+
+        let resp = patch(json!({ "long_url": "not a url" })).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_update_short_url_wrong_user() {
+        // Setup
+        let pool = setup_test_db().await;
+        let token = login_as(&pool, "wrong_user").await;
+        insert_test_url(&pool, 1, "usertest", "https://example.com", "owner_user").await;
+
+        let app = test::init_service(
+            App::new()
+                .configure(app_data(&pool, test_config()))
+                .service(update_short_url),
+        )
+        .await;
+
+        let req = test::TestRequest::patch()
+            .uri("/urls/1")
+            .insert_header(bearer(&token))
+            .set_json(json!({ "long_url": "https://evil.example.com" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        // Assert
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        let long_url: String = sqlx::query_scalar("SELECT long_url FROM short_urls WHERE id = 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(long_url, "https://example.com");
+    }
+
+    #[actix_web::test]
+    async fn test_api_token_scopes_are_enforced() {
+        // Setup
+        let pool = setup_test_db().await;
+        login_as(&pool, "test_user").await;
+        let read_only = api_token_for(&pool, "test_user", &["links:read"]).await;
+        insert_test_url(&pool, 1, "scoped", "https://example.com", "test_user").await;
+
+        let app = test::init_service(
+            App::new()
+                .configure(app_data(&pool, AppConfig::default()))
+                .service(create_short_url)
+                .service(get_short_urls)
+                .service(get_url_stats)
+                .service(delete_short_url),
+        )
+        .await;
+
+        // links:read is granted
+        let req = test::TestRequest::get()
+            .uri("/urls")
+            .insert_header(bearer(&read_only))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        // Everything else is rejected
+        for req in [
+            test::TestRequest::post()
+                .uri("/shorten")
+                .set_json(json!({ "long_url": "https://example.com" })),
+            test::TestRequest::get().uri("/urls/1/stats"),
+            test::TestRequest::delete().uri("/urls/1"),
+        ] {
+            let resp =
+                test::call_service(&app, req.insert_header(bearer(&read_only)).to_request()).await;
+            assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+            let body = test::read_body(resp).await;
+            let response: serde_json::Value =
+                serde_json::from_slice(&body).expect("Failed to parse response");
+            assert_eq!(response["error_code"], "INSUFFICIENT_SCOPE");
+        }
+
+        // A token with links:write can create links for its owner
+        let writer = api_token_for(&pool, "test_user", &["links:write"]).await;
+        let req = test::TestRequest::post()
+            .uri("/shorten")
+            .insert_header(bearer(&writer))
+            .set_json(json!({ "long_url": "https://example.com/release" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let urls = UrlService::get_user_urls(&SqliteLinkStore::new(pool.clone()), "test_user")
+            .await
+            .unwrap();
+        assert_eq!(urls.len(), 2);
     }
 }
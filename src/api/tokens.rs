@@ -0,0 +1,179 @@
+use crate::api::auth::AuthUser;
+use crate::error::AppError;
+use crate::pb::abi::CreateApiTokenRequest;
+use crate::pb::ApiResponse;
+use crate::services::api_tokens::ApiTokenService;
+use actix_web::{web, HttpResponse};
+use sqlx::SqlitePool;
+
+// API Token 只能通过登录会话管理，避免 Token 自我续期或提权
+
+#[actix_web::post("/tokens")]
+pub async fn create_api_token(
+    pool: web::Data<SqlitePool>,
+    user: AuthUser,
+    request: web::Json<CreateApiTokenRequest>,
+) -> Result<HttpResponse, AppError> {
+    user.require_session()?;
+
+    let created = ApiTokenService::create(&pool, user.id, request.into_inner()).await?;
+    Ok(HttpResponse::Created().json(ApiResponse::success(
+        "API token created successfully",
+        created,
+    )))
+}
+
+#[actix_web::get("/tokens")]
+pub async fn get_api_tokens(
+    pool: web::Data<SqlitePool>,
+    user: AuthUser,
+) -> Result<HttpResponse, AppError> {
+    user.require_session()?;
+
+    let tokens = ApiTokenService::list(&pool, user.id).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(
+        "API tokens retrieved successfully",
+        tokens,
+    )))
+}
+
+#[actix_web::delete("/tokens/{id}")]
+pub async fn revoke_api_token(
+    pool: web::Data<SqlitePool>,
+    user: AuthUser,
+    path: web::Path<i64>,
+) -> Result<HttpResponse, AppError> {
+    user.require_session()?;
+
+    ApiTokenService::revoke(&pool, user.id, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("API token revoked successfully", ())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::db;
+    use crate::services::auth::AuthService;
+    use actix_web::{http, test, App};
+    use serde_json::json;
+
+    async fn setup_test_db() -> SqlitePool {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+
+        db::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        pool
+    }
+
+    async fn login_as(pool: &SqlitePool, username: &str) -> String {
+        let config = AppConfig::default();
+        AuthService::register(pool, &config, username, "correct-horse")
+            .await
+            .expect("Failed to register");
+        AuthService::login(pool, &config, username, "correct-horse")
+            .await
+            .expect("Failed to login")
+            .token
+    }
+
+    fn bearer(token: &str) -> (http::header::HeaderName, String) {
+        (http::header::AUTHORIZATION, format!("Bearer {}", token))
+    }
+
+    #[actix_web::test]
+    async fn test_create_list_revoke() {
+        // Setup
+        let pool = setup_test_db().await;
+        let session = login_as(&pool, "alice").await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .service(create_api_token)
+                .service(get_api_tokens)
+                .service(revoke_api_token),
+        )
+        .await;
+
+        // Create
+        let req = test::TestRequest::post()
+            .uri("/tokens")
+            .insert_header(bearer(&session))
+            .set_json(json!({ "name": "release-notes", "scopes": ["links:write"] }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let body = test::read_body(resp).await;
+        let response: serde_json::Value =
+            serde_json::from_slice(&body).expect("Failed to parse response");
+        let id = response["data"]["api_token"]["id"].as_i64().unwrap();
+        assert_eq!(
+            response["data"]["api_token"]["scopes"],
+            json!(["links:write"])
+        );
+        let api_token = response["data"]["token"].as_str().unwrap().to_string();
+
+        // List never returns the plain token
+        let req = test::TestRequest::get()
+            .uri("/tokens")
+            .insert_header(bearer(&session))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["data"].as_array().unwrap().len(), 1);
+        assert!(resp["data"][0].get("token").is_none());
+
+        // An API token cannot manage tokens
+        let req = test::TestRequest::get()
+            .uri("/tokens")
+            .insert_header(bearer(&api_token))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        // Revoke
+        let req = test::TestRequest::delete()
+            .uri(&format!("/tokens/{}", id))
+            .insert_header(bearer(&session))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        // The revoked token is rejected
+        let req = test::TestRequest::get()
+            .uri("/tokens")
+            .insert_header(bearer(&api_token))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn test_create_with_unknown_scope() {
+        // Setup
+        let pool = setup_test_db().await;
+        let session = login_as(&pool, "alice").await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .service(create_api_token),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/tokens")
+            .insert_header(bearer(&session))
+            .set_json(json!({ "name": "ci", "scopes": ["admin"] }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        // Assert
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+}
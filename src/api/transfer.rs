@@ -0,0 +1,273 @@
+use crate::api::auth::AuthUser;
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::pb::ApiResponse;
+use crate::services::api_tokens::Scope;
+use crate::services::transfer::{self, ConflictPolicy, TransferFormat, MAX_IMPORT_BYTES};
+use crate::services::url_policy::UrlPolicy;
+use crate::store::LinkStore;
+use actix_multipart::Multipart;
+use actix_web::{http::header, web, web::Bytes, HttpResponse};
+use chrono::Utc;
+use futures::{StreamExt, TryStreamExt};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub format: Option<String>,
+    #[serde(default)]
+    pub all: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    pub format: Option<String>,
+    pub on_conflict: Option<String>,
+}
+
+// 边读边写，不在内存中缓存全部链接。all=true 导出所有用户的链接，仅限管理员
+#[actix_web::get("/urls/export")]
+pub async fn export_links(
+    store: web::Data<dyn LinkStore>,
+    config: web::Data<AppConfig>,
+    user: AuthUser,
+    query: web::Query<ExportQuery>,
+) -> Result<HttpResponse, AppError> {
+    user.require_scope(Scope::LinksRead)?;
+
+    let format = match &query.format {
+        Some(value) => value.parse().map_err(AppError::InvalidRequest)?,
+        None => TransferFormat::Csv,
+    };
+    let owner = if query.all {
+        user.require_admin(&config)?;
+        None
+    } else {
+        Some(user.username.clone())
+    };
+
+    let body = transfer::export(store.into_inner(), owner, format).map_ok(Bytes::from);
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"short_urls.{}\"", format.extension()),
+        ))
+        .streaming(body))
+}
+
+// 文件通过 multipart 的 file 字段上传，未指定 format 时按文件扩展名判断
+#[actix_web::post("/urls/import")]
+pub async fn import_links(
+    store: web::Data<dyn LinkStore>,
+    url_policy: web::Data<UrlPolicy>,
+    config: web::Data<AppConfig>,
+    user: AuthUser,
+    query: web::Query<ImportQuery>,
+    mut payload: Multipart,
+) -> Result<HttpResponse, AppError> {
+    user.require_scope(Scope::LinksWrite)?;
+
+    let on_conflict: ConflictPolicy = match &query.on_conflict {
+        Some(value) => value.parse().map_err(AppError::InvalidRequest)?,
+        None => ConflictPolicy::default(),
+    };
+    let mut format = match &query.format {
+        Some(value) => Some(value.parse().map_err(AppError::InvalidRequest)?),
+        None => None,
+    };
+
+    let mut data = None;
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|e| AppError::InvalidRequest(e.to_string()))?;
+        if field.name() != Some("file") {
+            continue;
+        }
+        if format.is_none() {
+            format = field
+                .content_disposition()
+                .and_then(|cd| cd.get_filename())
+                .and_then(TransferFormat::from_filename);
+        }
+
+        let mut buf = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|e| AppError::InvalidRequest(e.to_string()))?;
+            if buf.len() + chunk.len() > MAX_IMPORT_BYTES {
+                return Err(AppError::InvalidRequest(format!(
+                    "File exceeds {} bytes",
+                    MAX_IMPORT_BYTES
+                )));
+            }
+            buf.extend_from_slice(&chunk);
+        }
+        data = Some(buf);
+    }
+
+    let data = data.ok_or_else(|| AppError::InvalidRequest("Missing file field".to_string()))?;
+    let format = format.ok_or_else(|| {
+        AppError::InvalidRequest("Unable to determine format, use ?format=csv|ndjson".to_string())
+    })?;
+
+    let report = transfer::import(
+        store.get_ref(),
+        &config,
+        &url_policy,
+        &user.username,
+        transfer::parse(format, &data),
+        on_conflict,
+        Utc::now(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("URLs imported", report)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::services::auth::AuthService;
+    use crate::store::{self, NewLink, SqliteLinkStore};
+    use actix_web::{http, test, App};
+    use sqlx::SqlitePool;
+
+    async fn login(pool: &SqlitePool, config: &AppConfig, username: &str) -> String {
+        AuthService::register(pool, config, username, "correct-horse")
+            .await
+            .expect("Failed to register");
+        AuthService::login(pool, config, username, "correct-horse")
+            .await
+            .expect("Failed to login")
+            .token
+    }
+
+    fn bearer(token: &str) -> (http::header::HeaderName, String) {
+        (http::header::AUTHORIZATION, format!("Bearer {}", token))
+    }
+
+    fn multipart(filename: &str, content: &str) -> (String, String) {
+        let boundary = "transfer-test-boundary";
+        let body = format!(
+            "--{boundary}\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"{filename}\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n\
+             {content}\r\n\
+             --{boundary}--\r\n"
+        );
+        (format!("multipart/form-data; boundary={}", boundary), body)
+    }
+
+    #[actix_web::test]
+    async fn test_export_and_import() {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+        db::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        let mut config = AppConfig::default();
+        config.auth.admin_users = vec!["admin".to_string()];
+        let admin_token = login(&pool, &config, "admin").await;
+        let alice_token = login(&pool, &config, "alice").await;
+
+        let link_store = SqliteLinkStore::new(pool.clone());
+        for (short_code, user_id) in [("alice-1", "alice"), ("bob-1", "bob")] {
+            link_store
+                .insert(NewLink {
+                    id: None,
+                    long_url: format!("https://example.com/{}", short_code),
+                    short_code: short_code.to_string(),
+                    created_at: "2024-01-01T00:00:00+00:00".to_string(),
+                    expires_at: None,
+                    user_id: user_id.to_string(),
+                })
+                .await
+                .expect("Failed to insert");
+        }
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(store::app_data(link_store))
+                .app_data(web::Data::new(UrlPolicy::new(&config)))
+                .app_data(web::Data::new(config))
+                .service(export_links)
+                .service(import_links),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/urls/export")
+            .insert_header(bearer(&alice_token))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "attachment; filename=\"short_urls.csv\""
+        );
+        let body = test::read_body(resp).await;
+        assert_eq!(
+            std::str::from_utf8(&body).unwrap(),
+            "short_code,long_url,created_at,expires_at,user_id\n\
+             alice-1,https://example.com/alice-1,2024-01-01T00:00:00+00:00,,alice\n"
+        );
+
+        // Only admins may export every user's links
+        let req = test::TestRequest::get()
+            .uri("/urls/export?all=true")
+            .insert_header(bearer(&alice_token))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::get()
+            .uri("/urls/export?all=true&format=ndjson")
+            .insert_header(bearer(&admin_token))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/x-ndjson"
+        );
+        let body = test::read_body(resp).await;
+        let lines: Vec<serde_json::Value> = std::str::from_utf8(&body)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["short_code"], "bob-1");
+
+        // Format is taken from the file extension
+        let (content_type, body) = multipart(
+            "links.ndjson",
+            "{\"short_code\":\"bob-1\",\"long_url\":\"https://example.com/mine\"}\n\
+             {\"short_code\":\"alice-2\",\"long_url\":\"https://example.com/2\"}",
+        );
+        let req = test::TestRequest::post()
+            .uri("/urls/import?on_conflict=overwrite")
+            .insert_header(bearer(&alice_token))
+            .insert_header((header::CONTENT_TYPE, content_type))
+            .set_payload(body)
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["success"], true);
+        assert_eq!(resp["data"]["created"], 1);
+        assert_eq!(resp["data"]["failed"], 1);
+        assert_eq!(resp["data"]["rows"][0]["status"], "failed");
+        assert_eq!(resp["data"]["rows"][0]["error_code"], "CODE_TAKEN");
+        assert_eq!(resp["data"]["rows"][1]["status"], "created");
+
+        let (content_type, body) = multipart("links.txt", "short_code,long_url\n");
+        let req = test::TestRequest::post()
+            .uri("/urls/import")
+            .insert_header(bearer(&alice_token))
+            .insert_header((header::CONTENT_TYPE, content_type))
+            .set_payload(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+}
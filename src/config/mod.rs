@@ -0,0 +1,970 @@
+use crate::db::Backend;
+use crate::utils::code_validator::{is_code_char, RESERVED_CODES};
+use crate::utils::short_code_generator::{
+    Base62Generator, CodeGenerator, RandomGenerator, SkipReserved, SqidsGenerator,
+    TimeOrderedGenerator, BASE62_ALPHABET,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+// 未通过 CONFIG_FILE 指定时默认读取的配置文件，不存在则全部使用默认值
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+pub const MIN_CODE_LENGTH: usize = 4;
+pub const MAX_CODE_LENGTH: usize = 32;
+pub const MIN_ALPHABET_LENGTH: usize = 10;
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub short_code: ShortCodeConfig,
+    pub custom_code: CustomCodeConfig,
+    pub url_policy: UrlPolicyConfig,
+    pub cors: CorsConfig,
+    pub auth: AuthConfig,
+    pub grpc: GrpcConfig,
+    pub cache: CacheConfig,
+    pub bloom_filter: BloomFilterConfig,
+    pub rate_limit: RateLimitConfig,
+    pub reaper: ReaperConfig,
+    pub clicks: ClickLogConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    // 对外访问的基础地址，用于拼接完整短链接
+    pub public_base_url: String,
+    pub bind_addr: String,
+    // 为空时使用 actix 默认值（CPU 核数）
+    pub workers: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    // 短链接存储，sqlite: 或 postgres://（需要启用 postgres feature）
+    pub url: String,
+    // url 为 PostgreSQL 时，账号、Token 和访问记录保存在该 SQLite 数据库中
+    pub sqlite_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShortCodeConfig {
+    // random 策略的短码长度，sqids 策略的最小长度
+    pub length: usize,
+    pub strategy: CodeStrategy,
+    // random 和 sqids 策略使用的字符集，只能包含字母、数字、"-" 和 "_"
+    pub alphabet: String,
+    // sqids 策略打乱字符集的密钥，不同部署使用不同的值
+    pub salt: String,
+    // 创建请求未指定 dedupe 时，是否复用该用户指向同一 URL 的有效链接
+    pub dedupe: bool,
+    // 自动生成的短码冲突率超过该值时长度加一，为 1 时不自动增长
+    pub growth_threshold: f64,
+}
+
+// 用户指定的短码（创建时的 custom_code、修改时的 short_code 和普通用户导入的短码）的校验规则
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CustomCodeConfig {
+    pub min_length: usize,
+    pub max_length: usize,
+    // 不能使用的短码，不区分大小写，默认为各顶层路由
+    pub reserved: Vec<String>,
+    // 为 true 时短码以小写保存，访问时不区分大小写
+    pub case_insensitive: bool,
+}
+
+// 创建、修改和导入链接时对目标 URL 的限制
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UrlPolicyConfig {
+    // 允许的协议，不区分大小写
+    pub allowed_schemes: Vec<String>,
+    // 拒绝指向私有、回环和链路本地地址的 IP 字面量
+    pub block_private_ips: bool,
+    // 屏蔽的主机名列表文件，每行一个，"*.example.com" 屏蔽该域名及其所有子域名。
+    // 修改后调用 POST /api/admin/url-policy/reload 重新加载
+    pub blocklist_file: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    // 为空或包含 "*" 时允许任意来源
+    pub allowed_origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    // 登录会话有效期（秒）
+    pub session_ttl_secs: i64,
+    pub min_password_length: usize,
+    // 管理员用户名，可以调用 /api/admin 下的接口
+    pub admin_users: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GrpcConfig {
+    // 与 HTTP 服务同时启动 gRPC 服务
+    pub enabled: bool,
+    pub bind_addr: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    // 重定向查询的进程内缓存
+    pub enabled: bool,
+    // 最多缓存的短码数量，超出后淘汰最久未访问的
+    pub capacity: usize,
+    pub ttl_secs: u64,
+    // 不存在的短码的缓存时间，应比 ttl_secs 短
+    pub negative_ttl_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BloomFilterConfig {
+    // 重定向前用布隆过滤器排除不存在的短码
+    pub enabled: bool,
+    // 预计的短码数量，启动时至少按现有数量的两倍分配
+    pub expected_items: usize,
+    pub false_positive_rate: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    // 部署在反向代理之后时按 X-Forwarded-For / Forwarded 识别客户端 IP，否则使用连接地址
+    pub trust_forwarded_for: bool,
+    // POST /api/shorten
+    pub shorten: LimitConfig,
+    // 其余 /api 接口
+    pub management: LimitConfig,
+    // GET /{short_code}
+    pub redirect: LimitConfig,
+}
+
+// 令牌桶参数：最多连续 burst 次请求，之后每分钟恢复 per_minute 次
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LimitConfig {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReaperConfig {
+    // 后台定期清理过期链接
+    pub enabled: bool,
+    pub interval_secs: u64,
+    // 过期超过该时长的链接才会被清理
+    pub grace_period_secs: u64,
+    pub mode: ReaperMode,
+    // 每个事务最多处理的链接数
+    pub batch_size: usize,
+}
+
+// 访问记录由后台任务批量写入
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClickLogConfig {
+    // 等待写入的访问记录数上限，队列满时丢弃新的记录
+    pub queue_size: usize,
+    // 每个事务最多写入的记录数
+    pub batch_size: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReaperMode {
+    // 移到 short_urls_archive 表
+    Archive,
+    // 直接删除
+    Delete,
+}
+
+// 自动生成短码的策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeStrategy {
+    // 从字符集中随机选取
+    #[default]
+    Random,
+    // 行 id 的 base62 编码
+    Base62,
+    // 按 salt 混淆的行 id
+    Sqids,
+    // 毫秒时间戳加随机后缀，按时间排序
+    Time,
+}
+
+impl FromStr for CodeStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(CodeStrategy::Random),
+            "base62" => Ok(CodeStrategy::Base62),
+            "sqids" => Ok(CodeStrategy::Sqids),
+            "time" => Ok(CodeStrategy::Time),
+            _ => Err(format!("Unknown short code strategy: {}", s)),
+        }
+    }
+}
+
+impl ShortCodeConfig {
+    // length 为当前的短码长度，见 CodeAllocator
+    pub fn generator(&self, length: usize) -> Box<dyn CodeGenerator> {
+        match self.strategy {
+            CodeStrategy::Random => Box::new(RandomGenerator::new(&self.alphabet, length)),
+            CodeStrategy::Base62 => Box::new(Base62Generator),
+            CodeStrategy::Sqids => {
+                Box::new(SqidsGenerator::new(&self.alphabet, &self.salt, length))
+            }
+            CodeStrategy::Time => Box::new(TimeOrderedGenerator),
+        }
+    }
+}
+
+impl ReaperMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReaperMode::Archive => "archive",
+            ReaperMode::Delete => "delete",
+        }
+    }
+}
+
+impl fmt::Display for ReaperMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ReaperMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "archive" => Ok(ReaperMode::Archive),
+            "delete" => Ok(ReaperMode::Delete),
+            _ => Err(format!("Unknown reaper mode: {}", s)),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            public_base_url: "http://localhost:8080".to_string(),
+            bind_addr: "0.0.0.0:8080".to_string(),
+            workers: None,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "sqlite:short_url.db".to_string(),
+            sqlite_url: "sqlite:short_url.db".to_string(),
+        }
+    }
+}
+
+impl Default for ShortCodeConfig {
+    fn default() -> Self {
+        Self {
+            length: 6,
+            strategy: CodeStrategy::default(),
+            alphabet: BASE62_ALPHABET.to_string(),
+            salt: String::new(),
+            dedupe: false,
+            growth_threshold: 0.05,
+        }
+    }
+}
+
+impl Default for CustomCodeConfig {
+    fn default() -> Self {
+        Self {
+            min_length: 3,
+            max_length: MAX_CODE_LENGTH,
+            reserved: RESERVED_CODES.iter().map(|word| word.to_string()).collect(),
+            case_insensitive: false,
+        }
+    }
+}
+
+impl Default for UrlPolicyConfig {
+    fn default() -> Self {
+        Self {
+            allowed_schemes: vec!["http".to_string(), "https".to_string()],
+            block_private_ips: true,
+            blocklist_file: None,
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["*".to_string()],
+        }
+    }
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            session_ttl_secs: 7 * 24 * 3600,
+            min_password_length: 8,
+            admin_users: Vec::new(),
+        }
+    }
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            bind_addr: "0.0.0.0:50051".to_string(),
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            capacity: 10_000,
+            ttl_secs: 300,
+            negative_ttl_secs: 30,
+        }
+    }
+}
+
+impl Default for BloomFilterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            expected_items: 1_000_000,
+            false_positive_rate: 0.01,
+        }
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            trust_forwarded_for: false,
+            shorten: LimitConfig {
+                burst: 10,
+                per_minute: 10,
+            },
+            management: LimitConfig {
+                burst: 60,
+                per_minute: 120,
+            },
+            redirect: LimitConfig {
+                burst: 100,
+                per_minute: 600,
+            },
+        }
+    }
+}
+
+impl Default for ReaperConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 3600,
+            grace_period_secs: 7 * 24 * 3600,
+            mode: ReaperMode::Archive,
+            batch_size: 500,
+        }
+    }
+}
+
+impl Default for ClickLogConfig {
+    fn default() -> Self {
+        Self {
+            queue_size: 10000,
+            batch_size: 500,
+        }
+    }
+}
+
+impl AppConfig {
+    // 加载顺序：默认值 -> 配置文件 -> 环境变量
+    pub fn load() -> Result<Self, String> {
+        let path = std::env::var("CONFIG_FILE").ok();
+        let mut config = match &path {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::from_file(DEFAULT_CONFIG_FILE)?
+            }
+            None => Self::default(),
+        };
+
+        config.apply_env(|key| std::env::var(key).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+        Self::from_toml(&content)
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self, String> {
+        toml::from_str(content).map_err(|e| format!("Invalid config: {}", e))
+    }
+
+    // 环境变量覆盖，env 参数便于测试时注入
+    pub fn apply_env(&mut self, env: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+        if let Some(value) = env("SHORT_URL_PUBLIC_BASE_URL") {
+            self.server.public_base_url = value;
+        }
+        if let Some(value) = env("SHORT_URL_BIND_ADDR") {
+            self.server.bind_addr = value;
+        }
+        if let Some(value) = env("SHORT_URL_WORKERS") {
+            self.server.workers = Some(parse_env("SHORT_URL_WORKERS", &value)?);
+        }
+        if let Some(value) = env("DATABASE_URL") {
+            self.database.url = value;
+        }
+        if let Some(value) = env("SHORT_URL_SQLITE_URL") {
+            self.database.sqlite_url = value;
+        }
+        if let Some(value) = env("SHORT_URL_CODE_LENGTH") {
+            self.short_code.length = parse_env("SHORT_URL_CODE_LENGTH", &value)?;
+        }
+        if let Some(value) = env("SHORT_URL_CODE_STRATEGY") {
+            self.short_code.strategy = parse_env("SHORT_URL_CODE_STRATEGY", &value)?;
+        }
+        if let Some(value) = env("SHORT_URL_CODE_ALPHABET") {
+            self.short_code.alphabet = value;
+        }
+        if let Some(value) = env("SHORT_URL_CODE_SALT") {
+            self.short_code.salt = value;
+        }
+        if let Some(value) = env("SHORT_URL_DEDUPE") {
+            self.short_code.dedupe = parse_env("SHORT_URL_DEDUPE", &value)?;
+        }
+        if let Some(value) = env("SHORT_URL_CODE_GROWTH_THRESHOLD") {
+            self.short_code.growth_threshold =
+                parse_env("SHORT_URL_CODE_GROWTH_THRESHOLD", &value)?;
+        }
+        if let Some(value) = env("SHORT_URL_CUSTOM_CODE_RESERVED") {
+            self.custom_code.reserved = split_list(&value);
+        }
+        if let Some(value) = env("SHORT_URL_CUSTOM_CODE_CASE_INSENSITIVE") {
+            self.custom_code.case_insensitive =
+                parse_env("SHORT_URL_CUSTOM_CODE_CASE_INSENSITIVE", &value)?;
+        }
+        if let Some(value) = env("SHORT_URL_ALLOWED_SCHEMES") {
+            self.url_policy.allowed_schemes = split_list(&value);
+        }
+        if let Some(value) = env("SHORT_URL_BLOCK_PRIVATE_IPS") {
+            self.url_policy.block_private_ips = parse_env("SHORT_URL_BLOCK_PRIVATE_IPS", &value)?;
+        }
+        if let Some(value) = env("SHORT_URL_BLOCKLIST_FILE") {
+            self.url_policy.blocklist_file = Some(value).filter(|path| !path.is_empty());
+        }
+        if let Some(value) = env("SHORT_URL_CORS_ORIGINS") {
+            self.cors.allowed_origins = split_list(&value);
+        }
+        if let Some(value) = env("SHORT_URL_SESSION_TTL_SECS") {
+            self.auth.session_ttl_secs = parse_env("SHORT_URL_SESSION_TTL_SECS", &value)?;
+        }
+        if let Some(value) = env("SHORT_URL_ADMIN_USERS") {
+            self.auth.admin_users = split_list(&value);
+        }
+        if let Some(value) = env("SHORT_URL_GRPC_ENABLED") {
+            self.grpc.enabled = parse_env("SHORT_URL_GRPC_ENABLED", &value)?;
+        }
+        if let Some(value) = env("SHORT_URL_GRPC_BIND_ADDR") {
+            self.grpc.bind_addr = value;
+        }
+        if let Some(value) = env("SHORT_URL_CACHE_ENABLED") {
+            self.cache.enabled = parse_env("SHORT_URL_CACHE_ENABLED", &value)?;
+        }
+        if let Some(value) = env("SHORT_URL_CACHE_CAPACITY") {
+            self.cache.capacity = parse_env("SHORT_URL_CACHE_CAPACITY", &value)?;
+        }
+        if let Some(value) = env("SHORT_URL_CACHE_TTL_SECS") {
+            self.cache.ttl_secs = parse_env("SHORT_URL_CACHE_TTL_SECS", &value)?;
+        }
+        if let Some(value) = env("SHORT_URL_RATE_LIMIT_ENABLED") {
+            self.rate_limit.enabled = parse_env("SHORT_URL_RATE_LIMIT_ENABLED", &value)?;
+        }
+        if let Some(value) = env("SHORT_URL_TRUST_FORWARDED_FOR") {
+            self.rate_limit.trust_forwarded_for =
+                parse_env("SHORT_URL_TRUST_FORWARDED_FOR", &value)?;
+        }
+        if let Some(value) = env("SHORT_URL_BLOOM_FILTER_ENABLED") {
+            self.bloom_filter.enabled = parse_env("SHORT_URL_BLOOM_FILTER_ENABLED", &value)?;
+        }
+        if let Some(value) = env("SHORT_URL_REAPER_ENABLED") {
+            self.reaper.enabled = parse_env("SHORT_URL_REAPER_ENABLED", &value)?;
+        }
+        if let Some(value) = env("SHORT_URL_REAPER_MODE") {
+            self.reaper.mode = parse_env("SHORT_URL_REAPER_MODE", &value)?;
+        }
+        Ok(())
+    }
+
+    pub fn validate(&mut self) -> Result<(), String> {
+        let base_url = self
+            .server
+            .public_base_url
+            .trim_end_matches('/')
+            .to_string();
+        match url::Url::parse(&base_url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") && url.host().is_some() => {}
+            _ => {
+                return Err(format!(
+                    "Invalid server.public_base_url: {}",
+                    self.server.public_base_url
+                ))
+            }
+        }
+        self.server.public_base_url = base_url;
+
+        if self.server.workers == Some(0) {
+            return Err("server.workers must be at least 1".to_string());
+        }
+
+        match Backend::from_url(&self.database.url) {
+            Some(Backend::Sqlite) => {}
+            Some(Backend::Postgres) if cfg!(feature = "postgres") => {}
+            Some(Backend::Postgres) => {
+                return Err(
+                    "database.url is PostgreSQL but the `postgres` feature is not enabled"
+                        .to_string(),
+                )
+            }
+            None => return Err(format!("Unsupported database.url: {}", self.database.url)),
+        }
+        if Backend::from_url(self.sqlite_url()) != Some(Backend::Sqlite) {
+            return Err(format!(
+                "Invalid database.sqlite_url: {}",
+                self.database.sqlite_url
+            ));
+        }
+
+        if !(MIN_CODE_LENGTH..=MAX_CODE_LENGTH).contains(&self.short_code.length) {
+            return Err(format!(
+                "short_code.length must be between {} and {}",
+                MIN_CODE_LENGTH, MAX_CODE_LENGTH
+            ));
+        }
+        let alphabet = &self.short_code.alphabet;
+        if alphabet.chars().count() < MIN_ALPHABET_LENGTH || !alphabet.chars().all(is_code_char) {
+            return Err(format!(
+                "short_code.alphabet must contain at least {} letters, digits, '-' or '_'",
+                MIN_ALPHABET_LENGTH
+            ));
+        }
+        let mut chars: Vec<char> = alphabet.chars().collect();
+        chars.sort_unstable();
+        chars.dedup();
+        if chars.len() != alphabet.len() {
+            return Err("short_code.alphabet must not contain duplicate characters".to_string());
+        }
+        if !(self.short_code.growth_threshold > 0.0 && self.short_code.growth_threshold <= 1.0) {
+            return Err("short_code.growth_threshold must be in (0, 1]".to_string());
+        }
+        let custom_code = &self.custom_code;
+        if custom_code.min_length == 0
+            || custom_code.min_length > custom_code.max_length
+            || custom_code.max_length > MAX_CODE_LENGTH
+        {
+            return Err(format!(
+                "custom_code.min_length and max_length must satisfy 1 <= min_length <= max_length <= {}",
+                MAX_CODE_LENGTH
+            ));
+        }
+
+        if self.url_policy.allowed_schemes.is_empty() {
+            return Err("url_policy.allowed_schemes must not be empty".to_string());
+        }
+        for scheme in &mut self.url_policy.allowed_schemes {
+            scheme.make_ascii_lowercase();
+        }
+
+        if self.auth.session_ttl_secs <= 0 {
+            return Err("auth.session_ttl_secs must be positive".to_string());
+        }
+
+        if self.grpc.enabled && self.grpc.bind_addr.parse::<std::net::SocketAddr>().is_err() {
+            return Err(format!("Invalid grpc.bind_addr: {}", self.grpc.bind_addr));
+        }
+
+        if self.cache.enabled && self.cache.capacity == 0 {
+            return Err("cache.capacity must be at least 1".to_string());
+        }
+
+        let bloom = &self.bloom_filter;
+        if bloom.enabled && bloom.expected_items == 0 {
+            return Err("bloom_filter.expected_items must be at least 1".to_string());
+        }
+        if bloom.enabled && !(bloom.false_positive_rate > 0.0 && bloom.false_positive_rate < 1.0) {
+            return Err("bloom_filter.false_positive_rate must be between 0 and 1".to_string());
+        }
+
+        for (name, limit) in [
+            ("shorten", &self.rate_limit.shorten),
+            ("management", &self.rate_limit.management),
+            ("redirect", &self.rate_limit.redirect),
+        ] {
+            if self.rate_limit.enabled && (limit.burst == 0 || limit.per_minute == 0) {
+                return Err(format!(
+                    "rate_limit.{}.burst and per_minute must be at least 1",
+                    name
+                ));
+            }
+        }
+
+        if self.reaper.enabled && self.reaper.interval_secs == 0 {
+            return Err("reaper.interval_secs must be at least 1".to_string());
+        }
+        if self.reaper.batch_size == 0 {
+            return Err("reaper.batch_size must be at least 1".to_string());
+        }
+
+        if self.clicks.queue_size == 0 || self.clicks.batch_size == 0 {
+            return Err("clicks.queue_size and batch_size must be at least 1".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.server.public_base_url
+    }
+
+    // 按 short_code 配置生成短码，跳过 custom_code.reserved 中的保留字。
+    // length 为当前的短码长度，见 CodeAllocator
+    pub fn code_generator(&self, length: usize) -> Box<dyn CodeGenerator> {
+        Box::new(SkipReserved::new(
+            self.short_code.generator(length),
+            &self.custom_code.reserved,
+        ))
+    }
+
+    // 账号、Token 和访问记录所在的 SQLite 数据库，database.url 为 SQLite 时与其相同
+    pub fn sqlite_url(&self) -> &str {
+        match Backend::from_url(&self.database.url) {
+            Some(Backend::Sqlite) => &self.database.url,
+            _ => &self.database.sqlite_url,
+        }
+    }
+
+    pub fn is_admin(&self, username: &str) -> bool {
+        self.auth.admin_users.iter().any(|admin| admin == username)
+    }
+
+    pub fn allows_any_origin(&self) -> bool {
+        self.cors.allowed_origins.is_empty() || self.cors.allowed_origins.iter().any(|o| o == "*")
+    }
+}
+
+// 逗号分隔的列表
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn parse_env<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", key, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_default_config() {
+        let mut config = AppConfig::default();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.base_url(), "http://localhost:8080");
+        assert_eq!(config.server.bind_addr, "0.0.0.0:8080");
+        assert_eq!(config.short_code.length, 6);
+        assert!(config.allows_any_origin());
+    }
+
+    #[test]
+    fn test_from_toml_partial() {
+        let config = AppConfig::from_toml(
+            r#"
+            [server]
+            public_base_url = "https://s.example.com/"
+            workers = 4
+
+            [cors]
+            allowed_origins = ["https://app.example.com"]
+            "#,
+        )
+        .expect("Failed to parse config");
+
+        assert_eq!(config.server.public_base_url, "https://s.example.com/");
+        assert_eq!(config.server.workers, Some(4));
+        // Sections that are not present fall back to defaults
+        assert_eq!(config.server.bind_addr, "0.0.0.0:8080");
+        assert_eq!(config.database.url, "sqlite:short_url.db");
+        assert!(!config.allows_any_origin());
+    }
+
+    #[test]
+    fn test_from_toml_short_code_strategy() {
+        let config = AppConfig::from_toml(
+            r#"
+            [short_code]
+            strategy = "sqids"
+            alphabet = "0123456789abcdef"
+            salt = "s3cret"
+            "#,
+        )
+        .expect("Failed to parse config");
+
+        assert_eq!(config.short_code.strategy, CodeStrategy::Sqids);
+        assert_eq!(config.short_code.length, 6);
+        let generator = config.short_code.generator(config.short_code.length);
+        assert!(generator.uses_row_id());
+        let code = generator.generate(42, 0);
+        assert!(code.len() >= 6);
+        assert!(code.chars().all(|c| c.is_ascii_hexdigit()));
+        assert!(AppConfig::from_toml("[short_code]\nstrategy = \"uuid\"").is_err());
+    }
+
+    #[test]
+    fn test_from_toml_rate_limit() {
+        let config = AppConfig::from_toml(
+            r#"
+            [rate_limit.redirect]
+            burst = 5
+            per_minute = 30
+            "#,
+        )
+        .expect("Failed to parse config");
+
+        assert_eq!(config.rate_limit.redirect.burst, 5);
+        assert_eq!(config.rate_limit.redirect.per_minute, 30);
+        assert_eq!(
+            config.rate_limit.shorten,
+            RateLimitConfig::default().shorten
+        );
+    }
+
+    #[test]
+    fn test_from_toml_reaper() {
+        let config = AppConfig::from_toml(
+            r#"
+            [auth]
+            admin_users = ["alice"]
+
+            [reaper]
+            mode = "delete"
+            grace_period_secs = 0
+            "#,
+        )
+        .expect("Failed to parse config");
+
+        assert!(config.is_admin("alice"));
+        assert!(!config.is_admin("bob"));
+        assert_eq!(config.reaper.mode, ReaperMode::Delete);
+        assert_eq!(config.reaper.grace_period_secs, 0);
+        assert_eq!(config.reaper.batch_size, 500);
+
+        assert!(AppConfig::from_toml("[reaper]\nmode = \"truncate\"").is_err());
+    }
+
+    #[test]
+    fn test_from_toml_rejects_unknown_fields() {
+        let result = AppConfig::from_toml(
+            r#"
+            [server]
+            base_url = "https://s.example.com"
+            "#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let env: HashMap<&str, &str> = HashMap::from([
+            ("SHORT_URL_PUBLIC_BASE_URL", "https://sho.rt/"),
+            ("SHORT_URL_BIND_ADDR", "127.0.0.1:9000"),
+            ("SHORT_URL_WORKERS", "2"),
+            ("DATABASE_URL", "sqlite::memory:"),
+            ("SHORT_URL_CODE_LENGTH", "8"),
+            ("SHORT_URL_DEDUPE", "true"),
+            ("SHORT_URL_CODE_GROWTH_THRESHOLD", "0.25"),
+            ("SHORT_URL_CUSTOM_CODE_RESERVED", "api, login"),
+            ("SHORT_URL_CUSTOM_CODE_CASE_INSENSITIVE", "true"),
+            ("SHORT_URL_ALLOWED_SCHEMES", "HTTPS, mailto"),
+            ("SHORT_URL_BLOCKLIST_FILE", "/etc/short_url/blocklist.txt"),
+            ("SHORT_URL_CORS_ORIGINS", "https://a.com, https://b.com"),
+            ("SHORT_URL_GRPC_ENABLED", "false"),
+            ("SHORT_URL_GRPC_BIND_ADDR", "127.0.0.1:50052"),
+            ("SHORT_URL_CACHE_CAPACITY", "100"),
+            ("SHORT_URL_CACHE_TTL_SECS", "60"),
+            ("SHORT_URL_ADMIN_USERS", "alice, bob"),
+            ("SHORT_URL_REAPER_MODE", "delete"),
+        ]);
+
+        let mut config = AppConfig::default();
+        config
+            .apply_env(|key| env.get(key).map(|v| v.to_string()))
+            .expect("Failed to apply env");
+        config.validate().expect("Config should be valid");
+
+        assert_eq!(config.base_url(), "https://sho.rt");
+        assert_eq!(config.server.bind_addr, "127.0.0.1:9000");
+        assert_eq!(config.server.workers, Some(2));
+        assert_eq!(config.database.url, "sqlite::memory:");
+        assert_eq!(config.short_code.length, 8);
+        assert!(config.short_code.dedupe);
+        assert_eq!(config.short_code.growth_threshold, 0.25);
+        assert_eq!(config.custom_code.reserved, ["api", "login"]);
+        assert!(config.custom_code.case_insensitive);
+        assert_eq!(config.url_policy.allowed_schemes, ["https", "mailto"]);
+        assert_eq!(
+            config.url_policy.blocklist_file.as_deref(),
+            Some("/etc/short_url/blocklist.txt")
+        );
+        assert_eq!(
+            config.cors.allowed_origins,
+            vec!["https://a.com".to_string(), "https://b.com".to_string()]
+        );
+        assert!(!config.grpc.enabled);
+        assert_eq!(config.grpc.bind_addr, "127.0.0.1:50052");
+        assert_eq!(config.cache.capacity, 100);
+        assert_eq!(config.cache.ttl_secs, 60);
+        assert!(config.is_admin("bob"));
+        assert_eq!(config.reaper.mode, ReaperMode::Delete);
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let mut config = AppConfig::default();
+        assert!(config
+            .apply_env(|key| (key == "SHORT_URL_WORKERS").then(|| "many".to_string()))
+            .is_err());
+
+        let mut config = AppConfig::default();
+        config.server.public_base_url = "localhost".to_string();
+        assert!(config.validate().is_err());
+
+        let mut config = AppConfig::default();
+        config.short_code.length = 2;
+        assert!(config.validate().is_err());
+
+        for alphabet in ["abc", "abcdefghij/", "abcdefghija"] {
+            let mut config = AppConfig::default();
+            config.short_code.alphabet = alphabet.to_string();
+            assert!(config.validate().is_err(), "{}", alphabet);
+        }
+        for (min_length, max_length) in [(0, 8), (9, 8), (3, 33)] {
+            let mut config = AppConfig::default();
+            config.custom_code.min_length = min_length;
+            config.custom_code.max_length = max_length;
+            assert!(config.validate().is_err());
+        }
+        for threshold in [0.0, 1.5, f64::NAN] {
+            let mut config = AppConfig::default();
+            config.short_code.growth_threshold = threshold;
+            assert!(config.validate().is_err(), "{}", threshold);
+        }
+        let mut config = AppConfig::default();
+        assert!(config
+            .apply_env(|key| (key == "SHORT_URL_CODE_STRATEGY").then(|| "uuid".to_string()))
+            .is_err());
+
+        let mut config = AppConfig::default();
+        config.url_policy.allowed_schemes.clear();
+        assert!(config.validate().is_err());
+
+        let mut config = AppConfig::default();
+        config.server.workers = Some(0);
+        assert!(config.validate().is_err());
+
+        let mut config = AppConfig::default();
+        config.grpc.bind_addr = "localhost".to_string();
+        assert!(config.validate().is_err());
+
+        let mut config = AppConfig::default();
+        config.database.url = "mysql://localhost/short_url".to_string();
+        assert!(config.validate().is_err());
+
+        let mut config = AppConfig::default();
+        config.cache.capacity = 0;
+        assert!(config.validate().is_err());
+        config.cache.enabled = false;
+        assert!(config.validate().is_ok());
+
+        let mut config = AppConfig::default();
+        config.bloom_filter.false_positive_rate = 1.0;
+        assert!(config.validate().is_err());
+
+        let mut config = AppConfig::default();
+        config.rate_limit.shorten.per_minute = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = AppConfig::default();
+        config.reaper.interval_secs = 0;
+        assert!(config.validate().is_err());
+        config.reaper.enabled = false;
+        assert!(config.validate().is_ok());
+
+        let mut config = AppConfig::default();
+        config.clicks.queue_size = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_database_backend() {
+        let mut config = AppConfig::default();
+        config.database.url = "postgres://localhost/short_url".to_string();
+        config.database.sqlite_url = "sqlite:accounts.db".to_string();
+
+        // PostgreSQL 只在启用 feature 时可用
+        assert_eq!(config.validate().is_ok(), cfg!(feature = "postgres"));
+        assert_eq!(config.sqlite_url(), "sqlite:accounts.db");
+
+        config.database.sqlite_url = "postgres://localhost/accounts".to_string();
+        assert!(config.validate().is_err());
+
+        // SQLite 时所有数据在同一个数据库中
+        let mut config = AppConfig::default();
+        config.database.url = "sqlite::memory:".to_string();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.sqlite_url(), "sqlite::memory:");
+    }
+}
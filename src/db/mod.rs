@@ -1,4 +1,58 @@
-use sqlx::{migrate::MigrateDatabase, Executor, SqlitePool};
+#[cfg(feature = "postgres")]
+pub mod postgres;
+
+use sqlx::{
+    migrate::{MigrateDatabase, Migrator},
+    SqlitePool,
+};
+
+// 迁移文件位于 migrations/<数据库>/ 目录，编译时嵌入二进制，已执行的版本记录在 _sqlx_migrations 表中
+// 已发布的迁移文件不能修改（会校验 checksum），变更表结构时新增一个版本
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+// 由连接串协议决定的数据库类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Sqlite,
+    Postgres,
+}
+
+impl Backend {
+    pub fn from_url(url: &str) -> Option<Self> {
+        let scheme = url.split(':').next()?.to_ascii_lowercase();
+        match scheme.as_str() {
+            "sqlite" => Some(Backend::Sqlite),
+            "postgres" | "postgresql" => Some(Backend::Postgres),
+            _ => None,
+        }
+    }
+}
+
+// 数据库当前版本与二进制中内嵌的最新版本
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub current: Option<i64>,
+    pub latest: Option<i64>,
+    pub pending: Vec<i64>,
+}
+
+impl MigrationStatus {
+    pub fn new(migrator: &Migrator, applied: &[i64]) -> Self {
+        Self {
+            current: applied.iter().max().copied(),
+            latest: migrator.iter().map(|migration| migration.version).max(),
+            pending: migrator
+                .iter()
+                .map(|migration| migration.version)
+                .filter(|version| !applied.contains(version))
+                .collect(),
+        }
+    }
+
+    pub fn is_up_to_date(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
 
 pub async fn create_pool(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
     // Create database if it doesn't exist
@@ -13,30 +67,38 @@ pub async fn create_pool(database_url: &str) -> Result<SqlitePool, sqlx::Error>
     Ok(pool)
 }
 
+// 执行所有未执行的迁移（up）
 pub async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    pool.execute(
-        r#"
-        CREATE TABLE IF NOT EXISTS short_urls (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            long_url TEXT NOT NULL,
-            short_code TEXT NOT NULL UNIQUE,
-            created_at TEXT NOT NULL,
-            expires_at TEXT,
-            user_id TEXT NOT NULL
-        )
-        "#,
+    MIGRATOR
+        .run(pool)
+        .await
+        .map_err(|e| sqlx::Error::Migrate(Box::new(e)))
+}
+
+// 已成功执行的迁移版本，迁移表不存在时为空
+async fn applied_versions(pool: &SqlitePool) -> Result<Vec<i64>, sqlx::Error> {
+    let table_exists = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
     )
-    .await?;
+    .fetch_one(pool)
+    .await?
+        > 0;
+    if !table_exists {
+        return Ok(Vec::new());
+    }
 
-    // Create index on short_code for faster lookups
-    pool.execute("CREATE INDEX IF NOT EXISTS idx_short_code ON short_urls(short_code)")
-        .await?;
+    sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = 1 ORDER BY version")
+        .fetch_all(pool)
+        .await
+}
 
-    // Create index on user_id for faster user queries
-    pool.execute("CREATE INDEX IF NOT EXISTS idx_user_id ON short_urls(user_id)")
-        .await?;
+pub async fn current_version(pool: &SqlitePool) -> Result<Option<i64>, sqlx::Error> {
+    Ok(applied_versions(pool).await?.last().copied())
+}
 
-    Ok(())
+pub async fn migration_status(pool: &SqlitePool) -> Result<MigrationStatus, sqlx::Error> {
+    let applied = applied_versions(pool).await?;
+    Ok(MigrationStatus::new(&MIGRATOR, &applied))
 }
 
 #[cfg(test)]
@@ -89,6 +151,55 @@ mod tests {
         assert!(index_names.contains(&"idx_user_id".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_auth_tables_created() {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+        run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        for table in ["users", "sessions", "api_tokens"] {
+            let exists =
+                sqlx::query("SELECT name FROM sqlite_master WHERE type='table' AND name=?")
+                    .bind(table)
+                    .fetch_optional(&pool)
+                    .await
+                    .expect("Failed to check if table exists");
+            assert!(exists.is_some(), "{} table missing", table);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clicks_table_structure() {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+        run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        let columns = sqlx::query("PRAGMA table_info(clicks)")
+            .fetch_all(&pool)
+            .await
+            .expect("Failed to get table info");
+
+        let column_names: Vec<String> = columns.iter().map(|row| row.get("name")).collect();
+        for name in [
+            "id",
+            "short_code",
+            "clicked_at",
+            "referrer",
+            "user_agent",
+            "ip",
+            "accept_language",
+            "link_id",
+        ] {
+            assert!(column_names.contains(&name.to_string()));
+        }
+    }
+
     #[tokio::test]
     async fn test_table_structure() {
         // Create a test pool and run migrations
@@ -127,4 +238,103 @@ mod tests {
 
         assert_eq!(pk_column, "id");
     }
+
+    #[test]
+    fn test_backend_from_url() {
+        assert_eq!(
+            Backend::from_url("sqlite:short_url.db"),
+            Some(Backend::Sqlite)
+        );
+        assert_eq!(Backend::from_url("sqlite::memory:"), Some(Backend::Sqlite));
+        assert_eq!(
+            Backend::from_url("postgres://localhost/short_url"),
+            Some(Backend::Postgres)
+        );
+        assert_eq!(
+            Backend::from_url("postgresql://localhost/short_url"),
+            Some(Backend::Postgres)
+        );
+        assert_eq!(Backend::from_url("mysql://localhost/short_url"), None);
+        assert_eq!(Backend::from_url("short_url.db"), None);
+    }
+
+    #[tokio::test]
+    async fn test_migration_versions() {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+
+        // Fresh database has no version
+        let status = migration_status(&pool).await.expect("Failed to get status");
+        assert_eq!(status.current, None);
+        assert!(!status.is_up_to_date());
+        assert_eq!(status.pending.len(), MIGRATOR.iter().count());
+
+        run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        let status = migration_status(&pool).await.expect("Failed to get status");
+        assert!(status.is_up_to_date());
+        assert_eq!(status.current, status.latest);
+        assert_eq!(
+            current_version(&pool).await.expect("Failed to get version"),
+            status.latest
+        );
+
+        // Running again is a no-op
+        run_migrations(&pool)
+            .await
+            .expect("Failed to rerun migrations");
+        let applied: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM _sqlx_migrations")
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to count migrations");
+        assert_eq!(applied as usize, MIGRATOR.iter().count());
+    }
+
+    #[tokio::test]
+    async fn test_migrate_existing_database() {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+
+        // Database created before versioned migrations existed
+        sqlx::query(
+            r#"
+            CREATE TABLE short_urls (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                long_url TEXT NOT NULL,
+                short_code TEXT NOT NULL UNIQUE,
+                created_at TEXT NOT NULL,
+                expires_at TEXT,
+                user_id TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create legacy table");
+        sqlx::query(
+            "INSERT INTO short_urls (long_url, short_code, created_at, user_id) \
+             VALUES ('https://example.com', 'legacy', '2024-01-01T00:00:00Z', 'alice')",
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to insert legacy row");
+
+        run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM short_urls")
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to count rows");
+        assert_eq!(count, 1);
+        assert!(migration_status(&pool)
+            .await
+            .expect("Failed to get status")
+            .is_up_to_date());
+    }
 }
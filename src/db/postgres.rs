@@ -0,0 +1,46 @@
+use super::MigrationStatus;
+use sqlx::{
+    migrate::{MigrateDatabase, Migrator},
+    PgPool,
+};
+
+// PostgreSQL 只保存短链接，账号、Token 和访问记录仍在 SQLite 中
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+
+pub async fn create_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
+    // Create database if it doesn't exist
+    if !sqlx::Postgres::database_exists(database_url)
+        .await
+        .unwrap_or(false)
+    {
+        sqlx::Postgres::create_database(database_url).await?;
+    }
+
+    PgPool::connect(database_url).await
+}
+
+pub async fn run_migrations(pool: &PgPool) -> Result<(), sqlx::Error> {
+    MIGRATOR
+        .run(pool)
+        .await
+        .map_err(|e| sqlx::Error::Migrate(Box::new(e)))
+}
+
+async fn applied_versions(pool: &PgPool) -> Result<Vec<i64>, sqlx::Error> {
+    let table_exists: bool =
+        sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+    if !table_exists {
+        return Ok(Vec::new());
+    }
+
+    sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success ORDER BY version")
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn migration_status(pool: &PgPool) -> Result<MigrationStatus, sqlx::Error> {
+    let applied = applied_versions(pool).await?;
+    Ok(MigrationStatus::new(&MIGRATOR, &applied))
+}
@@ -0,0 +1,224 @@
+use crate::pb::abi::ErrorResponse;
+use actix_web::{
+    http::{header, StatusCode},
+    web, HttpResponse, ResponseError,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("Invalid URL format")]
+    InvalidUrl,
+
+    #[error("Custom code already exists")]
+    CodeTaken,
+
+    #[error("{0}")]
+    InvalidRequest(String),
+
+    // 某个请求字段的值不合法，field 为字段名
+    #[error("{message}")]
+    InvalidField {
+        field: &'static str,
+        message: String,
+    },
+
+    // 目标 URL 违反 url_policy：协议不允许、主机被屏蔽、内网地址或指向本服务
+    #[error("{0}")]
+    UrlNotAllowed(String),
+
+    #[error("{0}")]
+    Unauthorized(&'static str),
+
+    #[error("Token is missing required scope: {0}")]
+    InsufficientScope(&'static str),
+
+    #[error("Username already exists")]
+    UsernameTaken,
+
+    #[error("{0} not found")]
+    NotFound(&'static str),
+
+    #[error("{0}")]
+    Forbidden(&'static str),
+
+    #[error("Short URL has expired")]
+    Expired,
+
+    #[error("Too many requests, retry after {retry_after} seconds")]
+    RateLimited { retry_after: u64 },
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl AppError {
+    // 稳定的错误码，客户端应依据此字段而不是 message 做分支判断
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            AppError::InvalidUrl => "INVALID_URL",
+            AppError::CodeTaken => "CODE_TAKEN",
+            AppError::InvalidRequest(_) => "INVALID_REQUEST",
+            AppError::InvalidField { .. } => "INVALID_FIELD",
+            AppError::UrlNotAllowed(_) => "URL_NOT_ALLOWED",
+            AppError::Unauthorized(_) => "UNAUTHORIZED",
+            AppError::InsufficientScope(_) => "INSUFFICIENT_SCOPE",
+            AppError::UsernameTaken => "USERNAME_TAKEN",
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::Forbidden(_) => "FORBIDDEN",
+            AppError::Expired => "EXPIRED",
+            AppError::RateLimited { .. } => "RATE_LIMITED",
+            AppError::Database(_) => "DATABASE_ERROR",
+            AppError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    // 服务端错误不向客户端暴露内部细节
+    fn public_message(&self) -> String {
+        match self {
+            AppError::Database(_) => "Database error".to_string(),
+            AppError::Internal(_) => "Internal server error".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    pub fn to_error_response(&self) -> ErrorResponse {
+        ErrorResponse {
+            success: false,
+            message: self.public_message(),
+            error_code: self.error_code().to_string(),
+            field: match self {
+                AppError::InvalidField { field, .. } => field.to_string(),
+                _ => String::new(),
+            },
+        }
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::InvalidUrl
+            | AppError::InvalidRequest(_)
+            | AppError::InvalidField { .. }
+            | AppError::UrlNotAllowed(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::CodeTaken | AppError::UsernameTaken => StatusCode::CONFLICT,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Forbidden(_) | AppError::InsufficientScope(_) => StatusCode::FORBIDDEN,
+            AppError::Expired => StatusCode::GONE,
+            AppError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if self.status_code().is_server_error() {
+            log::error!("{}", self);
+        }
+        let mut builder = HttpResponse::build(self.status_code());
+        match self {
+            AppError::Unauthorized(_) => {
+                builder.insert_header((header::WWW_AUTHENTICATE, "Bearer"));
+            }
+            AppError::RateLimited { retry_after } => {
+                builder.insert_header((header::RETRY_AFTER, retry_after.to_string()));
+            }
+            _ => {}
+        }
+        builder.json(self.to_error_response())
+    }
+}
+
+// 请求体 / 查询参数解析失败时同样返回 ErrorResponse
+pub fn json_config() -> web::JsonConfig {
+    web::JsonConfig::default()
+        .error_handler(|err, _req| AppError::InvalidRequest(err.to_string()).into())
+}
+
+pub fn query_config() -> web::QueryConfig {
+    web::QueryConfig::default()
+        .error_handler(|err, _req| AppError::InvalidRequest(err.to_string()).into())
+}
+
+pub fn path_config() -> web::PathConfig {
+    web::PathConfig::default()
+        .error_handler(|err, _req| AppError::InvalidRequest(err.to_string()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+
+    #[test]
+    fn test_status_codes() {
+        assert_eq!(AppError::InvalidUrl.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(AppError::CodeTaken.status_code(), StatusCode::CONFLICT);
+        assert_eq!(
+            AppError::UrlNotAllowed("Host 'evil.example' is blocked".to_string()).status_code(),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            AppError::Unauthorized("Authentication required").status_code(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            AppError::NotFound("URL").status_code(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            AppError::Forbidden("URL is not owned by user").status_code(),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            AppError::InsufficientScope("links:write").status_code(),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(AppError::Expired.status_code(), StatusCode::GONE);
+        assert_eq!(
+            AppError::RateLimited { retry_after: 3 }.status_code(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(
+            AppError::Database(sqlx::Error::RowNotFound).status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_error_response_body() {
+        let resp = AppError::CodeTaken.error_response();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        let body = to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["success"], false);
+        assert_eq!(json["error_code"], "CODE_TAKEN");
+        assert_eq!(json["message"], "Custom code already exists");
+        assert!(json.get("field").is_none());
+
+        let resp = AppError::InvalidField {
+            field: "custom_code",
+            message: "custom_code 'api' is reserved".to_string(),
+        }
+        .error_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error_code"], "INVALID_FIELD");
+        assert_eq!(json["field"], "custom_code");
+        assert_eq!(json["message"], "custom_code 'api' is reserved");
+    }
+
+    #[actix_web::test]
+    async fn test_internal_details_are_hidden() {
+        let resp = AppError::Database(sqlx::Error::PoolTimedOut).error_response();
+        let body = to_bytes(resp.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error_code"], "DATABASE_ERROR");
+        assert_eq!(json["message"], "Database error");
+    }
+}
@@ -0,0 +1,456 @@
+use crate::api::auth::AuthUser;
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::pb::abi::short_url_service_server::{ShortUrlService, ShortUrlServiceServer};
+use crate::pb::abi::{
+    BatchCreateShortUrlsRequest, BatchCreateShortUrlsResponse, CreateShortUrlRequest,
+    DeleteShortUrlRequest, DeleteShortUrlResponse, GetShortUrlRequest, GetUserUrlsResponse,
+    ListShortUrlsRequest, ResolveRequest, ResolveResponse, ShortUrlResponse, UpdateShortUrlRequest,
+};
+use crate::pb::FILE_DESCRIPTOR_SET;
+use crate::services::allocator::CodeAllocator;
+use crate::services::api_tokens::Scope;
+use crate::services::url_policy::UrlPolicy;
+use crate::services::UrlService;
+use crate::store::LinkStore;
+use crate::utils::url_validator::normalize_url;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use tonic::metadata::MetadataValue;
+use tonic::{Code, Request, Response, Status};
+
+// 与 HTTP 接口共用 UrlService 和认证逻辑
+pub struct ShortUrlGrpc {
+    pool: SqlitePool,
+    store: Arc<dyn LinkStore>,
+    allocator: Arc<CodeAllocator>,
+    url_policy: Arc<UrlPolicy>,
+    config: Arc<AppConfig>,
+}
+
+impl ShortUrlGrpc {
+    pub fn new(
+        pool: SqlitePool,
+        store: Arc<dyn LinkStore>,
+        allocator: Arc<CodeAllocator>,
+        url_policy: Arc<UrlPolicy>,
+        config: Arc<AppConfig>,
+    ) -> Self {
+        Self {
+            pool,
+            store,
+            allocator,
+            url_policy,
+            config,
+        }
+    }
+
+    async fn authenticate<T>(
+        &self,
+        request: &Request<T>,
+        scope: Scope,
+    ) -> Result<AuthUser, Status> {
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .ok_or(AppError::Unauthorized("Authentication required"))?;
+
+        let user = AuthUser::from_token(&self.pool, token).await?;
+        user.require_scope(scope)?;
+        Ok(user)
+    }
+}
+
+#[tonic::async_trait]
+impl ShortUrlService for ShortUrlGrpc {
+    async fn create(
+        &self,
+        request: Request<CreateShortUrlRequest>,
+    ) -> Result<Response<ShortUrlResponse>, Status> {
+        let user = self.authenticate(&request, Scope::LinksWrite).await?;
+
+        let mut req = request.into_inner();
+        req.user_id = user.username;
+        req.long_url = normalize_url(&req.long_url);
+
+        let short_url = UrlService::create_short_url(
+            self.store.as_ref(),
+            &self.allocator,
+            &self.url_policy,
+            &self.config,
+            req,
+        )
+        .await?;
+        Ok(Response::new(short_url.to_response(self.config.base_url())))
+    }
+
+    async fn batch_create(
+        &self,
+        request: Request<BatchCreateShortUrlsRequest>,
+    ) -> Result<Response<BatchCreateShortUrlsResponse>, Status> {
+        let user = self.authenticate(&request, Scope::LinksWrite).await?;
+
+        let mut req = request.into_inner();
+        for item in &mut req.items {
+            item.long_url = normalize_url(&item.long_url);
+        }
+
+        let result = UrlService::batch_create_short_urls(
+            self.store.as_ref(),
+            &self.allocator,
+            &self.url_policy,
+            &self.config,
+            &user.username,
+            req,
+        )
+        .await?;
+        Ok(Response::new(result.to_response(self.config.base_url())))
+    }
+
+    async fn get(
+        &self,
+        request: Request<GetShortUrlRequest>,
+    ) -> Result<Response<ShortUrlResponse>, Status> {
+        let user = self.authenticate(&request, Scope::LinksRead).await?;
+
+        let short_url =
+            UrlService::get_short_url(self.store.as_ref(), request.get_ref().id).await?;
+        if short_url.user_id != user.username {
+            return Err(AppError::Forbidden("URL is not owned by user").into());
+        }
+        Ok(Response::new(short_url.to_response(self.config.base_url())))
+    }
+
+    async fn list(
+        &self,
+        request: Request<ListShortUrlsRequest>,
+    ) -> Result<Response<GetUserUrlsResponse>, Status> {
+        let user = self.authenticate(&request, Scope::LinksRead).await?;
+
+        let page =
+            UrlService::list_user_urls(self.store.as_ref(), &user.username, request.get_ref())
+                .await?;
+        Ok(Response::new(page.to_response(self.config.base_url())))
+    }
+
+    async fn update(
+        &self,
+        request: Request<UpdateShortUrlRequest>,
+    ) -> Result<Response<ShortUrlResponse>, Status> {
+        let user = self.authenticate(&request, Scope::LinksWrite).await?;
+
+        let mut req = request.into_inner();
+        req.user_id = user.username.clone();
+        req.long_url = req.long_url.as_deref().map(normalize_url);
+
+        let short_url = UrlService::update_short_url(
+            self.store.as_ref(),
+            &self.url_policy,
+            &self.config,
+            req.id,
+            &user.username,
+            req,
+        )
+        .await?;
+        Ok(Response::new(short_url.to_response(self.config.base_url())))
+    }
+
+    // 请求中的 user_id 会被忽略，以 Token 对应的用户为准
+    async fn delete(
+        &self,
+        request: Request<DeleteShortUrlRequest>,
+    ) -> Result<Response<DeleteShortUrlResponse>, Status> {
+        let user = self.authenticate(&request, Scope::LinksDelete).await?;
+
+        UrlService::delete_short_url(self.store.as_ref(), request.get_ref().id, &user.username)
+            .await?;
+        Ok(Response::new(DeleteShortUrlResponse {}))
+    }
+
+    async fn resolve(
+        &self,
+        request: Request<ResolveRequest>,
+    ) -> Result<Response<ResolveResponse>, Status> {
+        let url = UrlService::resolve(
+            self.store.as_ref(),
+            &self.config.custom_code,
+            &request.get_ref().short_code,
+        )
+        .await?;
+        Ok(Response::new(ResolveResponse {
+            long_url: url.long_url,
+        }))
+    }
+}
+
+impl From<AppError> for Status {
+    fn from(err: AppError) -> Self {
+        let code = match &err {
+            AppError::InvalidUrl
+            | AppError::InvalidRequest(_)
+            | AppError::InvalidField { .. }
+            | AppError::UrlNotAllowed(_) => Code::InvalidArgument,
+            AppError::Unauthorized(_) => Code::Unauthenticated,
+            AppError::Forbidden(_) | AppError::InsufficientScope(_) => Code::PermissionDenied,
+            AppError::NotFound(_) => Code::NotFound,
+            AppError::CodeTaken | AppError::UsernameTaken => Code::AlreadyExists,
+            AppError::Expired => Code::FailedPrecondition,
+            AppError::RateLimited { .. } => Code::ResourceExhausted,
+            AppError::Database(_) | AppError::Internal(_) => Code::Internal,
+        };
+        if code == Code::Internal {
+            log::error!("{}", err);
+        }
+
+        // 与 HTTP 接口相同的错误码放在 metadata 中
+        let response = err.to_error_response();
+        let mut status = Status::new(code, response.message);
+        status
+            .metadata_mut()
+            .insert("x-error-code", MetadataValue::from_static(err.error_code()));
+        if let AppError::InvalidField { field, .. } = err {
+            status
+                .metadata_mut()
+                .insert("x-error-field", MetadataValue::from_static(field));
+        }
+        status
+    }
+}
+
+// 启动 gRPC 服务，同时提供健康检查和反射服务
+pub async fn serve(
+    pool: SqlitePool,
+    store: Arc<dyn LinkStore>,
+    allocator: Arc<CodeAllocator>,
+    url_policy: Arc<UrlPolicy>,
+    config: Arc<AppConfig>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let addr = config.grpc.bind_addr.parse()?;
+
+    let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+    health_reporter
+        .set_serving::<ShortUrlServiceServer<ShortUrlGrpc>>()
+        .await;
+
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+        .build()?;
+
+    tonic::transport::Server::builder()
+        .add_service(health_service)
+        .add_service(reflection_service)
+        .add_service(ShortUrlServiceServer::new(ShortUrlGrpc::new(
+            pool, store, allocator, url_policy, config,
+        )))
+        .serve(addr)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::services::auth::AuthService;
+    use crate::store::SqliteLinkStore;
+
+    async fn setup() -> (ShortUrlGrpc, String) {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+
+        db::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        let mut config = AppConfig::default();
+        config.server.public_base_url = "https://sho.rt".to_string();
+
+        AuthService::register(&pool, &config, "test_user", "correct-horse")
+            .await
+            .expect("Failed to register");
+        let token = AuthService::login(&pool, &config, "test_user", "correct-horse")
+            .await
+            .expect("Failed to login")
+            .token;
+
+        let store = Arc::new(SqliteLinkStore::new(pool.clone()));
+        let allocator = Arc::new(CodeAllocator::new());
+        let url_policy = Arc::new(UrlPolicy::new(&config));
+        (
+            ShortUrlGrpc::new(pool, store, allocator, url_policy, Arc::new(config)),
+            token,
+        )
+    }
+
+    fn authorized<T>(message: T, token: &str) -> Request<T> {
+        let mut request = Request::new(message);
+        request.metadata_mut().insert(
+            "authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        request
+    }
+
+    #[tokio::test]
+    async fn test_create_get_list_resolve_delete() {
+        let (service, token) = setup().await;
+
+        let created = service
+            .create(authorized(
+                CreateShortUrlRequest {
+                    long_url: "example.com/grpc".to_string(),
+                    custom_code: Some("grpc".to_string()),
+                    ..Default::default()
+                },
+                &token,
+            ))
+            .await
+            .expect("Failed to create")
+            .into_inner();
+        assert_eq!(created.short_url, "https://sho.rt/grpc");
+        assert_eq!(created.long_url, "http://example.com/grpc");
+
+        let fetched = service
+            .get(authorized(GetShortUrlRequest { id: created.id }, &token))
+            .await
+            .expect("Failed to get")
+            .into_inner();
+        assert_eq!(fetched.short_code, "grpc");
+
+        let listed = service
+            .list(authorized(ListShortUrlsRequest::default(), &token))
+            .await
+            .expect("Failed to list")
+            .into_inner();
+        assert_eq!(listed.urls.len(), 1);
+        assert_eq!(listed.total, 1);
+        assert!(listed.next_page_token.is_empty());
+
+        let status = service
+            .list(authorized(
+                ListShortUrlsRequest {
+                    status: "unknown".to_string(),
+                    ..Default::default()
+                },
+                &token,
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        let resolved = service
+            .resolve(Request::new(ResolveRequest {
+                short_code: "grpc".to_string(),
+            }))
+            .await
+            .expect("Failed to resolve")
+            .into_inner();
+        assert_eq!(resolved.long_url, "http://example.com/grpc");
+
+        let updated = service
+            .update(authorized(
+                UpdateShortUrlRequest {
+                    id: created.id,
+                    long_url: Some("example.com/updated".to_string()),
+                    ..Default::default()
+                },
+                &token,
+            ))
+            .await
+            .expect("Failed to update")
+            .into_inner();
+        assert_eq!(updated.long_url, "http://example.com/updated");
+        assert_eq!(updated.short_code, "grpc");
+
+        service
+            .delete(authorized(
+                DeleteShortUrlRequest {
+                    id: created.id,
+                    ..Default::default()
+                },
+                &token,
+            ))
+            .await
+            .expect("Failed to delete");
+
+        let status = service
+            .resolve(Request::new(ResolveRequest {
+                short_code: "grpc".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+        assert_eq!(status.metadata().get("x-error-code").unwrap(), "NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn test_requires_authentication() {
+        let (service, _) = setup().await;
+
+        let status = service
+            .list(Request::new(ListShortUrlsRequest::default()))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+
+        let status = service
+            .list(authorized(
+                ListShortUrlsRequest::default(),
+                "not-a-real-token",
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_error_mapping() {
+        let (service, token) = setup().await;
+
+        let status = service
+            .create(authorized(
+                CreateShortUrlRequest {
+                    long_url: "://invalid-url".to_string(),
+                    ..Default::default()
+                },
+                &token,
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(
+            status.metadata().get("x-error-code").unwrap(),
+            "INVALID_URL"
+        );
+
+        // Links back to the service itself would redirect in a loop
+        let status = service
+            .create(authorized(
+                CreateShortUrlRequest {
+                    long_url: "https://sho.rt/grpc".to_string(),
+                    ..Default::default()
+                },
+                &token,
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(
+            status.metadata().get("x-error-code").unwrap(),
+            "URL_NOT_ALLOWED"
+        );
+
+        let status = service
+            .get(authorized(GetShortUrlRequest { id: 42 }, &token))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+    }
+}
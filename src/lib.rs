@@ -1,6 +1,10 @@
 pub mod api;
+pub mod config;
 pub mod db;
+pub mod error;
+pub mod grpc;
 pub mod models;
 pub mod pb;
 pub mod services;
+pub mod store;
 pub mod utils;
@@ -1,41 +1,174 @@
-use actix_web::{middleware::Logger, web, App, HttpResponse, HttpServer};
-use short_url_rs::{api::*, db};
+use actix_web::{
+    middleware::{from_fn, Logger},
+    web, App, HttpResponse, HttpServer,
+};
+use short_url_rs::{
+    api::*,
+    config::AppConfig,
+    db, error, grpc,
+    services::{
+        allocator::CodeAllocator, click_log::ClickLog, reaper::ReaperService,
+        url_policy::UrlPolicy, UrlService,
+    },
+    store::{self, BloomLinkStore, CachedLinkStore, LinkCache, LinkStore},
+};
+use std::sync::Arc;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    dotenv::dotenv().ok();
     env_logger::init();
 
-    // Initialize database
-    let database_url =
-        std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:short_url.db".to_string());
+    // Load configuration
+    let config = AppConfig::load().expect("Failed to load configuration");
 
-    let pool = db::create_pool(&database_url)
+    // Initialize database
+    let pool = db::create_pool(config.sqlite_url())
         .await
         .expect("Failed to create database pool");
 
+    // `short_url_rs migrate status` 只查看迁移状态，不执行迁移
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().map(String::as_str).eq(["migrate", "status"]) {
+        let status = db::migration_status(&pool)
+            .await
+            .expect("Failed to read migration status");
+        print_migration_status("SQLite", &status);
+
+        #[cfg(feature = "postgres")]
+        if db::Backend::from_url(&config.database.url) == Some(db::Backend::Postgres) {
+            let pg_pool = db::postgres::create_pool(&config.database.url)
+                .await
+                .expect("Failed to create PostgreSQL pool");
+            let status = db::postgres::migration_status(&pg_pool)
+                .await
+                .expect("Failed to read migration status");
+            print_migration_status("PostgreSQL", &status);
+        }
+        return Ok(());
+    }
+
     // Run migrations
     db::run_migrations(&pool)
         .await
         .expect("Failed to run migrations");
 
-    println!("Starting server at http://0.0.0.0:8080");
+    let version = db::current_version(&pool)
+        .await
+        .expect("Failed to read schema version");
+    log::info!("Database schema version: {:?}", version);
+
+    println!(
+        "Starting server at http://{} (public URL: {})",
+        config.server.bind_addr,
+        config.base_url()
+    );
+
+    let bind_addr = config.server.bind_addr.clone();
+    let workers = config.server.workers;
+    // 短链接存储由 database.url 的协议决定，重定向查询依次经过布隆过滤器和进程内缓存
+    let link_cache = web::Data::new(LinkCache::new(&config.cache));
+    let backend = store::connect(&config.database.url, &pool)
+        .await
+        .expect("Failed to initialize link store");
+    let backfilled = store::backfill_all(backend.as_ref())
+        .await
+        .expect("Failed to backfill links");
+    if backfilled > 0 {
+        log::info!(
+            "Backfilled {} links created before the last migration",
+            backfilled
+        );
+    }
+    let linked = UrlService::backfill_click_links(backend.as_ref(), &pool)
+        .await
+        .expect("Failed to backfill clicks");
+    if linked > 0 {
+        log::info!(
+            "Linked {} clicks recorded before the last migration",
+            linked
+        );
+    }
+    let mut link_store: Arc<dyn LinkStore> = Arc::new(CachedLinkStore::new(
+        backend,
+        link_cache.clone().into_inner(),
+    ));
+    if config.bloom_filter.enabled {
+        link_store = Arc::new(
+            BloomLinkStore::build(link_store, &config.bloom_filter)
+                .await
+                .expect("Failed to build bloom filter"),
+        );
+    }
+    let link_store: web::Data<dyn LinkStore> = web::Data::from(link_store);
+    if config.reaper.enabled {
+        tokio::spawn(ReaperService::run(
+            link_store.clone().into_inner(),
+            config.reaper.clone(),
+        ));
+    }
+    // 访问记录由后台任务批量写入，队列在所有 worker 之间共享
+    let click_log = web::Data::new(ClickLog::spawn(pool.clone(), &config.clicks));
+    let pending_clicks = click_log.clone();
+    // 令牌桶在所有 worker 之间共享
+    let rate_limiters = web::Data::new(rate_limit::RateLimiters::new(&config.rate_limit));
+    // 自动生成短码的长度和冲突统计在 HTTP 和 gRPC 之间共享
+    let code_allocator = web::Data::new(CodeAllocator::new());
+    let url_policy =
+        web::Data::new(UrlPolicy::load(&config).expect("Failed to load URL blocklist"));
+    let config = web::Data::new(config);
+
+    // gRPC 服务与 HTTP 服务共用连接池和配置
+    let grpc_server = config.grpc.enabled.then(|| {
+        println!("Starting gRPC server at {}", config.grpc.bind_addr);
+        grpc::serve(
+            pool.clone(),
+            link_store.clone().into_inner(),
+            code_allocator.clone().into_inner(),
+            url_policy.clone().into_inner(),
+            config.clone().into_inner(),
+        )
+    });
 
-    HttpServer::new(move || {
+    let mut server = HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(pool.clone()))
+            .app_data(link_store.clone())
+            .app_data(link_cache.clone())
+            .app_data(click_log.clone())
+            .app_data(rate_limiters.clone())
+            .app_data(code_allocator.clone())
+            .app_data(url_policy.clone())
+            .app_data(config.clone())
+            .app_data(error::json_config())
+            .app_data(error::query_config())
+            .app_data(error::path_config())
+            .wrap(from_fn(rate_limit::rate_limit))
             .wrap(Logger::default())
-            .wrap(
-                actix_cors::Cors::default()
-                    .allow_any_origin()
-                    .allow_any_method()
-                    .allow_any_header(),
-            )
+            .wrap(cors(&config))
             .service(
                 web::scope("/api")
+                    .wrap(from_fn(negotiate::encode_errors))
+                    .service(auth::register)
+                    .service(auth::login)
+                    .service(auth::logout)
+                    .service(auth::me)
+                    .service(tokens::create_api_token)
+                    .service(tokens::get_api_tokens)
+                    .service(tokens::revoke_api_token)
                     .service(shorten::create_short_url)
+                    .service(shorten::batch_create_short_urls)
+                    .service(transfer::export_links)
+                    .service(transfer::import_links)
                     .service(shorten::get_short_urls)
+                    .service(shorten::get_url_stats)
+                    .service(shorten::update_short_url)
                     .service(shorten::delete_short_url)
-                    .service(qrcode::redirect_qrcode),
+                    .service(qrcode::get_qrcode)
+                    .service(cache::get_cache_stats)
+                    .service(admin::reap_expired_links)
+                    .service(admin::get_keyspace_stats)
+                    .service(admin::reload_url_policy),
             )
             .service(redirect::redirect_to_long_url)
             .route(
@@ -43,8 +176,46 @@ async fn main() -> std::io::Result<()> {
                 web::get()
                     .to(|| async { HttpResponse::Ok().body("Short URL Service is running!") }),
             )
-    })
-    .bind("0.0.0.0:8080")?
-    .run()
-    .await
+    });
+
+    if let Some(workers) = workers {
+        server = server.workers(workers);
+    }
+
+    let http_server = server.bind(bind_addr)?.run();
+
+    // 任一服务退出时整个进程退出，退出前写入队列中剩余的访问记录
+    let result = match grpc_server {
+        Some(grpc_server) => tokio::select! {
+            result = http_server => result,
+            result = grpc_server => result.map_err(std::io::Error::other),
+        },
+        None => http_server.await,
+    };
+    pending_clicks.flush().await;
+    result
+}
+
+fn print_migration_status(database: &str, status: &db::MigrationStatus) {
+    let version = |v: Option<i64>| v.map_or_else(|| "none".to_string(), |v| v.to_string());
+    println!("[{}]", database);
+    println!("Current version: {}", version(status.current));
+    println!("Latest version: {}", version(status.latest));
+    println!("Pending migrations: {:?}", status.pending);
+}
+
+fn cors(config: &AppConfig) -> actix_cors::Cors {
+    let cors = actix_cors::Cors::default()
+        .allow_any_method()
+        .allow_any_header();
+
+    if config.allows_any_origin() {
+        cors.allow_any_origin()
+    } else {
+        config
+            .cors
+            .allowed_origins
+            .iter()
+            .fold(cors, |cors, origin| cors.allowed_origin(origin))
+    }
 }
@@ -16,6 +16,63 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for abi::ShortUrl {
     }
 }
 
+#[cfg(feature = "postgres")]
+impl sqlx::FromRow<'_, sqlx::postgres::PgRow> for abi::ShortUrl {
+    fn from_row(row: &sqlx::postgres::PgRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+        Ok(abi::ShortUrl {
+            id: row.get("id"),
+            long_url: row.get("long_url"),
+            short_code: row.get("short_code"),
+            created_at: row.get("created_at"),
+            expires_at: row.get("expires_at"),
+            user_id: row.get("user_id"),
+        })
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for abi::Click {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+        Ok(abi::Click {
+            id: row.get("id"),
+            short_code: row.get("short_code"),
+            clicked_at: row.get("clicked_at"),
+            referrer: row.get("referrer"),
+            user_agent: row.get("user_agent"),
+            ip: row.get("ip"),
+            accept_language: row.get("accept_language"),
+            link_id: row.get::<Option<i64>, _>("link_id").unwrap_or_default(),
+        })
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for abi::User {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+        Ok(abi::User {
+            id: row.get("id"),
+            username: row.get("username"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for abi::ApiToken {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+        let scopes: String = row.get("scopes");
+        Ok(abi::ApiToken {
+            id: row.get("id"),
+            name: row.get("name"),
+            scopes: scopes.split_whitespace().map(str::to_string).collect(),
+            created_at: row.get("created_at"),
+            expires_at: row.get("expires_at"),
+            last_used_at: row.get("last_used_at"),
+        })
+    }
+}
+
 // 为 Protobuf 类型实现转换方法
 impl abi::ShortUrl {
     pub fn to_response(&self, base_url: &str) -> abi::ShortUrlResponse {
@@ -26,6 +83,7 @@ impl abi::ShortUrl {
             short_url: format!("{}/{}", base_url, self.short_code),
             created_at: self.created_at.clone(),
             expires_at: self.expires_at.clone(),
+            reused: false,
         }
     }
 }
@@ -54,13 +112,210 @@ impl Serialize for abi::ShortUrlResponse {
         S: serde::Serializer,
     {
         use serde::ser::SerializeStruct;
-        let mut state = serializer.serialize_struct("ShortUrlResponse", 6)?;
+        let mut state = serializer.serialize_struct("ShortUrlResponse", 7)?;
         state.serialize_field("id", &self.id)?;
         state.serialize_field("long_url", &self.long_url)?;
         state.serialize_field("short_code", &self.short_code)?;
         state.serialize_field("short_url", &self.short_url)?;
         state.serialize_field("created_at", &self.created_at)?;
         state.serialize_field("expires_at", &self.expires_at)?;
+        state.serialize_field("reused", &self.reused)?;
+        state.end()
+    }
+}
+
+impl Serialize for abi::GetUserUrlsResponse {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("GetUserUrlsResponse", 3)?;
+        state.serialize_field("urls", &self.urls)?;
+        // 没有下一页时为 null
+        let next_page_token = Some(&self.next_page_token).filter(|token| !token.is_empty());
+        state.serialize_field("next_page_token", &next_page_token)?;
+        state.serialize_field("total", &self.total)?;
+        state.end()
+    }
+}
+
+impl Serialize for abi::BatchItemResult {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("BatchItemResult", 5)?;
+        state.serialize_field("index", &self.index)?;
+        state.serialize_field("status", &self.status)?;
+        state.serialize_field("short_url", &self.short_url)?;
+        // 成功时为 null
+        let error_code = Some(&self.error_code).filter(|code| !code.is_empty());
+        state.serialize_field("error_code", &error_code)?;
+        let message = Some(&self.message).filter(|message| !message.is_empty());
+        state.serialize_field("message", &message)?;
+        state.end()
+    }
+}
+
+impl Serialize for abi::BatchCreateShortUrlsResponse {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("BatchCreateShortUrlsResponse", 5)?;
+        state.serialize_field("results", &self.results)?;
+        state.serialize_field("committed", &self.committed)?;
+        state.serialize_field("created", &self.created)?;
+        state.serialize_field("failed", &self.failed)?;
+        state.serialize_field("reused", &self.reused)?;
+        state.end()
+    }
+}
+
+impl Serialize for abi::Click {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("Click", 8)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("short_code", &self.short_code)?;
+        state.serialize_field("clicked_at", &self.clicked_at)?;
+        state.serialize_field("referrer", &self.referrer)?;
+        state.serialize_field("user_agent", &self.user_agent)?;
+        state.serialize_field("ip", &self.ip)?;
+        state.serialize_field("accept_language", &self.accept_language)?;
+        state.serialize_field("link_id", &self.link_id)?;
+        state.end()
+    }
+}
+
+impl Serialize for abi::TimeBucket {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("TimeBucket", 2)?;
+        state.serialize_field("start", &self.start)?;
+        state.serialize_field("clicks", &self.clicks)?;
+        state.end()
+    }
+}
+
+impl Serialize for abi::BreakdownEntry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("BreakdownEntry", 2)?;
+        state.serialize_field("key", &self.key)?;
+        state.serialize_field("clicks", &self.clicks)?;
+        state.end()
+    }
+}
+
+impl Serialize for abi::UrlStats {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("UrlStats", 12)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("short_code", &self.short_code)?;
+        state.serialize_field("total_clicks", &self.total_clicks)?;
+        state.serialize_field("range_clicks", &self.range_clicks)?;
+        state.serialize_field("granularity", &self.granularity)?;
+        state.serialize_field("from", &self.from)?;
+        state.serialize_field("to", &self.to)?;
+        state.serialize_field("series", &self.series)?;
+        state.serialize_field("top_referrers", &self.top_referrers)?;
+        state.serialize_field("browsers", &self.browsers)?;
+        state.serialize_field("operating_systems", &self.operating_systems)?;
+        state.serialize_field("devices", &self.devices)?;
+        state.end()
+    }
+}
+
+impl Serialize for abi::ErrorResponse {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("ErrorResponse", 4)?;
+        state.serialize_field("success", &self.success)?;
+        state.serialize_field("message", &self.message)?;
+        state.serialize_field("error_code", &self.error_code)?;
+        if self.field.is_empty() {
+            state.skip_field("field")?;
+        } else {
+            state.serialize_field("field", &self.field)?;
+        }
+        state.end()
+    }
+}
+
+impl Serialize for abi::User {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("User", 3)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("username", &self.username)?;
+        state.serialize_field("created_at", &self.created_at)?;
+        state.end()
+    }
+}
+
+impl Serialize for abi::LoginResponse {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("LoginResponse", 3)?;
+        state.serialize_field("token", &self.token)?;
+        state.serialize_field("expires_at", &self.expires_at)?;
+        state.serialize_field("user", &self.user)?;
+        state.end()
+    }
+}
+
+impl Serialize for abi::ApiToken {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("ApiToken", 6)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("scopes", &self.scopes)?;
+        state.serialize_field("created_at", &self.created_at)?;
+        state.serialize_field("expires_at", &self.expires_at)?;
+        state.serialize_field("last_used_at", &self.last_used_at)?;
+        state.end()
+    }
+}
+
+impl Serialize for abi::CreateApiTokenResponse {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("CreateApiTokenResponse", 2)?;
+        state.serialize_field("token", &self.token)?;
+        state.serialize_field("api_token", &self.api_token)?;
         state.end()
     }
 }
@@ -76,7 +331,10 @@ impl<'de> Deserialize<'de> for abi::CreateShortUrlRequest {
             long_url: String,
             custom_code: Option<String>,
             timeout: Option<i64>,
+            // 由认证信息决定，请求体中的值会被忽略
+            #[serde(default)]
             user_id: String,
+            dedupe: Option<bool>,
         }
 
         let helper = Helper::deserialize(deserializer)?;
@@ -85,6 +343,138 @@ impl<'de> Deserialize<'de> for abi::CreateShortUrlRequest {
             custom_code: helper.custom_code,
             timeout: helper.timeout,
             user_id: helper.user_id,
+            dedupe: helper.dedupe,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for abi::BatchCreateShortUrlsRequest {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Helper {
+            items: Vec<abi::CreateShortUrlRequest>,
+            mode: Option<String>,
+        }
+
+        let helper = Helper::deserialize(deserializer)?;
+        Ok(abi::BatchCreateShortUrlsRequest {
+            items: helper.items,
+            mode: helper.mode.unwrap_or_default(),
+        })
+    }
+}
+
+// PATCH /api/urls/{id} 的请求体，id 来自路径。
+// expires_at 为 null 时清除过期时间，不传则保持不变
+impl<'de> Deserialize<'de> for abi::UpdateShortUrlRequest {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Helper {
+            long_url: Option<String>,
+            short_code: Option<String>,
+            timeout: Option<i64>,
+            #[serde(default, deserialize_with = "present")]
+            expires_at: Option<Option<String>>,
+            #[serde(default)]
+            user_id: String,
+        }
+
+        // 字段存在时（包括 null）为 Some
+        fn present<'de, D>(deserializer: D) -> Result<Option<Option<String>>, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            Option::<String>::deserialize(deserializer).map(Some)
+        }
+
+        let helper = Helper::deserialize(deserializer)?;
+        Ok(abi::UpdateShortUrlRequest {
+            id: 0,
+            long_url: helper.long_url,
+            short_code: helper.short_code,
+            timeout: helper.timeout,
+            expires_at: helper
+                .expires_at
+                .map(|expires_at| expires_at.unwrap_or_default()),
+            user_id: helper.user_id,
+        })
+    }
+}
+
+// GET /api/urls 的查询参数
+impl<'de> Deserialize<'de> for abi::ListShortUrlsRequest {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Helper {
+            page_size: Option<u32>,
+            page_token: Option<String>,
+            status: Option<String>,
+            created_from: Option<String>,
+            created_to: Option<String>,
+            domain: Option<String>,
+            code_prefix: Option<String>,
+            sort: Option<String>,
+        }
+
+        let helper = Helper::deserialize(deserializer)?;
+        Ok(abi::ListShortUrlsRequest {
+            page_size: helper.page_size.unwrap_or_default(),
+            page_token: helper.page_token.unwrap_or_default(),
+            status: helper.status.unwrap_or_default(),
+            created_from: helper.created_from.unwrap_or_default(),
+            created_to: helper.created_to.unwrap_or_default(),
+            domain: helper.domain.unwrap_or_default(),
+            code_prefix: helper.code_prefix.unwrap_or_default(),
+            sort: helper.sort.unwrap_or_default(),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for abi::Credentials {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Helper {
+            username: String,
+            password: String,
+        }
+
+        let helper = Helper::deserialize(deserializer)?;
+        Ok(abi::Credentials {
+            username: helper.username,
+            password: helper.password,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for abi::CreateApiTokenRequest {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Helper {
+            name: String,
+            scopes: Vec<String>,
+            expires_in: Option<i64>,
+        }
+
+        let helper = Helper::deserialize(deserializer)?;
+        Ok(abi::CreateApiTokenRequest {
+            name: helper.name,
+            scopes: helper.scopes,
+            expires_in: helper.expires_in,
         })
     }
 }
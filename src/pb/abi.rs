@@ -29,6 +29,53 @@ pub struct CreateShortUrlRequest {
     pub timeout: ::core::option::Option<i64>,
     #[prost(string, tag = "4")]
     pub user_id: ::prost::alloc::string::String,
+    /// 复用该用户指向同一 URL 的有效链接，未设置时使用服务端配置
+    #[prost(bool, optional, tag = "5")]
+    pub dedupe: ::core::option::Option<bool>,
+}
+/// 批量创建短链接请求
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchCreateShortUrlsRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub items: ::prost::alloc::vec::Vec<CreateShortUrlRequest>,
+    /// all_or_nothing（默认）/ best_effort
+    #[prost(string, tag = "2")]
+    pub mode: ::prost::alloc::string::String,
+}
+/// 批量创建中单个条目的结果
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchItemResult {
+    /// 条目在请求中的位置
+    #[prost(uint32, tag = "1")]
+    pub index: u32,
+    /// created / reused（开启去重时复用已有链接）/ failed / aborted（批次失败未创建）
+    #[prost(string, tag = "2")]
+    pub status: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub short_url: ::core::option::Option<ShortUrlResponse>,
+    /// 失败原因，与错误响应中的 error_code 相同
+    #[prost(string, tag = "4")]
+    pub error_code: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub message: ::prost::alloc::string::String,
+}
+/// 批量创建短链接响应
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchCreateShortUrlsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub results: ::prost::alloc::vec::Vec<BatchItemResult>,
+    /// all_or_nothing 模式下有条目失败时为 false
+    #[prost(bool, tag = "2")]
+    pub committed: bool,
+    #[prost(uint32, tag = "3")]
+    pub created: u32,
+    #[prost(uint32, tag = "4")]
+    pub failed: u32,
+    #[prost(uint32, tag = "5")]
+    pub reused: u32,
 }
 /// 短链接响应
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -47,13 +94,42 @@ pub struct ShortUrlResponse {
     pub created_at: ::prost::alloc::string::String,
     #[prost(string, optional, tag = "6")]
     pub expires_at: ::core::option::Option<::prost::alloc::string::String>,
+    /// 是否复用了已有链接
+    #[prost(bool, tag = "7")]
+    pub reused: bool,
 }
 /// 获取用户链接响应
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GetUserUrlsResponse {
     #[prost(message, repeated, tag = "1")]
-    pub urls: ::prost::alloc::vec::Vec<ShortUrl>,
+    pub urls: ::prost::alloc::vec::Vec<ShortUrlResponse>,
+    /// 下一页的 page_token，没有更多数据时为空
+    #[prost(string, tag = "2")]
+    pub next_page_token: ::prost::alloc::string::String,
+    /// 符合过滤条件的链接总数
+    #[prost(int64, tag = "3")]
+    pub total: i64,
+}
+/// 修改短链接请求，未设置的字段保持不变
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateShortUrlRequest {
+    #[prost(int64, tag = "1")]
+    pub id: i64,
+    #[prost(string, optional, tag = "2")]
+    pub long_url: ::core::option::Option<::prost::alloc::string::String>,
+    /// 新短码，不能已被占用
+    #[prost(string, optional, tag = "3")]
+    pub short_code: ::core::option::Option<::prost::alloc::string::String>,
+    /// 从现在起的过期时间（秒）
+    #[prost(int64, optional, tag = "4")]
+    pub timeout: ::core::option::Option<i64>,
+    /// 过期时间（RFC 3339），空字符串表示清除，不能与 timeout 同时设置
+    #[prost(string, optional, tag = "5")]
+    pub expires_at: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, tag = "6")]
+    pub user_id: ::prost::alloc::string::String,
 }
 /// 删除链接请求
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -72,7 +148,7 @@ pub struct ApiResponse {
     pub success: bool,
     #[prost(string, tag = "2")]
     pub message: ::prost::alloc::string::String,
-    #[prost(oneof = "api_response::Data", tags = "3, 4")]
+    #[prost(oneof = "api_response::Data", tags = "3, 4, 5, 6")]
     pub data: ::core::option::Option<api_response::Data>,
 }
 /// Nested message and enum types in `ApiResponse`.
@@ -84,6 +160,10 @@ pub mod api_response {
         ShortUrl(super::ShortUrlResponse),
         #[prost(message, tag = "4")]
         UserUrls(super::GetUserUrlsResponse),
+        #[prost(message, tag = "5")]
+        UrlStats(super::UrlStats),
+        #[prost(message, tag = "6")]
+        Batch(super::BatchCreateShortUrlsResponse),
     }
 }
 /// 错误响应
@@ -96,4 +176,963 @@ pub struct ErrorResponse {
     pub message: ::prost::alloc::string::String,
     #[prost(string, tag = "3")]
     pub error_code: ::prost::alloc::string::String,
+    /// 校验失败的请求字段，仅 INVALID_FIELD 错误设置
+    #[prost(string, tag = "4")]
+    pub field: ::prost::alloc::string::String,
+}
+/// 访问记录
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Click {
+    #[prost(int64, tag = "1")]
+    pub id: i64,
+    #[prost(string, tag = "2")]
+    pub short_code: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub clicked_at: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "4")]
+    pub referrer: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "5")]
+    pub user_agent: ::core::option::Option<::prost::alloc::string::String>,
+    /// 匿名化后的 IP
+    #[prost(string, optional, tag = "6")]
+    pub ip: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "7")]
+    pub accept_language: ::core::option::Option<::prost::alloc::string::String>,
+    /// 所属链接的 id，短码修改后访问记录仍然关联
+    #[prost(int64, tag = "8")]
+    pub link_id: i64,
+}
+/// 时间序列中的一个时间桶
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TimeBucket {
+    /// 桶起始时间（UTC）
+    #[prost(string, tag = "1")]
+    pub start: ::prost::alloc::string::String,
+    #[prost(int64, tag = "2")]
+    pub clicks: i64,
+}
+/// 按维度聚合的访问计数
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BreakdownEntry {
+    #[prost(string, tag = "1")]
+    pub key: ::prost::alloc::string::String,
+    #[prost(int64, tag = "2")]
+    pub clicks: i64,
+}
+/// 短链接访问统计
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UrlStats {
+    #[prost(int64, tag = "1")]
+    pub id: i64,
+    #[prost(string, tag = "2")]
+    pub short_code: ::prost::alloc::string::String,
+    /// 链接创建以来的总访问量
+    #[prost(int64, tag = "3")]
+    pub total_clicks: i64,
+    /// 查询区间内的访问量
+    #[prost(int64, tag = "4")]
+    pub range_clicks: i64,
+    /// hour / day / week
+    #[prost(string, tag = "5")]
+    pub granularity: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub from: ::prost::alloc::string::String,
+    #[prost(string, tag = "7")]
+    pub to: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "8")]
+    pub series: ::prost::alloc::vec::Vec<TimeBucket>,
+    #[prost(message, repeated, tag = "9")]
+    pub top_referrers: ::prost::alloc::vec::Vec<BreakdownEntry>,
+    #[prost(message, repeated, tag = "10")]
+    pub browsers: ::prost::alloc::vec::Vec<BreakdownEntry>,
+    #[prost(message, repeated, tag = "11")]
+    pub operating_systems: ::prost::alloc::vec::Vec<BreakdownEntry>,
+    #[prost(message, repeated, tag = "12")]
+    pub devices: ::prost::alloc::vec::Vec<BreakdownEntry>,
+}
+/// 用户
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct User {
+    #[prost(int64, tag = "1")]
+    pub id: i64,
+    #[prost(string, tag = "2")]
+    pub username: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub created_at: ::prost::alloc::string::String,
+}
+/// 注册 / 登录请求
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Credentials {
+    #[prost(string, tag = "1")]
+    pub username: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub password: ::prost::alloc::string::String,
+}
+/// 登录响应
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoginResponse {
+    /// Bearer Token，仅在登录时返回一次
+    #[prost(string, tag = "1")]
+    pub token: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub expires_at: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub user: ::core::option::Option<User>,
+}
+/// 个人 API Token，不包含明文
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ApiToken {
+    #[prost(int64, tag = "1")]
+    pub id: i64,
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    /// links:read / links:write / links:delete / stats:read
+    #[prost(string, repeated, tag = "3")]
+    pub scopes: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, tag = "4")]
+    pub created_at: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "5")]
+    pub expires_at: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "6")]
+    pub last_used_at: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// 创建 API Token 请求
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateApiTokenRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "2")]
+    pub scopes: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// 有效期（秒），为空表示永不过期
+    #[prost(int64, optional, tag = "3")]
+    pub expires_in: ::core::option::Option<i64>,
+}
+/// 创建 API Token 响应
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateApiTokenResponse {
+    /// 明文 Token，仅在创建时返回一次
+    #[prost(string, tag = "1")]
+    pub token: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub api_token: ::core::option::Option<ApiToken>,
+}
+/// 按 ID 获取短链接
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetShortUrlRequest {
+    #[prost(int64, tag = "1")]
+    pub id: i64,
+}
+/// 列出当前用户的短链接，所有字段可选，为空表示不过滤
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListShortUrlsRequest {
+    /// 每页数量，默认 50，最大 500
+    #[prost(uint32, tag = "1")]
+    pub page_size: u32,
+    /// 上一页返回的 next_page_token
+    #[prost(string, tag = "2")]
+    pub page_token: ::prost::alloc::string::String,
+    /// active / expired
+    #[prost(string, tag = "3")]
+    pub status: ::prost::alloc::string::String,
+    /// 创建时间下限（含），RFC 3339 或 YYYY-MM-DD
+    #[prost(string, tag = "4")]
+    pub created_from: ::prost::alloc::string::String,
+    /// 创建时间上限（不含）
+    #[prost(string, tag = "5")]
+    pub created_to: ::prost::alloc::string::String,
+    /// long_url 的主机名
+    #[prost(string, tag = "6")]
+    pub domain: ::prost::alloc::string::String,
+    /// 短码前缀，区分大小写
+    #[prost(string, tag = "7")]
+    pub code_prefix: ::prost::alloc::string::String,
+    /// created_at / short_code / long_url，前缀 - 表示倒序，默认 -created_at
+    #[prost(string, tag = "8")]
+    pub sort: ::prost::alloc::string::String,
+}
+/// 删除短链接响应
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteShortUrlResponse {}
+/// 解析短码
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResolveRequest {
+    #[prost(string, tag = "1")]
+    pub short_code: ::prost::alloc::string::String,
+}
+/// 解析结果
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResolveResponse {
+    #[prost(string, tag = "1")]
+    pub long_url: ::prost::alloc::string::String,
+}
+/// Generated client implementations.
+pub mod short_url_service_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    /// 短链接 gRPC 服务，认证方式与 HTTP 接口相同：metadata 中携带 authorization: Bearer <token>
+    #[derive(Debug, Clone)]
+    pub struct ShortUrlServiceClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl ShortUrlServiceClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> ShortUrlServiceClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> ShortUrlServiceClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            ShortUrlServiceClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        pub async fn create(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateShortUrlRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ShortUrlResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/abi.ShortUrlService/Create",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("abi.ShortUrlService", "Create"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn batch_create(
+            &mut self,
+            request: impl tonic::IntoRequest<super::BatchCreateShortUrlsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::BatchCreateShortUrlsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/abi.ShortUrlService/BatchCreate",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("abi.ShortUrlService", "BatchCreate"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetShortUrlRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ShortUrlResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/abi.ShortUrlService/Get");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("abi.ShortUrlService", "Get"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListShortUrlsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetUserUrlsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/abi.ShortUrlService/List");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("abi.ShortUrlService", "List"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn update(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateShortUrlRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ShortUrlResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/abi.ShortUrlService/Update",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("abi.ShortUrlService", "Update"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteShortUrlRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteShortUrlResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/abi.ShortUrlService/Delete",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("abi.ShortUrlService", "Delete"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// 无需认证，与 HTTP 重定向一致
+        pub async fn resolve(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ResolveRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ResolveResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/abi.ShortUrlService/Resolve",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("abi.ShortUrlService", "Resolve"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod short_url_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with ShortUrlServiceServer.
+    #[async_trait]
+    pub trait ShortUrlService: Send + Sync + 'static {
+        async fn create(
+            &self,
+            request: tonic::Request<super::CreateShortUrlRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ShortUrlResponse>,
+            tonic::Status,
+        >;
+        async fn batch_create(
+            &self,
+            request: tonic::Request<super::BatchCreateShortUrlsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::BatchCreateShortUrlsResponse>,
+            tonic::Status,
+        >;
+        async fn get(
+            &self,
+            request: tonic::Request<super::GetShortUrlRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ShortUrlResponse>,
+            tonic::Status,
+        >;
+        async fn list(
+            &self,
+            request: tonic::Request<super::ListShortUrlsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetUserUrlsResponse>,
+            tonic::Status,
+        >;
+        async fn update(
+            &self,
+            request: tonic::Request<super::UpdateShortUrlRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ShortUrlResponse>,
+            tonic::Status,
+        >;
+        async fn delete(
+            &self,
+            request: tonic::Request<super::DeleteShortUrlRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteShortUrlResponse>,
+            tonic::Status,
+        >;
+        /// 无需认证，与 HTTP 重定向一致
+        async fn resolve(
+            &self,
+            request: tonic::Request<super::ResolveRequest>,
+        ) -> std::result::Result<tonic::Response<super::ResolveResponse>, tonic::Status>;
+    }
+    /// 短链接 gRPC 服务，认证方式与 HTTP 接口相同：metadata 中携带 authorization: Bearer <token>
+    #[derive(Debug)]
+    pub struct ShortUrlServiceServer<T: ShortUrlService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: ShortUrlService> ShortUrlServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for ShortUrlServiceServer<T>
+    where
+        T: ShortUrlService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/abi.ShortUrlService/Create" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateSvc<T: ShortUrlService>(pub Arc<T>);
+                    impl<
+                        T: ShortUrlService,
+                    > tonic::server::UnaryService<super::CreateShortUrlRequest>
+                    for CreateSvc<T> {
+                        type Response = super::ShortUrlResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateShortUrlRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ShortUrlService>::create(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/abi.ShortUrlService/BatchCreate" => {
+                    #[allow(non_camel_case_types)]
+                    struct BatchCreateSvc<T: ShortUrlService>(pub Arc<T>);
+                    impl<
+                        T: ShortUrlService,
+                    > tonic::server::UnaryService<super::BatchCreateShortUrlsRequest>
+                    for BatchCreateSvc<T> {
+                        type Response = super::BatchCreateShortUrlsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::BatchCreateShortUrlsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ShortUrlService>::batch_create(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BatchCreateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/abi.ShortUrlService/Get" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSvc<T: ShortUrlService>(pub Arc<T>);
+                    impl<
+                        T: ShortUrlService,
+                    > tonic::server::UnaryService<super::GetShortUrlRequest>
+                    for GetSvc<T> {
+                        type Response = super::ShortUrlResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetShortUrlRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ShortUrlService>::get(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/abi.ShortUrlService/List" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListSvc<T: ShortUrlService>(pub Arc<T>);
+                    impl<
+                        T: ShortUrlService,
+                    > tonic::server::UnaryService<super::ListShortUrlsRequest>
+                    for ListSvc<T> {
+                        type Response = super::GetUserUrlsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListShortUrlsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ShortUrlService>::list(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/abi.ShortUrlService/Update" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdateSvc<T: ShortUrlService>(pub Arc<T>);
+                    impl<
+                        T: ShortUrlService,
+                    > tonic::server::UnaryService<super::UpdateShortUrlRequest>
+                    for UpdateSvc<T> {
+                        type Response = super::ShortUrlResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateShortUrlRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ShortUrlService>::update(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/abi.ShortUrlService/Delete" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteSvc<T: ShortUrlService>(pub Arc<T>);
+                    impl<
+                        T: ShortUrlService,
+                    > tonic::server::UnaryService<super::DeleteShortUrlRequest>
+                    for DeleteSvc<T> {
+                        type Response = super::DeleteShortUrlResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteShortUrlRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ShortUrlService>::delete(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/abi.ShortUrlService/Resolve" => {
+                    #[allow(non_camel_case_types)]
+                    struct ResolveSvc<T: ShortUrlService>(pub Arc<T>);
+                    impl<
+                        T: ShortUrlService,
+                    > tonic::server::UnaryService<super::ResolveRequest>
+                    for ResolveSvc<T> {
+                        type Response = super::ResolveResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ResolveRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ShortUrlService>::resolve(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ResolveSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: ShortUrlService> Clone for ShortUrlServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    impl<T: ShortUrlService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(Arc::clone(&self.0))
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: ShortUrlService> tonic::server::NamedService for ShortUrlServiceServer<T> {
+        const NAME: &'static str = "abi.ShortUrlService";
+    }
 }
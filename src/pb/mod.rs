@@ -1,5 +1,9 @@
 pub mod abi;
 
+// build.rs 生成的 abi.proto 文件描述符，供 gRPC 反射服务使用
+pub const FILE_DESCRIPTOR_SET: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/abi_descriptor.bin"));
+
 use serde::Serialize;
 
 #[derive(Serialize)]
@@ -27,6 +31,51 @@ impl<T> ApiResponse<T> {
     }
 }
 
+// 可以放入 Protobuf ApiResponse.data 的类型
+pub trait IntoProtoData {
+    fn into_proto_data(self) -> Option<abi::api_response::Data>;
+}
+
+impl IntoProtoData for () {
+    fn into_proto_data(self) -> Option<abi::api_response::Data> {
+        None
+    }
+}
+
+impl IntoProtoData for abi::ShortUrlResponse {
+    fn into_proto_data(self) -> Option<abi::api_response::Data> {
+        Some(abi::api_response::Data::ShortUrl(self))
+    }
+}
+
+impl IntoProtoData for abi::GetUserUrlsResponse {
+    fn into_proto_data(self) -> Option<abi::api_response::Data> {
+        Some(abi::api_response::Data::UserUrls(self))
+    }
+}
+
+impl IntoProtoData for abi::BatchCreateShortUrlsResponse {
+    fn into_proto_data(self) -> Option<abi::api_response::Data> {
+        Some(abi::api_response::Data::Batch(self))
+    }
+}
+
+impl IntoProtoData for abi::UrlStats {
+    fn into_proto_data(self) -> Option<abi::api_response::Data> {
+        Some(abi::api_response::Data::UrlStats(self))
+    }
+}
+
+impl<T: IntoProtoData> ApiResponse<T> {
+    pub fn into_proto(self) -> abi::ApiResponse {
+        abi::ApiResponse {
+            success: self.success,
+            message: self.message,
+            data: self.data.and_then(IntoProtoData::into_proto_data),
+        }
+    }
+}
+
 impl From<abi::ShortUrlResponse> for ApiResponse<abi::ShortUrlResponse> {
     fn from(res: abi::ShortUrlResponse) -> Self {
         ApiResponse {
@@ -0,0 +1,190 @@
+use crate::config::{ShortCodeConfig, MAX_CODE_LENGTH};
+use crate::error::AppError;
+use crate::store::LinkStore;
+use serde::Serialize;
+use std::sync::Mutex;
+
+// 每统计这么多次插入计算一次冲突率
+pub const COLLISION_WINDOW: u64 = 100;
+// 自动生成的短码被占用时最多尝试的次数
+pub const MAX_CODE_ATTEMPTS: u32 = 10;
+
+// 自动生成短码的长度状态，在所有请求之间共享。由数据库的 UNIQUE 约束判断冲突，
+// 一个统计窗口内的冲突率超过 short_code.growth_threshold 时长度加一。重启后从配置的长度重新开始
+#[derive(Debug, Default)]
+pub struct CodeAllocator {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    // 在 short_code.length 之上增加的位数
+    extra_length: usize,
+    window_attempts: u64,
+    window_collisions: u64,
+    // 当前长度下的累计次数，长度增加时清零
+    attempts: u64,
+    collisions: u64,
+}
+
+// 当前长度下的短码空间使用情况
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeyspaceStats {
+    pub length: usize,
+    pub alphabet_size: usize,
+    // alphabet_size 的 length 次方
+    pub capacity: f64,
+    // 已有的该长度短码数，包括同样长度的自定义短码
+    pub used: u64,
+    pub utilisation: f64,
+    pub attempts: u64,
+    pub collisions: u64,
+    pub collision_rate: f64,
+}
+
+impl CodeAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 当前自动生成短码的长度
+    pub fn length(&self, config: &ShortCodeConfig) -> usize {
+        let state = self.state.lock().unwrap();
+        (config.length + state.extra_length).min(MAX_CODE_LENGTH)
+    }
+
+    // 记录一次插入是否因短码冲突失败，返回长度是否增加
+    pub fn record(&self, config: &ShortCodeConfig, collided: bool) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.attempts += 1;
+        state.window_attempts += 1;
+        if collided {
+            state.collisions += 1;
+            state.window_collisions += 1;
+        }
+        if state.window_attempts < COLLISION_WINDOW {
+            return false;
+        }
+
+        let rate = state.window_collisions as f64 / state.window_attempts as f64;
+        state.window_attempts = 0;
+        state.window_collisions = 0;
+        if rate <= config.growth_threshold || config.length + state.extra_length >= MAX_CODE_LENGTH
+        {
+            return false;
+        }
+
+        state.extra_length += 1;
+        state.attempts = 0;
+        state.collisions = 0;
+        log::warn!(
+            "Short code collision rate {:.2} exceeded {}, generating {} character codes",
+            rate,
+            config.growth_threshold,
+            config.length + state.extra_length
+        );
+        true
+    }
+
+    pub async fn stats(
+        &self,
+        store: &dyn LinkStore,
+        config: &ShortCodeConfig,
+    ) -> Result<KeyspaceStats, AppError> {
+        let length = self.length(config);
+        let used = store.count_by_code_length(length).await?;
+        let (attempts, collisions) = {
+            let state = self.state.lock().unwrap();
+            (state.attempts, state.collisions)
+        };
+
+        let alphabet_size = config.alphabet.chars().count();
+        let capacity = (alphabet_size as f64).powi(length as i32);
+        Ok(KeyspaceStats {
+            length,
+            alphabet_size,
+            capacity,
+            used,
+            utilisation: used as f64 / capacity,
+            attempts,
+            collisions,
+            collision_rate: if attempts == 0 {
+                0.0
+            } else {
+                collisions as f64 / attempts as f64
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{MemoryLinkStore, NewLink};
+
+    #[test]
+    fn test_length_grows_past_threshold() {
+        let allocator = CodeAllocator::new();
+        let mut config = ShortCodeConfig {
+            growth_threshold: 0.2,
+            ..ShortCodeConfig::default()
+        };
+        assert_eq!(allocator.length(&config), 6);
+
+        // 20% collisions is not above the threshold
+        for i in 0..COLLISION_WINDOW {
+            assert!(!allocator.record(&config, i % 5 == 0));
+        }
+        assert_eq!(allocator.length(&config), 6);
+
+        let grew: Vec<bool> = (0..COLLISION_WINDOW)
+            .map(|i| allocator.record(&config, i % 4 == 0))
+            .collect();
+        assert_eq!(grew.iter().filter(|grew| **grew).count(), 1);
+        assert!(grew[COLLISION_WINDOW as usize - 1]);
+        assert_eq!(allocator.length(&config), 7);
+
+        // Never grows past the maximum length
+        config.length = MAX_CODE_LENGTH;
+        for _ in 0..COLLISION_WINDOW {
+            assert!(!allocator.record(&config, true));
+        }
+        assert_eq!(allocator.length(&config), MAX_CODE_LENGTH);
+    }
+
+    #[tokio::test]
+    async fn test_keyspace_stats() {
+        let store = MemoryLinkStore::new();
+        for short_code in ["abc", "abcd", "wxyz"] {
+            store
+                .insert(NewLink {
+                    id: None,
+                    long_url: "https://example.com".to_string(),
+                    short_code: short_code.to_string(),
+                    created_at: "2024-01-01T00:00:00+00:00".to_string(),
+                    expires_at: None,
+                    user_id: "alice".to_string(),
+                })
+                .await
+                .unwrap();
+        }
+
+        let allocator = CodeAllocator::new();
+        let config = ShortCodeConfig {
+            length: 4,
+            alphabet: "0123456789".to_string(),
+            ..ShortCodeConfig::default()
+        };
+        allocator.record(&config, true);
+        allocator.record(&config, false);
+
+        let stats = allocator.stats(&store, &config).await.unwrap();
+        assert_eq!(stats.length, 4);
+        assert_eq!(stats.alphabet_size, 10);
+        assert_eq!(stats.capacity, 10_000.0);
+        assert_eq!(stats.used, 2);
+        assert_eq!(stats.utilisation, 0.0002);
+        assert_eq!((stats.attempts, stats.collisions), (2, 1));
+        assert_eq!(stats.collision_rate, 0.5);
+    }
+}
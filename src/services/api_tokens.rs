@@ -0,0 +1,375 @@
+use crate::error::AppError;
+use crate::pb::abi::{ApiToken, CreateApiTokenRequest, CreateApiTokenResponse, User};
+use crate::services::auth::{generate_token, hash_token};
+use crate::services::expires_after;
+use chrono::Utc;
+use sqlx::{Row, SqlitePool};
+use std::fmt;
+use std::str::FromStr;
+
+// 用于和登录会话 Token 区分
+pub const API_TOKEN_PREFIX: &str = "sut_";
+const MAX_NAME_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Scope {
+    LinksRead,
+    LinksWrite,
+    LinksDelete,
+    StatsRead,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 4] = [
+        Scope::LinksRead,
+        Scope::LinksWrite,
+        Scope::LinksDelete,
+        Scope::StatsRead,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::LinksRead => "links:read",
+            Scope::LinksWrite => "links:write",
+            Scope::LinksDelete => "links:delete",
+            Scope::StatsRead => "stats:read",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Scope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| format!("Unknown scope: {}", s))
+    }
+}
+
+// 通过 API Token 认证得到的信息
+#[derive(Debug, Clone)]
+pub struct TokenIdentity {
+    pub token_id: i64,
+    pub user: User,
+    pub scopes: Vec<Scope>,
+}
+
+pub struct ApiTokenService;
+
+impl ApiTokenService {
+    // 创建 Token，明文只在返回值中出现一次
+    pub async fn create(
+        pool: &SqlitePool,
+        user_id: i64,
+        request: CreateApiTokenRequest,
+    ) -> Result<CreateApiTokenResponse, AppError> {
+        let name = request.name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+            return Err(AppError::InvalidRequest(format!(
+                "Token name must be 1-{} characters",
+                MAX_NAME_LENGTH
+            )));
+        }
+
+        let scopes = parse_scopes(&request.scopes)?;
+        if scopes.is_empty() {
+            return Err(AppError::InvalidRequest(
+                "At least one scope is required".to_string(),
+            ));
+        }
+
+        let now = Utc::now();
+        let expires_at = match request.expires_in {
+            Some(secs) if secs <= 0 => {
+                return Err(AppError::InvalidRequest(
+                    "expires_in must be positive".to_string(),
+                ))
+            }
+            Some(secs) => Some(expires_after(now, secs, "expires_in")?.to_rfc3339()),
+            None => None,
+        };
+
+        let token = format!("{}{}", API_TOKEN_PREFIX, generate_token());
+        let scopes = scopes
+            .iter()
+            .map(Scope::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO api_tokens (user_id, name, token_hash, scopes, created_at, expires_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(user_id)
+        .bind(name)
+        .bind(hash_token(&token))
+        .bind(&scopes)
+        .bind(now.to_rfc3339())
+        .bind(&expires_at)
+        .execute(pool)
+        .await?;
+
+        let api_token = sqlx::query_as::<_, ApiToken>("SELECT * FROM api_tokens WHERE id = ?")
+            .bind(result.last_insert_rowid())
+            .fetch_one(pool)
+            .await?;
+
+        Ok(CreateApiTokenResponse {
+            token,
+            api_token: Some(api_token),
+        })
+    }
+
+    pub async fn list(pool: &SqlitePool, user_id: i64) -> Result<Vec<ApiToken>, AppError> {
+        let tokens = sqlx::query_as::<_, ApiToken>(
+            "SELECT * FROM api_tokens WHERE user_id = ? ORDER BY created_at DESC, id DESC",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(tokens)
+    }
+
+    pub async fn revoke(pool: &SqlitePool, user_id: i64, id: i64) -> Result<(), AppError> {
+        let owner: Option<i64> = sqlx::query("SELECT user_id FROM api_tokens WHERE id = ?")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?
+            .map(|row| row.get("user_id"));
+
+        match owner {
+            None => Err(AppError::NotFound("API token")),
+            Some(owner) if owner != user_id => {
+                Err(AppError::Forbidden("API token is not owned by user"))
+            }
+            Some(_) => {
+                sqlx::query("DELETE FROM api_tokens WHERE id = ?")
+                    .bind(id)
+                    .execute(pool)
+                    .await?;
+                Ok(())
+            }
+        }
+    }
+
+    // 校验 Token 并记录最近使用时间，无效或过期时返回 None
+    pub async fn authenticate(
+        pool: &SqlitePool,
+        token: &str,
+    ) -> Result<Option<TokenIdentity>, AppError> {
+        let now = Utc::now().to_rfc3339();
+        let row = sqlx::query(
+            r#"
+            SELECT api_tokens.id AS token_id, api_tokens.scopes,
+                   users.id, users.username, users.created_at
+            FROM api_tokens JOIN users ON users.id = api_tokens.user_id
+            WHERE api_tokens.token_hash = ?
+              AND (api_tokens.expires_at IS NULL OR api_tokens.expires_at > ?)
+            "#,
+        )
+        .bind(hash_token(token))
+        .bind(&now)
+        .fetch_optional(pool)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let token_id: i64 = row.get("token_id");
+        sqlx::query("UPDATE api_tokens SET last_used_at = ? WHERE id = ?")
+            .bind(&now)
+            .bind(token_id)
+            .execute(pool)
+            .await?;
+
+        // 忽略已不再支持的 scope
+        let scopes: String = row.get("scopes");
+        let scopes = scopes
+            .split_whitespace()
+            .filter_map(|s| s.parse().ok())
+            .collect();
+
+        Ok(Some(TokenIdentity {
+            token_id,
+            user: User {
+                id: row.get("id"),
+                username: row.get("username"),
+                created_at: row.get("created_at"),
+            },
+            scopes,
+        }))
+    }
+}
+
+// 解析并去重，保持固定顺序
+fn parse_scopes(scopes: &[String]) -> Result<Vec<Scope>, AppError> {
+    let mut parsed = scopes
+        .iter()
+        .map(|s| s.trim().parse::<Scope>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(AppError::InvalidRequest)?;
+    parsed.sort();
+    parsed.dedup();
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::db;
+    use crate::services::auth::AuthService;
+
+    async fn setup_test_db() -> SqlitePool {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+
+        db::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        pool
+    }
+
+    async fn create_user(pool: &SqlitePool, username: &str) -> User {
+        AuthService::register(pool, &AppConfig::default(), username, "correct-horse")
+            .await
+            .expect("Failed to register")
+    }
+
+    fn request(scopes: &[&str], expires_in: Option<i64>) -> CreateApiTokenRequest {
+        CreateApiTokenRequest {
+            name: "ci".to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            expires_in,
+        }
+    }
+
+    #[test]
+    fn test_parse_scope() {
+        assert_eq!("links:write".parse::<Scope>(), Ok(Scope::LinksWrite));
+        assert_eq!(Scope::StatsRead.to_string(), "stats:read");
+        assert!("links:admin".parse::<Scope>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_and_authenticate() {
+        let pool = setup_test_db().await;
+        let user = create_user(&pool, "alice").await;
+
+        let created = ApiTokenService::create(
+            &pool,
+            user.id,
+            request(&["stats:read", "links:write", "links:write"], None),
+        )
+        .await
+        .expect("Failed to create token");
+
+        assert!(created.token.starts_with(API_TOKEN_PREFIX));
+        let api_token = created.api_token.unwrap();
+        assert_eq!(api_token.scopes, vec!["links:write", "stats:read"]);
+        assert!(api_token.expires_at.is_none());
+
+        // Only the hash is stored
+        let stored: String = sqlx::query("SELECT token_hash FROM api_tokens WHERE id = ?")
+            .bind(api_token.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+            .get("token_hash");
+        assert_eq!(stored, hash_token(&created.token));
+
+        let identity = ApiTokenService::authenticate(&pool, &created.token)
+            .await
+            .unwrap()
+            .expect("Token should be valid");
+        assert_eq!(identity.user.username, "alice");
+        assert_eq!(identity.scopes, vec![Scope::LinksWrite, Scope::StatsRead]);
+
+        let listed = ApiTokenService::list(&pool, user.id).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].last_used_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_create_validation() {
+        let pool = setup_test_db().await;
+        let user = create_user(&pool, "alice").await;
+
+        for bad in [
+            request(&[], None),
+            request(&["links:admin"], None),
+            request(&["links:read"], Some(0)),
+            // Far beyond any representable date
+            request(&["links:read"], Some(i64::MAX)),
+            CreateApiTokenRequest {
+                name: " ".to_string(),
+                ..request(&["links:read"], None)
+            },
+        ] {
+            let result = ApiTokenService::create(&pool, user.id, bad).await;
+            assert!(matches!(result, Err(AppError::InvalidRequest(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_revoke() {
+        let pool = setup_test_db().await;
+        let alice = create_user(&pool, "alice").await;
+        let bob = create_user(&pool, "bob").await;
+
+        let created = ApiTokenService::create(&pool, alice.id, request(&["links:read"], None))
+            .await
+            .unwrap();
+        let id = created.api_token.unwrap().id;
+
+        let result = ApiTokenService::revoke(&pool, bob.id, id).await;
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+
+        ApiTokenService::revoke(&pool, alice.id, id)
+            .await
+            .expect("Failed to revoke");
+        assert!(ApiTokenService::authenticate(&pool, &created.token)
+            .await
+            .unwrap()
+            .is_none());
+
+        let result = ApiTokenService::revoke(&pool, alice.id, id).await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_is_rejected() {
+        let pool = setup_test_db().await;
+        let user = create_user(&pool, "alice").await;
+
+        let created = ApiTokenService::create(&pool, user.id, request(&["links:read"], Some(60)))
+            .await
+            .unwrap();
+        assert!(created.api_token.unwrap().expires_at.is_some());
+
+        sqlx::query("UPDATE api_tokens SET expires_at = ?")
+            .bind((Utc::now() - chrono::Duration::seconds(1)).to_rfc3339())
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert!(ApiTokenService::authenticate(&pool, &created.token)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,320 @@
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::pb::abi::{LoginResponse, User};
+use argon2::password_hash::{PasswordHash, SaltString};
+use argon2::{Argon2, PasswordHasher, PasswordVerifier};
+use chrono::Utc;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::{Row, SqlitePool};
+
+const MIN_USERNAME_LENGTH: usize = 3;
+const MAX_USERNAME_LENGTH: usize = 32;
+// argon2 对超长输入同样有效，但没有必要接受
+const MAX_PASSWORD_LENGTH: usize = 128;
+// 用户名不存在时同样校验一次密码，使响应时间与密码错误时一致，避免据此判断用户名是否存在。
+// 参数与 Argon2::default() 相同
+const DUMMY_PASSWORD_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$LeGJChnmwksyHjB3cjMrTA$lf66UvTKf/FDII0J3riUoIvQgbdPYTzEYpVF8wrfmc8";
+
+pub struct AuthService;
+
+impl AuthService {
+    pub async fn register(
+        pool: &SqlitePool,
+        config: &AppConfig,
+        username: &str,
+        password: &str,
+    ) -> Result<User, AppError> {
+        validate_username(username)?;
+        if password.len() < config.auth.min_password_length || password.len() > MAX_PASSWORD_LENGTH
+        {
+            return Err(AppError::InvalidRequest(format!(
+                "Password must be between {} and {} characters",
+                config.auth.min_password_length, MAX_PASSWORD_LENGTH
+            )));
+        }
+
+        let password_hash = hash_password(password)?;
+
+        let result =
+            sqlx::query("INSERT INTO users (username, password_hash, created_at) VALUES (?, ?, ?)")
+                .bind(username)
+                .bind(&password_hash)
+                .bind(Utc::now().to_rfc3339())
+                .execute(pool)
+                .await;
+
+        let id = match result {
+            Ok(result) => result.last_insert_rowid(),
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                return Err(AppError::UsernameTaken)
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        Self::get_user(pool, id).await
+    }
+
+    // 登录成功后创建新的会话，返回的明文 Token 不会再次出现
+    pub async fn login(
+        pool: &SqlitePool,
+        config: &AppConfig,
+        username: &str,
+        password: &str,
+    ) -> Result<LoginResponse, AppError> {
+        let invalid = AppError::Unauthorized("Invalid username or password");
+
+        let row = sqlx::query("SELECT id, password_hash FROM users WHERE username = ?")
+            .bind(username)
+            .fetch_optional(pool)
+            .await?;
+
+        let password_hash: String = match &row {
+            Some(row) => row.get("password_hash"),
+            None => DUMMY_PASSWORD_HASH.to_string(),
+        };
+        let verified = verify_password(password, &password_hash);
+        let Some(row) = row.filter(|_| verified) else {
+            return Err(invalid);
+        };
+
+        let user_id: i64 = row.get("id");
+        let now = Utc::now();
+        let expires_at = now + chrono::Duration::seconds(config.auth.session_ttl_secs);
+        let token = generate_token();
+
+        // 顺便清理该用户已过期的会话
+        sqlx::query("DELETE FROM sessions WHERE user_id = ? AND expires_at <= ?")
+            .bind(user_id)
+            .bind(now.to_rfc3339())
+            .execute(pool)
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO sessions (user_id, token_hash, created_at, expires_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(user_id)
+        .bind(hash_token(&token))
+        .bind(now.to_rfc3339())
+        .bind(expires_at.to_rfc3339())
+        .execute(pool)
+        .await?;
+
+        Ok(LoginResponse {
+            token,
+            expires_at: expires_at.to_rfc3339(),
+            user: Some(Self::get_user(pool, user_id).await?),
+        })
+    }
+
+    pub async fn logout(pool: &SqlitePool, token: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM sessions WHERE token_hash = ?")
+            .bind(hash_token(token))
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // 根据 Bearer Token 解析当前用户
+    pub async fn authenticate(pool: &SqlitePool, token: &str) -> Result<User, AppError> {
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            SELECT users.id, users.username, users.created_at
+            FROM sessions JOIN users ON users.id = sessions.user_id
+            WHERE sessions.token_hash = ? AND sessions.expires_at > ?
+            "#,
+        )
+        .bind(hash_token(token))
+        .bind(Utc::now().to_rfc3339())
+        .fetch_optional(pool)
+        .await?;
+
+        user.ok_or(AppError::Unauthorized("Invalid or expired token"))
+    }
+
+    pub async fn get_user(pool: &SqlitePool, id: i64) -> Result<User, AppError> {
+        sqlx::query_as::<_, User>("SELECT id, username, created_at FROM users WHERE id = ?")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or(AppError::NotFound("User"))
+    }
+}
+
+fn validate_username(username: &str) -> Result<(), AppError> {
+    let valid_length = (MIN_USERNAME_LENGTH..=MAX_USERNAME_LENGTH).contains(&username.len());
+    let valid_chars = username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+
+    if valid_length && valid_chars {
+        Ok(())
+    } else {
+        Err(AppError::InvalidRequest(format!(
+            "Username must be {}-{} characters of letters, digits, '_', '-' or '.'",
+            MIN_USERNAME_LENGTH, MAX_USERNAME_LENGTH
+        )))
+    }
+}
+
+fn hash_password(password: &str) -> Result<String, AppError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| AppError::Internal(format!("Failed to hash password: {}", e)))
+}
+
+fn verify_password(password: &str, password_hash: &str) -> bool {
+    PasswordHash::new(password_hash)
+        .map(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+// 32 字节随机数的十六进制表示
+pub(crate) fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+pub(crate) fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    async fn setup_test_db() -> SqlitePool {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+
+        db::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_register_and_login() {
+        let pool = setup_test_db().await;
+        let config = AppConfig::default();
+
+        let user = AuthService::register(&pool, &config, "alice", "correct-horse")
+            .await
+            .expect("Failed to register");
+        assert_eq!(user.username, "alice");
+
+        // The password is never stored in plain text
+        let stored: String = sqlx::query("SELECT password_hash FROM users WHERE id = ?")
+            .bind(user.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+            .get("password_hash");
+        assert!(stored.starts_with("$argon2"));
+
+        let login = AuthService::login(&pool, &config, "alice", "correct-horse")
+            .await
+            .expect("Failed to login");
+        assert_eq!(login.token.len(), 64);
+        assert_eq!(login.user.as_ref().unwrap().id, user.id);
+
+        let authenticated = AuthService::authenticate(&pool, &login.token)
+            .await
+            .expect("Failed to authenticate");
+        assert_eq!(authenticated.username, "alice");
+    }
+
+    #[tokio::test]
+    async fn test_register_validation() {
+        let pool = setup_test_db().await;
+        let config = AppConfig::default();
+
+        let short_password = AuthService::register(&pool, &config, "alice", "short").await;
+        assert!(matches!(short_password, Err(AppError::InvalidRequest(_))));
+
+        let bad_username = AuthService::register(&pool, &config, "a/b", "correct-horse").await;
+        assert!(matches!(bad_username, Err(AppError::InvalidRequest(_))));
+
+        AuthService::register(&pool, &config, "alice", "correct-horse")
+            .await
+            .expect("Failed to register");
+        let duplicate = AuthService::register(&pool, &config, "alice", "another-pass").await;
+        assert!(matches!(duplicate, Err(AppError::UsernameTaken)));
+    }
+
+    #[tokio::test]
+    async fn test_login_with_wrong_password() {
+        let pool = setup_test_db().await;
+        let config = AppConfig::default();
+
+        AuthService::register(&pool, &config, "alice", "correct-horse")
+            .await
+            .expect("Failed to register");
+
+        let wrong = AuthService::login(&pool, &config, "alice", "wrong-horse").await;
+        assert!(matches!(wrong, Err(AppError::Unauthorized(_))));
+
+        let unknown = AuthService::login(&pool, &config, "bob", "correct-horse").await;
+        assert!(matches!(unknown, Err(AppError::Unauthorized(_))));
+
+        // Unknown usernames are checked against a real hash, even its own password fails
+        assert!(verify_password("dummy-password", DUMMY_PASSWORD_HASH));
+        let unknown = AuthService::login(&pool, &config, "bob", "dummy-password").await;
+        assert!(matches!(unknown, Err(AppError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn test_logout_revokes_token() {
+        let pool = setup_test_db().await;
+        let config = AppConfig::default();
+
+        AuthService::register(&pool, &config, "alice", "correct-horse")
+            .await
+            .expect("Failed to register");
+        let login = AuthService::login(&pool, &config, "alice", "correct-horse")
+            .await
+            .expect("Failed to login");
+
+        AuthService::logout(&pool, &login.token)
+            .await
+            .expect("Failed to logout");
+
+        let result = AuthService::authenticate(&pool, &login.token).await;
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn test_expired_session_is_rejected() {
+        let pool = setup_test_db().await;
+        let config = AppConfig::default();
+
+        AuthService::register(&pool, &config, "alice", "correct-horse")
+            .await
+            .expect("Failed to register");
+        let login = AuthService::login(&pool, &config, "alice", "correct-horse")
+            .await
+            .expect("Failed to login");
+
+        sqlx::query("UPDATE sessions SET expires_at = ?")
+            .bind((Utc::now() - chrono::Duration::seconds(1)).to_rfc3339())
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let result = AuthService::authenticate(&pool, &login.token).await;
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+    }
+}
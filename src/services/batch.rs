@@ -0,0 +1,165 @@
+use crate::error::AppError;
+use crate::pb::abi::{BatchCreateShortUrlsResponse, BatchItemResult, ShortUrl, ShortUrlResponse};
+use crate::store::{batch_committed, LinkStore, NewLink};
+use std::str::FromStr;
+
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BatchMode {
+    // 任意一条失败则全部不创建
+    #[default]
+    AllOrNothing,
+    // 跳过失败的条目，其余照常创建
+    BestEffort,
+}
+
+impl FromStr for BatchMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all_or_nothing" => Ok(BatchMode::AllOrNothing),
+            "best_effort" => Ok(BatchMode::BestEffort),
+            _ => Err(format!("Invalid mode: {}", s)),
+        }
+    }
+}
+
+// 未设置时为 all_or_nothing
+pub fn parse_mode(value: &str) -> Result<BatchMode, String> {
+    match value.trim() {
+        "" => Ok(BatchMode::default()),
+        value => value.parse(),
+    }
+}
+
+#[derive(Debug)]
+pub enum ItemOutcome {
+    Created(ShortUrl),
+    // 开启去重时复用的已有链接
+    Reused(ShortUrl),
+    Failed(AppError),
+    // 条目本身没有问题，但批次失败未创建
+    Aborted,
+}
+
+#[derive(Debug)]
+pub struct BatchResult {
+    pub committed: bool,
+    pub items: Vec<ItemOutcome>,
+}
+
+impl BatchResult {
+    pub fn created(&self) -> usize {
+        self.count(|item| matches!(item, ItemOutcome::Created(_)))
+    }
+
+    pub fn failed(&self) -> usize {
+        self.count(|item| matches!(item, ItemOutcome::Failed(_)))
+    }
+
+    pub fn reused(&self) -> usize {
+        self.count(|item| matches!(item, ItemOutcome::Reused(_)))
+    }
+
+    fn count(&self, f: impl Fn(&ItemOutcome) -> bool) -> usize {
+        self.items.iter().filter(|item| f(item)).count()
+    }
+
+    pub fn to_response(&self, base_url: &str) -> BatchCreateShortUrlsResponse {
+        let results = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let mut result = BatchItemResult {
+                    index: index as u32,
+                    ..Default::default()
+                };
+                match item {
+                    ItemOutcome::Created(url) => {
+                        result.status = "created".to_string();
+                        result.short_url = Some(url.to_response(base_url));
+                    }
+                    ItemOutcome::Reused(url) => {
+                        result.status = "reused".to_string();
+                        result.short_url = Some(ShortUrlResponse {
+                            reused: true,
+                            ..url.to_response(base_url)
+                        });
+                    }
+                    ItemOutcome::Failed(err) => {
+                        let error = err.to_error_response();
+                        result.status = "failed".to_string();
+                        result.error_code = error.error_code;
+                        result.message = error.message;
+                    }
+                    ItemOutcome::Aborted => result.status = "aborted".to_string(),
+                }
+                result
+            })
+            .collect();
+
+        BatchCreateShortUrlsResponse {
+            results,
+            committed: self.committed,
+            created: self.created() as u32,
+            failed: self.failed() as u32,
+            reused: self.reused() as u32,
+        }
+    }
+}
+
+// 插入校验通过的条目，links 与请求中的条目一一对应
+pub async fn insert(
+    store: &dyn LinkStore,
+    links: Vec<Result<NewLink, AppError>>,
+    mode: BatchMode,
+) -> Result<BatchResult, AppError> {
+    let atomic = mode == BatchMode::AllOrNothing;
+
+    // 校验失败时不必访问数据库
+    if atomic && links.iter().any(Result::is_err) {
+        let items = links
+            .into_iter()
+            .map(|link| match link {
+                Ok(_) => ItemOutcome::Aborted,
+                Err(err) => ItemOutcome::Failed(err),
+            })
+            .collect();
+        return Ok(BatchResult {
+            committed: false,
+            items,
+        });
+    }
+
+    let mut items = Vec::with_capacity(links.len());
+    let mut valid = Vec::new();
+    for link in links {
+        match link {
+            Ok(link) => {
+                valid.push(link);
+                items.push(None);
+            }
+            Err(err) => items.push(Some(ItemOutcome::Failed(err))),
+        }
+    }
+
+    let results = store.insert_batch(valid, atomic).await?;
+    let committed = batch_committed(&results, atomic);
+    let mut results = results.into_iter();
+    let items = items
+        .into_iter()
+        .map(|item| {
+            item.unwrap_or_else(|| match results.next() {
+                Some(Ok(_)) if !committed => ItemOutcome::Aborted,
+                Some(Ok(url)) => ItemOutcome::Created(url),
+                Some(Err(err)) => ItemOutcome::Failed(err),
+                None => ItemOutcome::Aborted,
+            })
+        })
+        .collect();
+
+    Ok(BatchResult { committed, items })
+}
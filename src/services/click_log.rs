@@ -0,0 +1,125 @@
+use crate::config::ClickLogConfig;
+use crate::pb::abi::Click;
+use crate::services::UrlService;
+use sqlx::SqlitePool;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+enum Command {
+    Record(Click),
+    // 写完之前收到的访问记录后通知
+    Flush(oneshot::Sender<()>),
+}
+
+// 访问记录的写入队列。重定向只把记录放进有界队列，由后台任务批量写入 clicks 表，
+// 队列满时丢弃新的记录，不阻塞跳转
+#[derive(Clone)]
+pub struct ClickLog {
+    sender: mpsc::Sender<Command>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl ClickLog {
+    // 启动后台写入任务，所有 ClickLog 副本被释放后任务结束
+    pub fn spawn(pool: SqlitePool, config: &ClickLogConfig) -> Self {
+        let (sender, receiver) = mpsc::channel(config.queue_size);
+        let dropped = Arc::new(AtomicU64::new(0));
+        tokio::spawn(Self::run(
+            pool,
+            receiver,
+            config.batch_size,
+            dropped.clone(),
+        ));
+        Self { sender, dropped }
+    }
+
+    pub fn record(&self, click: Click) {
+        if self.sender.try_send(Command::Record(click)).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // 等待队列中已有的访问记录写入数据库
+    pub async fn flush(&self) {
+        let (done, wait) = oneshot::channel();
+        if self.sender.send(Command::Flush(done)).await.is_ok() {
+            let _ = wait.await;
+        }
+    }
+
+    async fn run(
+        pool: SqlitePool,
+        mut receiver: mpsc::Receiver<Command>,
+        batch_size: usize,
+        dropped: Arc<AtomicU64>,
+    ) {
+        let mut commands = Vec::with_capacity(batch_size);
+        // 每次取出队列中已有的记录（最多 batch_size 条），在一个事务中写入
+        while receiver.recv_many(&mut commands, batch_size).await > 0 {
+            let mut clicks = Vec::with_capacity(commands.len());
+            let mut waiters = Vec::new();
+            for command in commands.drain(..) {
+                match command {
+                    Command::Record(click) => clicks.push(click),
+                    Command::Flush(done) => waiters.push(done),
+                }
+            }
+
+            if let Err(err) = UrlService::record_clicks(&pool, &clicks).await {
+                log::warn!("Failed to record {} clicks: {}", clicks.len(), err);
+            }
+            let count = dropped.swap(0, Ordering::Relaxed);
+            if count > 0 {
+                log::warn!("Dropped {} clicks because the click queue was full", count);
+            }
+            for done in waiters {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use chrono::Utc;
+
+    fn click(short_code: &str) -> Click {
+        Click {
+            id: 0,
+            short_code: short_code.to_string(),
+            clicked_at: Utc::now().to_rfc3339(),
+            referrer: None,
+            user_agent: None,
+            ip: None,
+            accept_language: None,
+            link_id: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clicks_are_written_in_batches() {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+        db::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        let config = ClickLogConfig {
+            queue_size: 100,
+            batch_size: 2,
+        };
+        let click_log = ClickLog::spawn(pool.clone(), &config);
+        for _ in 0..5 {
+            click_log.record(click("queued"));
+        }
+        click_log.flush().await;
+
+        let clicks = UrlService::get_clicks(&pool, "queued").await.unwrap();
+        assert_eq!(clicks.len(), 5);
+        assert_eq!(click_log.dropped.load(Ordering::Relaxed), 0);
+    }
+}
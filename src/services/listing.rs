@@ -0,0 +1,244 @@
+use crate::pb::abi::{GetUserUrlsResponse, ListShortUrlsRequest, ShortUrl};
+use crate::services::stats::parse_timestamp;
+use crate::store::{Cursor, LinkPage, LinkStatus, ListQuery, Sort, SortField};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+pub const MAX_PAGE_SIZE: usize = 500;
+
+// 一页链接，next_page_token 为 None 表示没有更多数据
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrlPage {
+    pub urls: Vec<ShortUrl>,
+    pub next_page_token: Option<String>,
+    pub total: i64,
+}
+
+impl UrlPage {
+    pub fn new(page: LinkPage, sort: &Sort) -> Self {
+        Self {
+            urls: page.urls,
+            next_page_token: page.next.map(|cursor| encode_page_token(sort, &cursor)),
+            total: page.total,
+        }
+    }
+
+    pub fn to_response(&self, base_url: &str) -> GetUserUrlsResponse {
+        GetUserUrlsResponse {
+            urls: self
+                .urls
+                .iter()
+                .map(|url| url.to_response(base_url))
+                .collect(),
+            next_page_token: self.next_page_token.clone().unwrap_or_default(),
+            total: self.total,
+        }
+    }
+}
+
+// page_token 的内容，记录排序方式以免翻页时排序被修改
+#[derive(Serialize, Deserialize)]
+struct PageToken {
+    sort: String,
+    value: String,
+    id: i64,
+}
+
+// 将请求参数转换为存储层的查询条件，空字符串表示未设置
+pub fn parse_request(
+    request: &ListShortUrlsRequest,
+    now: DateTime<Utc>,
+) -> Result<ListQuery, String> {
+    let limit = match request.page_size as usize {
+        0 => DEFAULT_PAGE_SIZE,
+        size if size <= MAX_PAGE_SIZE => size,
+        _ => return Err(format!("page_size must be at most {}", MAX_PAGE_SIZE)),
+    };
+
+    let status = match non_empty(&request.status) {
+        None => LinkStatus::All,
+        Some("active") => LinkStatus::Active,
+        Some("expired") => LinkStatus::Expired,
+        Some(status) => return Err(format!("Invalid status: {}", status)),
+    };
+
+    let timestamp = |value: &str| -> Result<Option<String>, String> {
+        non_empty(value)
+            .map(|value| parse_timestamp(value).map(|ts| ts.to_rfc3339()))
+            .transpose()
+    };
+    let created_from = timestamp(&request.created_from)?;
+    let created_to = timestamp(&request.created_to)?;
+
+    let sort = match non_empty(&request.sort) {
+        Some(sort) => parse_sort(sort)?,
+        None => Sort::default(),
+    };
+    let cursor = non_empty(&request.page_token)
+        .map(|token| decode_page_token(token, &sort))
+        .transpose()?;
+
+    Ok(ListQuery {
+        status,
+        now: now.to_rfc3339(),
+        created_from,
+        created_to,
+        domain: non_empty(&request.domain).map(str::to_lowercase),
+        code_prefix: non_empty(&request.code_prefix).map(str::to_string),
+        sort,
+        cursor,
+        limit,
+    })
+}
+
+fn non_empty(value: &str) -> Option<&str> {
+    Some(value.trim()).filter(|value| !value.is_empty())
+}
+
+fn parse_sort(value: &str) -> Result<Sort, String> {
+    let (descending, name) = match value.strip_prefix('-') {
+        Some(name) => (true, name),
+        None => (false, value),
+    };
+    let field = match name {
+        "created_at" => SortField::CreatedAt,
+        "short_code" => SortField::ShortCode,
+        "long_url" => SortField::LongUrl,
+        _ => return Err(format!("Invalid sort: {}", value)),
+    };
+
+    Ok(Sort { field, descending })
+}
+
+fn sort_name(sort: &Sort) -> String {
+    let prefix = if sort.descending { "-" } else { "" };
+    format!("{}{}", prefix, sort.field.column())
+}
+
+fn encode_page_token(sort: &Sort, cursor: &Cursor) -> String {
+    let token = PageToken {
+        sort: sort_name(sort),
+        value: cursor.value.clone(),
+        id: cursor.id,
+    };
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(&token).unwrap_or_default())
+}
+
+fn decode_page_token(token: &str, sort: &Sort) -> Result<Cursor, String> {
+    let token: PageToken = URL_SAFE_NO_PAD
+        .decode(token)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| "Invalid page_token".to_string())?;
+
+    if token.sort != sort_name(sort) {
+        return Err("page_token was issued for a different sort".to_string());
+    }
+
+    Ok(Cursor {
+        value: token.value,
+        id: token.id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        "2024-06-01T00:00:00Z".parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_request_defaults() {
+        let query = parse_request(&ListShortUrlsRequest::default(), now()).unwrap();
+        assert_eq!(query.limit, DEFAULT_PAGE_SIZE);
+        assert_eq!(query.status, LinkStatus::All);
+        assert_eq!(query.sort, Sort::default());
+        assert_eq!(query.now, "2024-06-01T00:00:00+00:00");
+        assert!(query.cursor.is_none());
+    }
+
+    #[test]
+    fn test_parse_request_filters() {
+        let request = ListShortUrlsRequest {
+            page_size: 20,
+            status: "expired".to_string(),
+            created_from: "2024-01-01".to_string(),
+            created_to: "2024-02-01T08:00:00+08:00".to_string(),
+            domain: "Example.COM".to_string(),
+            code_prefix: "Ab".to_string(),
+            sort: "short_code".to_string(),
+            ..ListShortUrlsRequest::default()
+        };
+        let query = parse_request(&request, now()).unwrap();
+        assert_eq!(query.limit, 20);
+        assert_eq!(query.status, LinkStatus::Expired);
+        assert_eq!(
+            query.created_from.as_deref(),
+            Some("2024-01-01T00:00:00+00:00")
+        );
+        assert_eq!(
+            query.created_to.as_deref(),
+            Some("2024-02-01T00:00:00+00:00")
+        );
+        assert_eq!(query.domain.as_deref(), Some("example.com"));
+        assert_eq!(query.code_prefix.as_deref(), Some("Ab"));
+        assert_eq!(
+            query.sort,
+            Sort {
+                field: SortField::ShortCode,
+                descending: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_request_rejects_invalid_values() {
+        let invalid = [
+            ListShortUrlsRequest {
+                page_size: 501,
+                ..ListShortUrlsRequest::default()
+            },
+            ListShortUrlsRequest {
+                status: "deleted".to_string(),
+                ..ListShortUrlsRequest::default()
+            },
+            ListShortUrlsRequest {
+                sort: "-user_id".to_string(),
+                ..ListShortUrlsRequest::default()
+            },
+            ListShortUrlsRequest {
+                created_from: "yesterday".to_string(),
+                ..ListShortUrlsRequest::default()
+            },
+            ListShortUrlsRequest {
+                page_token: "not-a-token".to_string(),
+                ..ListShortUrlsRequest::default()
+            },
+        ];
+        for request in invalid {
+            assert!(parse_request(&request, now()).is_err(), "{:?}", request);
+        }
+    }
+
+    #[test]
+    fn test_page_token_round_trip() {
+        let sort = Sort::default();
+        let cursor = Cursor {
+            value: "2024-01-01T00:00:00+00:00".to_string(),
+            id: 42,
+        };
+        let token = encode_page_token(&sort, &cursor);
+        assert_eq!(decode_page_token(&token, &sort), Ok(cursor));
+
+        // Tokens only work with the sort they were issued for
+        let other = Sort {
+            field: SortField::ShortCode,
+            descending: true,
+        };
+        assert!(decode_page_token(&token, &other).is_err());
+    }
+}
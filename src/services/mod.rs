@@ -1,141 +1,703 @@
-use crate::pb::abi::{CreateShortUrlRequest, ShortUrl};
-use crate::utils::short_code_generator::generate_short_code;
-use crate::utils::url_validator::is_valid_url;
-use chrono::Utc;
+pub mod allocator;
+pub mod api_tokens;
+pub mod auth;
+pub mod batch;
+pub mod click_log;
+pub mod listing;
+pub mod reaper;
+pub mod stats;
+pub mod transfer;
+pub mod url_policy;
+
+use crate::config::{AppConfig, CustomCodeConfig};
+use crate::error::AppError;
+use crate::pb::abi::{
+    BatchCreateShortUrlsRequest, Click, CreateShortUrlRequest, ListShortUrlsRequest, ShortUrl,
+    ShortUrlResponse, UpdateShortUrlRequest, UrlStats,
+};
+use crate::store::{LinkStore, LinkUpdate, NewLink, BACKFILL_BATCH_SIZE};
+use crate::utils::code_validator::validate_custom_code;
+use crate::utils::short_code_generator::CodeGenerator;
+use crate::utils::url_validator::{is_valid_url, url_hash};
+use allocator::{CodeAllocator, MAX_CODE_ATTEMPTS};
+use batch::{BatchMode, BatchResult, ItemOutcome};
+use chrono::{DateTime, Utc};
+use listing::UrlPage;
 use sqlx::{Row, SqlitePool};
+use stats::StatsRange;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use url_policy::UrlPolicy;
 
 pub struct UrlService;
 
+// 批量创建中的条目如何处理
+enum Slot {
+    // 待插入的第几条链接
+    Insert(usize),
+    // 复用用户已有的链接
+    Reused(ShortUrl),
+    // 复用同一批次中第几个条目新建的链接
+    SameAs(usize),
+}
+
+// 创建短链接的结果，reused 为 true 时返回的是已有链接
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreatedUrl {
+    pub url: ShortUrl,
+    pub reused: bool,
+}
+
+impl CreatedUrl {
+    pub fn to_response(&self, base_url: &str) -> ShortUrlResponse {
+        ShortUrlResponse {
+            reused: self.reused,
+            ..self.url.to_response(base_url)
+        }
+    }
+}
+
 impl UrlService {
+    // 开启去重时（请求中的 dedupe，未设置时取配置）返回该用户指向同一 URL 的有效链接，
+    // 过期时间保持不变。指定了自定义短码时总是创建新链接
     pub async fn create_short_url(
-        pool: &SqlitePool,
+        store: &dyn LinkStore,
+        allocator: &CodeAllocator,
+        url_policy: &UrlPolicy,
+        config: &AppConfig,
         request: CreateShortUrlRequest,
-    ) -> Result<ShortUrl, String> {
+    ) -> Result<CreatedUrl, AppError> {
+        let dedupe = request.dedupe.unwrap_or(config.short_code.dedupe);
+        if dedupe && request.custom_code.is_none() && is_valid_url(&request.long_url) {
+            // 已有的链接可能是在目标地址被屏蔽之前创建的
+            url_policy.check(&request.long_url)?;
+            let now = Utc::now().to_rfc3339();
+            if let Some(url) = store
+                .find_by_url(&request.user_id, &request.long_url, &now)
+                .await?
+            {
+                return Ok(CreatedUrl { url, reused: true });
+            }
+        }
+
+        let generator = config.code_generator(allocator.length(&config.short_code));
+        let generated = request.custom_code.is_none();
+        let id = if generated && generator.uses_row_id() {
+            store.reserve_ids(1).await?.pop()
+        } else {
+            None
+        };
+        let link = Self::new_link(
+            generator.as_ref(),
+            &config.custom_code,
+            url_policy,
+            request,
+            id,
+            &HashSet::new(),
+        )?;
+        let url = if generated {
+            Self::insert_generated(store, allocator, config, link).await?
+        } else {
+            store.insert(link).await?
+        };
+        Ok(CreatedUrl { url, reused: false })
+    }
+
+    // 批量创建，先逐条校验并分配短码，再在一个事务中插入
+    pub async fn batch_create_short_urls(
+        store: &dyn LinkStore,
+        allocator: &CodeAllocator,
+        url_policy: &UrlPolicy,
+        config: &AppConfig,
+        user_id: &str,
+        request: BatchCreateShortUrlsRequest,
+    ) -> Result<BatchResult, AppError> {
+        let mode = batch::parse_mode(&request.mode).map_err(AppError::InvalidRequest)?;
+        if request.items.is_empty() {
+            return Err(AppError::InvalidRequest(
+                "items must not be empty".to_string(),
+            ));
+        }
+        if request.items.len() > batch::MAX_BATCH_SIZE {
+            return Err(AppError::InvalidRequest(format!(
+                "At most {} items per batch",
+                batch::MAX_BATCH_SIZE
+            )));
+        }
+
+        // 开启去重的条目与单条创建相同，复用已有的链接；同一批次中重复的 URL 复用第一条新建的链接
+        let now = Utc::now().to_rfc3339();
+        let mut slots = Vec::with_capacity(request.items.len());
+        let mut first_by_url = HashMap::new();
+        let mut items = Vec::with_capacity(request.items.len());
+        for (index, mut item) in request.items.into_iter().enumerate() {
+            item.user_id = user_id.to_string();
+            let dedupe = item.dedupe.unwrap_or(config.short_code.dedupe)
+                && item.custom_code.is_none()
+                && is_valid_url(&item.long_url)
+                && url_policy.check(&item.long_url).is_ok();
+            if dedupe {
+                if let Some(url) = store.find_by_url(user_id, &item.long_url, &now).await? {
+                    slots.push(Slot::Reused(url));
+                    continue;
+                }
+                match first_by_url.entry(url_hash(&item.long_url)) {
+                    Entry::Occupied(first) => {
+                        slots.push(Slot::SameAs(*first.get()));
+                        continue;
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(index);
+                    }
+                }
+            }
+            slots.push(Slot::Insert(items.len()));
+            items.push(item);
+        }
+
+        // 同一批次中的短码也不能重复
+        let mut generator = config.code_generator(allocator.length(&config.short_code));
+        let mut ids = Vec::new().into_iter();
+        if generator.uses_row_id() {
+            let count = items
+                .iter()
+                .filter(|item| item.custom_code.is_none())
+                .count();
+            ids = store.reserve_ids(count).await?.into_iter();
+        }
+        let mut pending = HashSet::new();
+        let mut links = Vec::with_capacity(items.len());
+        // 自动生成、冲突后可以换一个重试的条目
+        let mut generated = Vec::with_capacity(items.len());
+        for item in items {
+            generated.push(item.custom_code.is_none());
+            let id = if item.custom_code.is_none() {
+                ids.next()
+            } else {
+                None
+            };
+            let link = Self::new_link(
+                generator.as_ref(),
+                &config.custom_code,
+                url_policy,
+                item,
+                id,
+                &pending,
+            );
+            if let Ok(link) = &link {
+                pending.insert(link.short_code.clone());
+            }
+            links.push(link);
+        }
+
+        // 自动生成的短码冲突时换一个重试。best_effort 只重新插入冲突的条目，
+        // all_or_nothing 只有在所有失败都是生成短码冲突时才整批重试
+        let mut valid: Vec<Option<NewLink>> = links
+            .iter()
+            .map(|link| link.as_ref().ok().cloned())
+            .collect();
+        let mut result = batch::insert(store, links, mode).await?;
+        let mut inserted: Vec<usize> = (0..valid.len()).collect();
+        for attempt in 1..=MAX_CODE_ATTEMPTS {
+            let mut collided = Vec::new();
+            for index in inserted {
+                if !generated[index] {
+                    continue;
+                }
+                let taken = match &result.items[index] {
+                    ItemOutcome::Failed(AppError::CodeTaken) => true,
+                    ItemOutcome::Failed(_) => continue,
+                    _ => false,
+                };
+                if taken {
+                    collided.push(index);
+                }
+                if allocator.record(&config.short_code, taken) {
+                    generator = config.code_generator(allocator.length(&config.short_code));
+                }
+            }
+
+            let atomic = mode == BatchMode::AllOrNothing;
+            if collided.is_empty()
+                || attempt == MAX_CODE_ATTEMPTS
+                || (atomic && collided.len() != result.failed())
+            {
+                break;
+            }
+            for &index in &collided {
+                if let Some(link) = &mut valid[index] {
+                    pending.remove(&link.short_code);
+                    link.short_code =
+                        Self::unused_code(generator.as_ref(), link.id, attempt, &pending)?;
+                    pending.insert(link.short_code.clone());
+                }
+            }
+
+            // 整批重试时所有条目都已通过校验
+            let retried: Vec<usize> = if atomic {
+                (0..valid.len()).collect()
+            } else {
+                collided
+            };
+            let links = retried
+                .iter()
+                .filter_map(|&index| valid[index].clone())
+                .map(Ok)
+                .collect();
+            let retried_result = batch::insert(store, links, mode).await?;
+            result.committed = retried_result.committed;
+            for (&index, item) in retried.iter().zip(retried_result.items) {
+                result.items[index] = item;
+            }
+            inserted = retried;
+        }
+        for (index, item) in result.items.iter_mut().enumerate() {
+            if generated[index] && matches!(item, ItemOutcome::Failed(AppError::CodeTaken)) {
+                *item = ItemOutcome::Failed(unique_code_error());
+            }
+        }
+
+        // 按请求中的顺序排列结果
+        let mut link_outcomes: Vec<Option<ItemOutcome>> =
+            result.items.into_iter().map(Some).collect();
+        let mut outcomes: Vec<ItemOutcome> = Vec::with_capacity(slots.len());
+        for slot in slots {
+            let outcome = match slot {
+                Slot::Insert(index) => link_outcomes[index].take().unwrap_or(ItemOutcome::Aborted),
+                Slot::Reused(url) => ItemOutcome::Reused(url),
+                Slot::SameAs(first) => match &outcomes[first] {
+                    ItemOutcome::Created(url) => ItemOutcome::Reused(url.clone()),
+                    ItemOutcome::Failed(_) => ItemOutcome::Failed(AppError::InvalidRequest(
+                        format!("Item {} with the same URL failed", first),
+                    )),
+                    _ => ItemOutcome::Aborted,
+                },
+            };
+            outcomes.push(outcome);
+        }
+
+        Ok(BatchResult {
+            committed: result.committed,
+            items: outcomes,
+        })
+    }
+
+    // 校验请求并分配短码，pending 中的短码视为已被占用。是否与已有短码冲突由插入时的
+    // UNIQUE 约束判断。id 为依赖行 id 的生成策略预先分配的 id
+    fn new_link(
+        generator: &dyn CodeGenerator,
+        codes: &CustomCodeConfig,
+        url_policy: &UrlPolicy,
+        request: CreateShortUrlRequest,
+        id: Option<i64>,
+        pending: &HashSet<String>,
+    ) -> Result<NewLink, AppError> {
         // Validate URL
         if !is_valid_url(&request.long_url) {
-            return Err("Invalid URL format".to_string());
+            return Err(AppError::InvalidUrl);
         }
+        url_policy.check(&request.long_url)?;
+
+        // Calculate expiration time
+        let now = Utc::now();
+        let expires_at = request
+            .timeout
+            .map(|timeout| expires_after(now, timeout, "timeout"))
+            .transpose()?;
 
         // Generate or use custom short code
         let short_code = match request.custom_code {
             Some(code) => {
-                // Check if custom code already exists
-                if Self::code_exists(pool, &code).await? {
-                    return Err("Custom code already exists".to_string());
+                let code = check_code(codes, "custom_code", &code)?;
+                if pending.contains(&code) {
+                    return Err(AppError::CodeTaken);
                 }
                 code
             }
-            None => {
-                // Generate unique code
-                let mut code = generate_short_code();
-                while Self::code_exists(pool, &code).await? {
-                    code = generate_short_code();
+            None => Self::unused_code(generator, id, 0, pending)?,
+        };
+
+        Ok(NewLink {
+            id,
+            long_url: request.long_url,
+            short_code,
+            created_at: now.to_rfc3339(),
+            expires_at: expires_at.map(|dt| dt.to_rfc3339()),
+            user_id: request.user_id,
+        })
+    }
+
+    // 从第 attempt 次尝试起生成一个不在 pending 中的短码
+    fn unused_code(
+        generator: &dyn CodeGenerator,
+        id: Option<i64>,
+        attempt: u32,
+        pending: &HashSet<String>,
+    ) -> Result<String, AppError> {
+        (attempt..attempt + MAX_CODE_ATTEMPTS)
+            .map(|attempt| generator.generate(id.unwrap_or_default(), attempt))
+            .find(|code| !pending.contains(code))
+            .ok_or_else(unique_code_error)
+    }
+
+    // 插入自动生成短码的链接，短码被占用时换一个重试，并记录冲突情况
+    async fn insert_generated(
+        store: &dyn LinkStore,
+        allocator: &CodeAllocator,
+        config: &AppConfig,
+        mut link: NewLink,
+    ) -> Result<ShortUrl, AppError> {
+        let short_code = &config.short_code;
+        let mut generator = config.code_generator(allocator.length(short_code));
+        for attempt in 1..=MAX_CODE_ATTEMPTS {
+            match store.insert(link.clone()).await {
+                Ok(url) => {
+                    allocator.record(short_code, false);
+                    return Ok(url);
                 }
-                code
+                Err(AppError::CodeTaken) => {
+                    if allocator.record(short_code, true) {
+                        generator = config.code_generator(allocator.length(short_code));
+                    }
+                    link.short_code = generator.generate(link.id.unwrap_or_default(), attempt);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Err(unique_code_error())
+    }
+
+    // 返回实际匹配的链接。开启 custom_code.case_insensitive 时自定义短码以小写保存，
+    // 原样找不到时再按小写查找
+    pub async fn resolve(
+        store: &dyn LinkStore,
+        codes: &CustomCodeConfig,
+        short_code: &str,
+    ) -> Result<ShortUrl, AppError> {
+        match Self::get_active_url(store, short_code).await {
+            Err(AppError::NotFound(_))
+                if codes.case_insensitive && short_code.chars().any(|c| c.is_ascii_uppercase()) =>
+            {
+                Self::get_active_url(store, &short_code.to_ascii_lowercase()).await
+            }
+            result => result,
+        }
+    }
+
+    pub async fn get_long_url(store: &dyn LinkStore, short_code: &str) -> Result<String, AppError> {
+        Ok(Self::get_active_url(store, short_code).await?.long_url)
+    }
+
+    // 按短码查找未过期的链接
+    async fn get_active_url(store: &dyn LinkStore, short_code: &str) -> Result<ShortUrl, AppError> {
+        let url = store
+            .get_by_code(short_code)
+            .await?
+            .ok_or(AppError::NotFound("Short URL"))?;
+
+        if url.expires_at.as_deref().is_some_and(is_expired) {
+            return Err(AppError::Expired);
+        }
+
+        Ok(url)
+    }
+
+    pub async fn get_user_urls(
+        store: &dyn LinkStore,
+        user_id: &str,
+    ) -> Result<Vec<ShortUrl>, AppError> {
+        store.list_by_user(user_id).await
+    }
+
+    // 按请求中的过滤、排序和分页参数列出用户的链接
+    pub async fn list_user_urls(
+        store: &dyn LinkStore,
+        user_id: &str,
+        request: &ListShortUrlsRequest,
+    ) -> Result<UrlPage, AppError> {
+        let query =
+            listing::parse_request(request, Utc::now()).map_err(AppError::InvalidRequest)?;
+        let page = store.list_page(user_id, &query).await?;
+        Ok(UrlPage::new(page, &query.sort))
+    }
+
+    pub async fn delete_short_url(
+        store: &dyn LinkStore,
+        id: i64,
+        user_id: &str,
+    ) -> Result<(), AppError> {
+        let url = Self::get_short_url(store, id).await?;
+        if url.user_id != user_id {
+            return Err(AppError::Forbidden("URL is not owned by user"));
+        }
+
+        store.delete(id, user_id).await?;
+
+        Ok(())
+    }
+
+    // 修改链接的目标地址、过期时间或短码，只能修改自己的链接
+    pub async fn update_short_url(
+        store: &dyn LinkStore,
+        url_policy: &UrlPolicy,
+        config: &AppConfig,
+        id: i64,
+        user_id: &str,
+        request: UpdateShortUrlRequest,
+    ) -> Result<ShortUrl, AppError> {
+        let url = Self::get_short_url(store, id).await?;
+        if url.user_id != user_id {
+            return Err(AppError::Forbidden("URL is not owned by user"));
+        }
+
+        if let Some(long_url) = &request.long_url {
+            if !is_valid_url(long_url) {
+                return Err(AppError::InvalidUrl);
             }
+            url_policy.check(long_url)?;
+        }
+
+        let short_code = match request.short_code {
+            Some(code) => Some(check_code(&config.custom_code, "short_code", &code)?)
+                .filter(|code| *code != url.short_code),
+            None => None,
         };
 
-        // Calculate expiration time
-        let expires_at = request
-            .timeout
-            .map(|timeout| Utc::now() + chrono::Duration::seconds(timeout));
-
-        // Insert into database
-        let result = sqlx::query(
-            r#"
-            INSERT INTO short_urls (long_url, short_code, created_at, expires_at, user_id)
-            VALUES (?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(&request.long_url)
-        .bind(&short_code)
-        .bind(Utc::now().to_rfc3339())
-        .bind(expires_at.map(|dt| dt.to_rfc3339()))
-        .bind(&request.user_id)
-        .execute(pool)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?;
+        let expires_at = match (request.timeout, request.expires_at) {
+            (Some(_), Some(_)) => {
+                return Err(AppError::InvalidRequest(
+                    "timeout and expires_at cannot both be set".to_string(),
+                ))
+            }
+            (Some(timeout), None) if timeout <= 0 => {
+                return Err(AppError::InvalidRequest(
+                    "timeout must be positive".to_string(),
+                ))
+            }
+            (Some(timeout), None) => Some(Some(
+                expires_after(Utc::now(), timeout, "timeout")?.to_rfc3339(),
+            )),
+            (None, Some(expires_at)) if expires_at.is_empty() => Some(None),
+            (None, Some(expires_at)) => {
+                // 统一为 UTC，与其他时间戳按字符串比较时顺序一致
+                let expires_at = chrono::DateTime::parse_from_rfc3339(&expires_at)
+                    .map_err(|_| {
+                        AppError::InvalidRequest(format!("Invalid expires_at: {}", expires_at))
+                    })?
+                    .with_timezone(&Utc);
+                Some(Some(expires_at.to_rfc3339()))
+            }
+            (None, None) => None,
+        };
 
-        // Fetch the created record
-        let short_url = sqlx::query_as::<_, ShortUrl>("SELECT * FROM short_urls WHERE id = ?")
-            .bind(result.last_insert_rowid())
-            .fetch_one(pool)
-            .await
-            .map_err(|e| format!("Database error: {}", e))?;
+        let update = LinkUpdate {
+            long_url: request.long_url,
+            short_code,
+            expires_at,
+        };
+        // 访问记录按链接 id 关联，修改短码不影响统计
+        store
+            .update(id, user_id, &update)
+            .await?
+            .map(|change| change.after)
+            .ok_or(AppError::NotFound("URL"))
+    }
 
-        Ok(short_url)
+    pub async fn record_click(pool: &SqlitePool, click: &Click) -> Result<(), AppError> {
+        Self::record_clicks(pool, std::slice::from_ref(click)).await
     }
 
-    pub async fn get_long_url(
-        pool: &SqlitePool,
-        short_code: &str,
-    ) -> Result<Option<String>, String> {
-        let result = sqlx::query(
-            r#"
-            SELECT long_url, expires_at FROM short_urls
-            WHERE short_code = ? AND (expires_at IS NULL OR expires_at > ?)
-            "#,
-        )
-        .bind(short_code)
-        .bind(Utc::now().to_rfc3339())
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?;
+    // 在一个事务中写入多条访问记录
+    pub async fn record_clicks(pool: &SqlitePool, clicks: &[Click]) -> Result<(), AppError> {
+        let mut tx = pool.begin().await?;
+        for click in clicks {
+            sqlx::query(
+                r#"
+                INSERT INTO clicks (link_id, short_code, clicked_at, referrer, user_agent, ip, accept_language)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(click.link_id)
+            .bind(&click.short_code)
+            .bind(&click.clicked_at)
+            .bind(&click.referrer)
+            .bind(&click.user_agent)
+            .bind(&click.ip)
+            .bind(&click.accept_language)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
 
-        Ok(result.map(|row| row.get("long_url")))
+        Ok(())
     }
 
-    pub async fn get_user_urls(pool: &SqlitePool, user_id: &str) -> Result<Vec<ShortUrl>, String> {
-        let urls = sqlx::query_as::<_, ShortUrl>(
-            "SELECT * FROM short_urls WHERE user_id = ? ORDER BY created_at DESC",
+    pub async fn get_clicks(pool: &SqlitePool, short_code: &str) -> Result<Vec<Click>, AppError> {
+        let clicks = sqlx::query_as::<_, Click>(
+            "SELECT * FROM clicks WHERE short_code = ? ORDER BY clicked_at DESC, id DESC",
         )
-        .bind(user_id)
+        .bind(short_code)
         .fetch_all(pool)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?;
+        .await?;
 
-        Ok(urls)
+        Ok(clicks)
     }
 
-    pub async fn delete_short_url(
+    // 为 link_id 出现之前的访问记录补上所属链接，返回补上的记录数。迁移只能匹配 SQLite 中的链接，
+    // 其余的在启动时按短码查找。只关联链接创建之后的访问，找不到链接的记录标记为 0，不再重复处理
+    pub async fn backfill_click_links(
+        store: &dyn LinkStore,
         pool: &SqlitePool,
-        id: i64,
-        user_id: &str,
-    ) -> Result<bool, String> {
-        let result = sqlx::query("DELETE FROM short_urls WHERE id = ? AND user_id = ?")
-            .bind(id)
-            .bind(user_id)
-            .execute(pool)
-            .await
-            .map_err(|e| format!("Database error: {}", e))?;
+    ) -> Result<u64, AppError> {
+        let mut total = 0;
+        loop {
+            let codes: Vec<String> = sqlx::query_scalar(
+                "SELECT DISTINCT short_code FROM clicks WHERE link_id IS NULL LIMIT ?",
+            )
+            .bind(BACKFILL_BATCH_SIZE as i64)
+            .fetch_all(pool)
+            .await?;
+            if codes.is_empty() {
+                return Ok(total);
+            }
 
-        Ok(result.rows_affected() > 0)
+            for code in codes {
+                let url = store.get_by_code(&code).await?;
+                let mut tx = pool.begin().await?;
+                if let Some(url) = url {
+                    total += sqlx::query(
+                        r#"
+                        UPDATE clicks SET link_id = ?
+                        WHERE short_code = ? AND link_id IS NULL AND clicked_at >= ?
+                        "#,
+                    )
+                    .bind(url.id)
+                    .bind(&code)
+                    .bind(&url.created_at)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected();
+                }
+                sqlx::query(
+                    "UPDATE clicks SET link_id = 0 WHERE short_code = ? AND link_id IS NULL",
+                )
+                .bind(&code)
+                .execute(&mut *tx)
+                .await?;
+                tx.commit().await?;
+            }
+        }
     }
 
-    async fn code_exists(pool: &SqlitePool, code: &str) -> Result<bool, String> {
-        let result = sqlx::query("SELECT COUNT(*) as count FROM short_urls WHERE short_code = ?")
-            .bind(code)
+    pub async fn get_short_url(store: &dyn LinkStore, id: i64) -> Result<ShortUrl, AppError> {
+        store.get_by_id(id).await?.ok_or(AppError::NotFound("URL"))
+    }
+
+    // url 为调用方已查询并校验过权限的链接，访问记录仍在 clicks 表中
+    pub async fn get_url_stats(
+        pool: &SqlitePool,
+        url: &ShortUrl,
+        range: &StatsRange,
+        top: usize,
+    ) -> Result<UrlStats, AppError> {
+        let total = sqlx::query("SELECT COUNT(*) as count FROM clicks WHERE link_id = ?")
+            .bind(url.id)
             .fetch_one(pool)
-            .await
-            .map_err(|e| format!("Database error: {}", e))?;
+            .await?;
+        let total_clicks: i64 = total.get("count");
+
+        // 在数据库中分组计数，只把每个桶、来源和 User-Agent 的数量读出来
+        let counts = stats::ClickCounts {
+            buckets: count_clicks_by(pool, url.id, range, range.granularity.bucket_sql())
+                .await?
+                .into_iter()
+                .filter_map(|(bucket, clicks)| Some((bucket?, clicks)))
+                .collect(),
+            referrers: count_clicks_by(pool, url.id, range, "referrer").await?,
+            user_agents: count_clicks_by(pool, url.id, range, "user_agent").await?,
+        };
 
-        let count: i64 = result.get("count");
-        Ok(count > 0)
+        Ok(stats::build_stats(url, total_clicks, &counts, range, top))
     }
 }
 
+// 按 key 表达式分组统计链接在区间内的访问数
+async fn count_clicks_by<T>(
+    pool: &SqlitePool,
+    link_id: i64,
+    range: &StatsRange,
+    key: &str,
+) -> Result<Vec<(Option<T>, i64)>, AppError>
+where
+    T: for<'r> sqlx::Decode<'r, sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Send + Unpin,
+{
+    let sql = format!(
+        r#"
+        SELECT {} AS key, COUNT(*) FROM clicks
+        WHERE link_id = ? AND clicked_at >= ? AND clicked_at < ?
+        GROUP BY key
+        "#,
+        key
+    );
+    let counts = sqlx::query_as(&sql)
+        .bind(link_id)
+        .bind(range.from.to_rfc3339())
+        .bind(range.to.to_rfc3339())
+        .fetch_all(pool)
+        .await?;
+    Ok(counts)
+}
+
+// 按 custom_code 配置校验用户指定的短码，field 为出错时报告的请求字段
+fn check_code(
+    codes: &CustomCodeConfig,
+    field: &'static str,
+    code: &str,
+) -> Result<String, AppError> {
+    validate_custom_code(code, codes).map_err(|reason| AppError::InvalidField {
+        field,
+        message: format!("{} {}", field, reason),
+    })
+}
+
+fn unique_code_error() -> AppError {
+    AppError::Internal("Failed to generate a unique short code".to_string())
+}
+
+// now 之后 secs 秒的时间，field 为出错时报告的请求字段。秒数来自客户端，超出可表示的范围时返回
+// InvalidRequest，不能直接用 chrono::Duration::seconds（会 panic）
+pub(crate) fn expires_after(
+    now: DateTime<Utc>,
+    secs: i64,
+    field: &str,
+) -> Result<DateTime<Utc>, AppError> {
+    chrono::TimeDelta::try_seconds(secs)
+        .and_then(|delta| now.checked_add_signed(delta))
+        .ok_or_else(|| AppError::InvalidRequest(format!("{} is too large", field)))
+}
+
+// 无法解析的过期时间按已过期处理
+fn is_expired(expires_at: &str) -> bool {
+    chrono::DateTime::parse_from_rfc3339(expires_at)
+        .map(|ts| ts <= Utc::now())
+        .unwrap_or(true)
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use crate::config::CodeStrategy;
     use crate::db;
     use crate::pb::abi::CreateShortUrlRequest;
+    use crate::store::MemoryLinkStore;
+    use crate::utils::short_code_generator::SqidsGenerator;
     use sqlx::sqlite::SqlitePoolOptions;
     use std::sync::Once;
 
     static INIT: Once = Once::new();
 
     // Helper function to set up an in-memory test database
-    async fn setup_test_db() -> SqlitePool {
+    async fn setup_test_db() -> (Box<dyn LinkStore>, SqlitePool) {
         // Initialize the logger only once
         INIT.call_once(|| {
             env_logger::builder().is_test(true).init();
@@ -153,12 +715,85 @@ mod tests {
             .await
             .expect("Failed to run migrations");
 
-        pool
+        // 设置 TEST_POSTGRES_URL 时短链接存储使用 PostgreSQL
+        (crate::store::tests::test_store(&pool).await, pool)
+    }
+
+    // The allocator, URL policy and config the create, batch and update services take,
+    // so tests only spell out the arguments they are about
+    pub(crate) struct TestCtx {
+        pub allocator: CodeAllocator,
+        pub url_policy: UrlPolicy,
+        pub config: AppConfig,
+    }
+
+    impl TestCtx {
+        pub fn new(config: AppConfig) -> Self {
+            Self {
+                allocator: CodeAllocator::new(),
+                url_policy: UrlPolicy::new(&config),
+                config,
+            }
+        }
+
+        pub async fn create(
+            &self,
+            store: &dyn LinkStore,
+            request: CreateShortUrlRequest,
+        ) -> Result<CreatedUrl, AppError> {
+            UrlService::create_short_url(
+                store,
+                &self.allocator,
+                &self.url_policy,
+                &self.config,
+                request,
+            )
+            .await
+        }
+
+        pub async fn batch(
+            &self,
+            store: &dyn LinkStore,
+            user_id: &str,
+            request: BatchCreateShortUrlsRequest,
+        ) -> Result<BatchResult, AppError> {
+            UrlService::batch_create_short_urls(
+                store,
+                &self.allocator,
+                &self.url_policy,
+                &self.config,
+                user_id,
+                request,
+            )
+            .await
+        }
+
+        pub async fn update(
+            &self,
+            store: &dyn LinkStore,
+            id: i64,
+            user_id: &str,
+            request: UpdateShortUrlRequest,
+        ) -> Result<ShortUrl, AppError> {
+            UrlService::update_short_url(
+                store,
+                &self.url_policy,
+                &self.config,
+                id,
+                user_id,
+                request,
+            )
+            .await
+        }
+    }
+
+    pub(crate) fn test_ctx() -> TestCtx {
+        TestCtx::new(AppConfig::default())
     }
 
     #[tokio::test]
     async fn test_create_short_url() {
-        let pool = setup_test_db().await;
+        let (store, _pool) = setup_test_db().await;
 
         // Create a test request
         let request = CreateShortUrlRequest {
@@ -166,10 +801,14 @@ mod tests {
             custom_code: None,
             timeout: None,
             user_id: "test_user".to_string(),
+            dedupe: None,
         };
 
         // Create a short URL
-        let result = UrlService::create_short_url(&pool, request).await;
+        let result = test_ctx()
+            .create(store.as_ref(), request)
+            .await
+            .map(|created| created.url);
         assert!(result.is_ok());
 
         let short_url = result.unwrap();
@@ -181,7 +820,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_create_short_url_with_custom_code() {
-        let pool = setup_test_db().await;
+        let (store, _pool) = setup_test_db().await;
 
         // Create a test request with custom code
         let request = CreateShortUrlRequest {
@@ -189,10 +828,14 @@ mod tests {
             custom_code: Some("custom".to_string()),
             timeout: None,
             user_id: "test_user".to_string(),
+            dedupe: None,
         };
 
         // Create a short URL
-        let result = UrlService::create_short_url(&pool, request).await;
+        let result = test_ctx()
+            .create(store.as_ref(), request)
+            .await
+            .map(|created| created.url);
         assert!(result.is_ok());
 
         let short_url = result.unwrap();
@@ -201,7 +844,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_create_short_url_with_timeout() {
-        let pool = setup_test_db().await;
+        let (store, _pool) = setup_test_db().await;
 
         // Create a test request with timeout
         let request = CreateShortUrlRequest {
@@ -209,19 +852,34 @@ mod tests {
             custom_code: None,
             timeout: Some(3600), // 1 hour
             user_id: "test_user".to_string(),
+            dedupe: None,
         };
 
         // Create a short URL
-        let result = UrlService::create_short_url(&pool, request).await;
+        let result = test_ctx()
+            .create(store.as_ref(), request)
+            .await
+            .map(|created| created.url);
         assert!(result.is_ok());
 
         let short_url = result.unwrap();
         assert!(short_url.expires_at.is_some());
+
+        // A timeout beyond any representable date is rejected
+        let request = CreateShortUrlRequest {
+            long_url: "https://example.com".to_string(),
+            custom_code: None,
+            timeout: Some(i64::MAX),
+            user_id: "test_user".to_string(),
+            dedupe: None,
+        };
+        let result = test_ctx().create(store.as_ref(), request).await;
+        assert!(matches!(result, Err(AppError::InvalidRequest(_))));
     }
 
     #[tokio::test]
     async fn test_create_short_url_with_invalid_url() {
-        let pool = setup_test_db().await;
+        let (store, _pool) = setup_test_db().await;
 
         // Create a test request with invalid URL
         let request = CreateShortUrlRequest {
@@ -229,17 +887,20 @@ mod tests {
             custom_code: None,
             timeout: None,
             user_id: "test_user".to_string(),
+            dedupe: None,
         };
 
         // Create a short URL should fail
-        let result = UrlService::create_short_url(&pool, request).await;
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Invalid URL format");
+        let result = test_ctx()
+            .create(store.as_ref(), request)
+            .await
+            .map(|created| created.url);
+        assert!(matches!(result, Err(AppError::InvalidUrl)));
     }
 
     #[tokio::test]
     async fn test_create_short_url_with_duplicate_custom_code() {
-        let pool = setup_test_db().await;
+        let (store, _pool) = setup_test_db().await;
 
         // Create first URL with custom code
         let request1 = CreateShortUrlRequest {
@@ -247,9 +908,13 @@ mod tests {
             custom_code: Some("duplicate".to_string()),
             timeout: None,
             user_id: "test_user".to_string(),
+            dedupe: None,
         };
 
-        let result1 = UrlService::create_short_url(&pool, request1).await;
+        let result1 = test_ctx()
+            .create(store.as_ref(), request1)
+            .await
+            .map(|created| created.url);
         assert!(result1.is_ok());
 
         // Try to create second URL with same custom code
@@ -258,16 +923,389 @@ mod tests {
             custom_code: Some("duplicate".to_string()),
             timeout: None,
             user_id: "test_user".to_string(),
+            dedupe: None,
+        };
+
+        let result2 = test_ctx()
+            .create(store.as_ref(), request2)
+            .await
+            .map(|created| created.url);
+        assert!(matches!(result2, Err(AppError::CodeTaken)));
+    }
+
+    #[tokio::test]
+    async fn test_create_short_url_dedupe() {
+        let store = MemoryLinkStore::new();
+        let mut ctx = test_ctx();
+        ctx.config.short_code.dedupe = true;
+        let request = |long_url: &str, user_id: &str| CreateShortUrlRequest {
+            long_url: long_url.to_string(),
+            user_id: user_id.to_string(),
+            ..Default::default()
+        };
+
+        let first = ctx
+            .create(&store, request("https://example.com/a", "alice"))
+            .await
+            .unwrap();
+        assert!(!first.reused);
+
+        // Same canonical URL for the same user returns the existing link
+        let again = ctx
+            .create(&store, request("HTTPS://EXAMPLE.COM:443/a", "alice"))
+            .await
+            .unwrap();
+        assert!(again.reused);
+        assert_eq!(again.url, first.url);
+        assert!(again.to_response("https://sho.rt").reused);
+
+        // Other users, custom codes and an explicit opt-out always create a new link
+        let other = ctx
+            .create(&store, request("https://example.com/a", "bob"))
+            .await
+            .unwrap();
+        assert!(!other.reused);
+        let custom = ctx
+            .create(
+                &store,
+                CreateShortUrlRequest {
+                    custom_code: Some("custom-a".to_string()),
+                    ..request("https://example.com/a", "alice")
+                },
+            )
+            .await
+            .unwrap();
+        assert!(!custom.reused);
+        let opted_out = ctx
+            .create(
+                &store,
+                CreateShortUrlRequest {
+                    dedupe: Some(false),
+                    ..request("https://example.com/a", "alice")
+                },
+            )
+            .await
+            .unwrap();
+        assert!(!opted_out.reused);
+
+        // Expired links are not reused
+        store
+            .insert(NewLink {
+                id: None,
+                long_url: "https://example.com/old".to_string(),
+                short_code: "old".to_string(),
+                created_at: "2024-01-01T00:00:00+00:00".to_string(),
+                expires_at: Some("2024-01-02T00:00:00+00:00".to_string()),
+                user_id: "alice".to_string(),
+            })
+            .await
+            .unwrap();
+        let fresh = ctx
+            .create(&store, request("https://example.com/old", "alice"))
+            .await
+            .unwrap();
+        assert!(!fresh.reused);
+        assert_ne!(fresh.url.short_code, "old");
+    }
+
+    #[tokio::test]
+    async fn test_batch_create_dedupe() {
+        let store = MemoryLinkStore::new();
+        let mut ctx = test_ctx();
+        ctx.config.short_code.dedupe = true;
+        let existing = ctx
+            .create(&store, batch_item("https://example.com/a", None))
+            .await
+            .unwrap();
+
+        let statuses = |result: &BatchResult| -> Vec<String> {
+            result
+                .to_response("https://sho.rt")
+                .results
+                .into_iter()
+                .map(|item| item.status)
+                .collect()
+        };
+        let request = BatchCreateShortUrlsRequest {
+            items: vec![
+                batch_item("https://example.com/a", None),
+                batch_item("https://example.com/b", None),
+                batch_item("HTTPS://example.com/b", None),
+                CreateShortUrlRequest {
+                    dedupe: Some(false),
+                    ..batch_item("https://example.com/a", None)
+                },
+                batch_item("https://example.com/a", Some("custom-a")),
+            ],
+            mode: String::new(),
+        };
+        let result = ctx.batch(&store, "", request).await.unwrap();
+        assert!(result.committed);
+        assert_eq!(
+            statuses(&result),
+            ["reused", "created", "reused", "created", "created"]
+        );
+        assert_eq!((result.created(), result.reused()), (3, 2));
+        let url = |index: usize| match &result.items[index] {
+            ItemOutcome::Created(url) | ItemOutcome::Reused(url) => url.clone(),
+            other => panic!("unexpected outcome {:?}", other),
+        };
+        assert_eq!(url(0), existing.url);
+        assert_eq!(url(2), url(1));
+        assert!(result.to_response("https://sho.rt").results[0]
+            .short_url
+            .as_ref()
+            .is_some_and(|url| url.reused));
+
+        // Duplicates follow the item they reuse when the batch is rolled back
+        let request = BatchCreateShortUrlsRequest {
+            items: vec![
+                batch_item("https://example.com/d", None),
+                batch_item("https://example.com/d", None),
+                batch_item("not a url", None),
+            ],
+            mode: String::new(),
+        };
+        let result = ctx.batch(&store, "", request).await.unwrap();
+        assert!(!result.committed);
+        assert_eq!(statuses(&result), ["aborted", "aborted", "failed"]);
+    }
+
+    #[tokio::test]
+    async fn test_create_short_url_code_strategies() {
+        let (store, _pool) = setup_test_db().await;
+        let request = |custom_code: Option<&str>| CreateShortUrlRequest {
+            long_url: "https://example.com".to_string(),
+            custom_code: custom_code.map(str::to_string),
+            user_id: "test_user".to_string(),
+            ..Default::default()
         };
+        let create = |config: AppConfig, custom_code: Option<&'static str>| {
+            let store = store.as_ref();
+            async move {
+                TestCtx::new(config)
+                    .create(store, request(custom_code))
+                    .await
+                    .map(|created| created.url)
+            }
+        };
+
+        let mut config = AppConfig::default();
+        config.short_code.strategy = CodeStrategy::Base62;
+        config.custom_code.min_length = 1;
+        let url = create(config.clone(), None).await.unwrap();
+        assert_eq!(url.short_code, url.id.to_string());
+
+        // A custom code squatting on the next id's code pushes it to a retry
+        create(config.clone(), Some("3")).await.unwrap();
+        let url = create(config.clone(), None).await.unwrap();
+        assert_eq!(url.id, 3);
+        assert_eq!(url.short_code, "3-1");
+
+        config.short_code.strategy = CodeStrategy::Sqids;
+        config.short_code.salt = "pepper".to_string();
+        let url = create(config.clone(), None).await.unwrap();
+        let sqids = SqidsGenerator::new(&config.short_code.alphabet, "pepper", 6);
+        assert_eq!(sqids.decode(&url.short_code), [url.id as u64]);
+        assert_eq!(store.get_by_code(&url.short_code).await.unwrap(), Some(url));
+
+        config.short_code.strategy = CodeStrategy::Random;
+        config.short_code.alphabet = "0123456789".to_string();
+        config.short_code.length = 9;
+        let url = create(config.clone(), None).await.unwrap();
+        assert_eq!(url.short_code.len(), 9);
+        assert!(url.short_code.chars().all(|c| c.is_ascii_digit()));
+
+        config.short_code.strategy = CodeStrategy::Time;
+        let first = create(config.clone(), None).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        let second = create(config, None).await.unwrap();
+        assert!(first.short_code < second.short_code);
+    }
 
-        let result2 = UrlService::create_short_url(&pool, request2).await;
-        assert!(result2.is_err());
-        assert_eq!(result2.unwrap_err(), "Custom code already exists");
+    #[tokio::test]
+    async fn test_batch_create_with_row_id_codes() {
+        let (store, _pool) = setup_test_db().await;
+        let mut ctx = test_ctx();
+        ctx.config.short_code.strategy = CodeStrategy::Base62;
+
+        let request = BatchCreateShortUrlsRequest {
+            items: vec![
+                batch_item("https://example.com/a", None),
+                batch_item("https://example.com/b", Some("custom")),
+                batch_item("https://example.com/c", None),
+            ],
+            mode: String::new(),
+        };
+        let result = ctx.batch(store.as_ref(), "alice", request).await.unwrap();
+        let codes: Vec<String> = result
+            .items
+            .iter()
+            .map(|item| match item {
+                ItemOutcome::Created(url) => url.short_code.clone(),
+                other => panic!("Unexpected outcome: {:?}", other),
+            })
+            .collect();
+        // Ids are reserved before the insert, the custom code gets the next one
+        assert_eq!(codes, ["1", "custom", "2"]);
+        assert_eq!(store.get_by_code("2").await.unwrap().unwrap().id, 2);
+
+        // A failed all-or-nothing batch leaves nothing behind
+        let request = BatchCreateShortUrlsRequest {
+            items: vec![
+                batch_item("https://example.com/d", None),
+                batch_item("https://example.com/e", Some("custom")),
+            ],
+            mode: String::new(),
+        };
+        let result = ctx.batch(store.as_ref(), "alice", request).await.unwrap();
+        assert!(!result.committed);
+        assert_eq!(store.list_after(None, 0, 100).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_row_id_codes_skip_reserved_words() {
+        let (_store, pool) = setup_test_db().await;
+        let store = crate::store::SqliteLinkStore::new(pool.clone());
+        let mut ctx = test_ctx();
+        ctx.config.short_code.strategy = CodeStrategy::Base62;
+        // The next row id is 141590, which is "api" in base62
+        sqlx::query(
+            "INSERT INTO short_urls (id, long_url, short_code, created_at, user_id) \
+             VALUES (141589, 'https://example.com', 'seed', '2024-01-01T00:00:00+00:00', 'alice')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let url = ctx
+            .create(&store, batch_item("https://example.com/a", None))
+            .await
+            .unwrap()
+            .url;
+        assert_eq!(url.id, 141590);
+        assert_eq!(url.short_code, "api-1");
+    }
+
+    #[tokio::test]
+    async fn test_custom_code_validation() {
+        let (store, _pool) = setup_test_db().await;
+        let mut ctx = test_ctx();
+        let request = |custom_code: &str| CreateShortUrlRequest {
+            user_id: "test_user".to_string(),
+            ..batch_item("https://example.com", Some(custom_code))
+        };
+
+        let result = ctx.create(store.as_ref(), request("Admin")).await;
+        assert!(matches!(
+            result,
+            Err(AppError::InvalidField {
+                field: "custom_code",
+                ..
+            })
+        ));
+
+        // Codes differing only in case are the same code
+        ctx.config.custom_code.case_insensitive = true;
+        let created = ctx.create(store.as_ref(), request("Promo")).await.unwrap();
+        assert_eq!(created.url.short_code, "promo");
+        assert!(matches!(
+            ctx.create(store.as_ref(), request("PROMO")).await,
+            Err(AppError::CodeTaken)
+        ));
+        let url = UrlService::resolve(store.as_ref(), &ctx.config.custom_code, "pROMO")
+            .await
+            .unwrap();
+        assert_eq!(url.id, created.url.id);
+        assert_eq!(url.long_url, "https://example.com");
+
+        // Invalid items fail on their own in a batch
+        let request = BatchCreateShortUrlsRequest {
+            items: vec![
+                batch_item("https://example.com/1", Some("ok-code")),
+                batch_item("https://example.com/2", Some("../etc")),
+            ],
+            mode: "best_effort".to_string(),
+        };
+        let result = ctx
+            .batch(store.as_ref(), "test_user", request)
+            .await
+            .unwrap();
+        assert!(matches!(result.items[0], ItemOutcome::Created(_)));
+        assert!(matches!(
+            result.items[1],
+            ItemOutcome::Failed(AppError::InvalidField { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_generated_code_collisions_are_bounded() {
+        let (store, _pool) = setup_test_db().await;
+        // A one-character keyspace of two codes, both already taken
+        let mut ctx = test_ctx();
+        ctx.config.short_code.alphabet = "ab".to_string();
+        ctx.config.short_code.length = 1;
+        ctx.config.custom_code.min_length = 1;
+        for code in ["a", "b"] {
+            let request = CreateShortUrlRequest {
+                user_id: "test_user".to_string(),
+                ..batch_item("https://example.com", Some(code))
+            };
+            ctx.create(store.as_ref(), request)
+                .await
+                .expect("Failed to create short URL");
+        }
+
+        let request = CreateShortUrlRequest {
+            user_id: "test_user".to_string(),
+            ..batch_item("https://example.com", None)
+        };
+        let result = ctx.create(store.as_ref(), request).await;
+        assert!(matches!(result, Err(AppError::Internal(_))));
+
+        // Custom codes are not counted, every generated attempt collided
+        let stats = ctx
+            .allocator
+            .stats(store.as_ref(), &ctx.config.short_code)
+            .await
+            .unwrap();
+        assert_eq!(stats.attempts, MAX_CODE_ATTEMPTS as u64);
+        assert_eq!(stats.collisions, MAX_CODE_ATTEMPTS as u64);
+        assert_eq!(stats.used, 2);
+        assert_eq!(stats.utilisation, 1.0);
+
+        for mode in ["all_or_nothing", "best_effort"] {
+            let request = BatchCreateShortUrlsRequest {
+                items: vec![
+                    batch_item("https://example.com/1", Some("custom")),
+                    batch_item("https://example.com/2", None),
+                ],
+                mode: mode.to_string(),
+            };
+            let result = ctx
+                .batch(store.as_ref(), "test_user", request)
+                .await
+                .unwrap();
+            assert!(matches!(
+                result.items[1],
+                ItemOutcome::Failed(AppError::Internal(_))
+            ));
+            assert_eq!(result.committed, mode == "best_effort");
+        }
+        assert!(store.exists("custom").await.unwrap());
+        assert_eq!(
+            ctx.allocator
+                .stats(store.as_ref(), &ctx.config.short_code)
+                .await
+                .unwrap()
+                .collisions,
+            3 * MAX_CODE_ATTEMPTS as u64
+        );
     }
 
     #[tokio::test]
     async fn test_get_long_url() {
-        let pool = setup_test_db().await;
+        let (store, _pool) = setup_test_db().await;
 
         // Create a short URL first
         let request = CreateShortUrlRequest {
@@ -275,35 +1313,53 @@ mod tests {
             custom_code: Some("testcode".to_string()),
             timeout: None,
             user_id: "test_user".to_string(),
+            dedupe: None,
         };
 
-        let create_result = UrlService::create_short_url(&pool, request).await;
+        let create_result = test_ctx()
+            .create(store.as_ref(), request)
+            .await
+            .map(|created| created.url);
         assert!(create_result.is_ok());
 
         // Now get the long URL
-        let result = UrlService::get_long_url(&pool, "testcode").await;
+        let result = UrlService::get_long_url(store.as_ref(), "testcode").await;
         assert!(result.is_ok());
-
-        let long_url_opt = result.unwrap();
-        assert!(long_url_opt.is_some());
-        assert_eq!(long_url_opt.unwrap(), "https://example.com");
+        assert_eq!(result.unwrap(), "https://example.com");
     }
 
     #[tokio::test]
     async fn test_get_nonexistent_url() {
-        let pool = setup_test_db().await;
+        let (store, _pool) = setup_test_db().await;
 
         // Try to get a non-existent short URL
-        let result = UrlService::get_long_url(&pool, "nonexistent").await;
-        assert!(result.is_ok());
+        let result = UrlService::get_long_url(store.as_ref(), "nonexistent").await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
 
-        let long_url_opt = result.unwrap();
-        assert!(long_url_opt.is_none());
+    #[tokio::test]
+    async fn test_get_expired_url() {
+        let (store, _pool) = setup_test_db().await;
+
+        store
+            .insert(NewLink {
+                id: None,
+                long_url: "https://example.com".to_string(),
+                short_code: "expired".to_string(),
+                created_at: Utc::now().to_rfc3339(),
+                expires_at: Some((Utc::now() - chrono::Duration::seconds(60)).to_rfc3339()),
+                user_id: "test_user".to_string(),
+            })
+            .await
+            .expect("Failed to insert expired URL");
+
+        let result = UrlService::get_long_url(store.as_ref(), "expired").await;
+        assert!(matches!(result, Err(AppError::Expired)));
     }
 
     #[tokio::test]
     async fn test_get_user_urls() {
-        let pool = setup_test_db().await;
+        let (store, _pool) = setup_test_db().await;
 
         // Create multiple short URLs for the same user
         for i in 1..=3 {
@@ -312,9 +1368,13 @@ mod tests {
                 custom_code: Some(format!("code{}", i)),
                 timeout: None,
                 user_id: "test_user".to_string(),
+                dedupe: None,
             };
 
-            let result = UrlService::create_short_url(&pool, request).await;
+            let result = test_ctx()
+                .create(store.as_ref(), request)
+                .await
+                .map(|created| created.url);
             assert!(result.is_ok());
         }
 
@@ -324,13 +1384,17 @@ mod tests {
             custom_code: Some("othercode".to_string()),
             timeout: None,
             user_id: "other_user".to_string(),
+            dedupe: None,
         };
 
-        let other_result = UrlService::create_short_url(&pool, other_request).await;
+        let other_result = test_ctx()
+            .create(store.as_ref(), other_request)
+            .await
+            .map(|created| created.url);
         assert!(other_result.is_ok());
 
         // Get URLs for test_user
-        let result = UrlService::get_user_urls(&pool, "test_user").await;
+        let result = UrlService::get_user_urls(store.as_ref(), "test_user").await;
         assert!(result.is_ok());
 
         let urls = result.unwrap();
@@ -344,7 +1408,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_delete_short_url() {
-        let pool = setup_test_db().await;
+        let (store, _pool) = setup_test_db().await;
 
         // Create a short URL
         let request = CreateShortUrlRequest {
@@ -352,37 +1416,189 @@ mod tests {
             custom_code: Some("deleteme".to_string()),
             timeout: None,
             user_id: "test_user".to_string(),
+            dedupe: None,
         };
 
-        let create_result = UrlService::create_short_url(&pool, request).await;
+        let create_result = test_ctx()
+            .create(store.as_ref(), request)
+            .await
+            .map(|created| created.url);
         assert!(create_result.is_ok());
 
         let short_url = create_result.unwrap();
 
         // Delete the URL
-        let delete_result = UrlService::delete_short_url(&pool, short_url.id, "test_user").await;
+        let delete_result =
+            UrlService::delete_short_url(store.as_ref(), short_url.id, "test_user").await;
         assert!(delete_result.is_ok());
-        assert!(delete_result.unwrap());
 
         // Verify it's deleted
-        let get_result = UrlService::get_long_url(&pool, "deleteme").await;
-        assert!(get_result.is_ok());
-        assert!(get_result.unwrap().is_none());
+        let get_result = UrlService::get_long_url(store.as_ref(), "deleteme").await;
+        assert!(matches!(get_result, Err(AppError::NotFound(_))));
     }
 
     #[tokio::test]
     async fn test_delete_nonexistent_url() {
-        let pool = setup_test_db().await;
+        let (store, _pool) = setup_test_db().await;
 
         // Try to delete a non-existent URL
-        let delete_result = UrlService::delete_short_url(&pool, 9999, "test_user").await;
-        assert!(delete_result.is_ok());
-        assert!(!delete_result.unwrap());
+        let delete_result = UrlService::delete_short_url(store.as_ref(), 9999, "test_user").await;
+        assert!(matches!(delete_result, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_record_and_get_clicks() {
+        let (_store, pool) = setup_test_db().await;
+
+        for i in 0..3 {
+            let click = Click {
+                id: 0,
+                short_code: "clicked".to_string(),
+                clicked_at: Utc::now().to_rfc3339(),
+                referrer: Some(format!("https://referrer{}.com", i)),
+                user_agent: Some("Mozilla/5.0".to_string()),
+                ip: Some("192.168.1.0".to_string()),
+                accept_language: Some("en-US".to_string()),
+                link_id: 1,
+            };
+            let result = UrlService::record_click(&pool, &click).await;
+            assert!(result.is_ok());
+        }
+
+        let clicks = UrlService::get_clicks(&pool, "clicked")
+            .await
+            .expect("Failed to get clicks");
+        assert_eq!(clicks.len(), 3);
+        assert!(clicks.iter().all(|c| c.short_code == "clicked"));
+        assert_eq!(clicks[0].referrer.as_deref(), Some("https://referrer2.com"));
+
+        // Clicks for other codes are not returned
+        let other = UrlService::get_clicks(&pool, "other")
+            .await
+            .expect("Failed to get clicks");
+        assert!(other.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_url_stats() {
+        let (store, pool) = setup_test_db().await;
+
+        let request = CreateShortUrlRequest {
+            long_url: "https://example.com".to_string(),
+            custom_code: Some("statscode".to_string()),
+            timeout: None,
+            user_id: "test_user".to_string(),
+            dedupe: None,
+        };
+        let short_url = test_ctx()
+            .create(store.as_ref(), request)
+            .await
+            .map(|created| created.url)
+            .expect("Failed to create short URL");
+
+        let now = Utc::now().to_rfc3339();
+        for (clicked_at, referrer) in [
+            (now.as_str(), Some("https://a.example.org/x")),
+            (now.as_str(), None),
+            // Outside of the range
+            (
+                "2000-01-01T00:00:00+00:00",
+                Some("https://old.example.org/"),
+            ),
+        ] {
+            let click = Click {
+                id: 0,
+                short_code: "statscode".to_string(),
+                clicked_at: clicked_at.to_string(),
+                referrer: referrer.map(str::to_string),
+                user_agent: None,
+                ip: None,
+                accept_language: None,
+                link_id: short_url.id,
+            };
+            UrlService::record_click(&pool, &click)
+                .await
+                .expect("Failed to record click");
+        }
+
+        let range = StatsRange::new(stats::Granularity::Hour, None, None).unwrap();
+        let stats = UrlService::get_url_stats(&pool, &short_url, &range, 10)
+            .await
+            .expect("Failed to get stats");
+
+        assert_eq!(stats.short_code, "statscode");
+        assert_eq!(stats.total_clicks, 3);
+        assert_eq!(stats.range_clicks, 2);
+        assert_eq!(stats.series.iter().map(|b| b.clicks).sum::<i64>(), 2);
+        assert_eq!(stats.top_referrers.len(), 2);
+
+        // Clicks of other links are not counted
+        let other = ShortUrl {
+            id: 9999,
+            ..short_url
+        };
+        let stats = UrlService::get_url_stats(&pool, &other, &range, 10)
+            .await
+            .expect("Failed to get stats");
+        assert_eq!(stats.total_clicks, 0);
+    }
+
+    #[tokio::test]
+    async fn test_backfill_click_links() {
+        // Links in another backend are not matched by the migration
+        let (_store, pool) = setup_test_db().await;
+        let store = MemoryLinkStore::new();
+        let request = CreateShortUrlRequest {
+            long_url: "https://example.com".to_string(),
+            custom_code: Some("legacy".to_string()),
+            timeout: None,
+            user_id: "test_user".to_string(),
+            dedupe: None,
+        };
+        let short_url = test_ctx()
+            .create(&store, request)
+            .await
+            .map(|created| created.url)
+            .expect("Failed to create short URL");
+
+        let now = Utc::now().to_rfc3339();
+        for (short_code, clicked_at) in [
+            ("legacy", now.as_str()),
+            ("legacy", now.as_str()),
+            // Recorded before the link was created, for an earlier link with the same code
+            ("legacy", "2000-01-01T00:00:00+00:00"),
+            ("gone", now.as_str()),
+        ] {
+            sqlx::query("INSERT INTO clicks (short_code, clicked_at) VALUES (?, ?)")
+                .bind(short_code)
+                .bind(clicked_at)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let linked = UrlService::backfill_click_links(&store, &pool)
+            .await
+            .expect("Failed to backfill clicks");
+        assert_eq!(linked, 2);
+        let link_ids: Vec<i64> = sqlx::query_scalar("SELECT link_id FROM clicks ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(link_ids, vec![short_url.id, short_url.id, 0, 0]);
+
+        // Nothing is left to process
+        assert_eq!(
+            UrlService::backfill_click_links(&store, &pool)
+                .await
+                .unwrap(),
+            0
+        );
     }
 
     #[tokio::test]
     async fn test_delete_url_wrong_user() {
-        let pool = setup_test_db().await;
+        let (store, _pool) = setup_test_db().await;
 
         // Create a short URL
         let request = CreateShortUrlRequest {
@@ -390,21 +1606,424 @@ mod tests {
             custom_code: Some("usertest".to_string()),
             timeout: None,
             user_id: "owner_user".to_string(),
+            dedupe: None,
         };
 
-        let create_result = UrlService::create_short_url(&pool, request).await;
+        let create_result = test_ctx()
+            .create(store.as_ref(), request)
+            .await
+            .map(|created| created.url);
         assert!(create_result.is_ok());
 
         let short_url = create_result.unwrap();
 
         // Try to delete with wrong user
-        let delete_result = UrlService::delete_short_url(&pool, short_url.id, "wrong_user").await;
-        assert!(delete_result.is_ok());
-        assert!(!delete_result.unwrap());
+        let delete_result =
+            UrlService::delete_short_url(store.as_ref(), short_url.id, "wrong_user").await;
+        assert!(matches!(delete_result, Err(AppError::Forbidden(_))));
 
         // Verify it's not deleted
-        let get_result = UrlService::get_long_url(&pool, "usertest").await;
+        let get_result = UrlService::get_long_url(store.as_ref(), "usertest").await;
         assert!(get_result.is_ok());
-        assert!(get_result.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_update_short_url() {
+        let (store, pool) = setup_test_db().await;
+
+        let request = CreateShortUrlRequest {
+            long_url: "https://exmaple.com".to_string(),
+            custom_code: Some("typo".to_string()),
+            timeout: Some(3600),
+            user_id: "test_user".to_string(),
+            dedupe: None,
+        };
+        let short_url = test_ctx()
+            .create(store.as_ref(), request)
+            .await
+            .map(|created| created.url)
+            .expect("Failed to create short URL");
+        let click = Click {
+            id: 0,
+            short_code: "typo".to_string(),
+            clicked_at: Utc::now().to_rfc3339(),
+            referrer: None,
+            user_agent: None,
+            ip: None,
+            accept_language: None,
+            link_id: short_url.id,
+        };
+        UrlService::record_click(&pool, &click)
+            .await
+            .expect("Failed to record click");
+
+        // Only the owner can update
+        let request = UpdateShortUrlRequest {
+            long_url: Some("https://example.com".to_string()),
+            ..Default::default()
+        };
+        let result = test_ctx()
+            .update(store.as_ref(), short_url.id, "other", request)
+            .await;
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+
+        // Fix the target, rename the code and set an explicit expiry
+        let request = UpdateShortUrlRequest {
+            long_url: Some("https://example.com".to_string()),
+            short_code: Some("fixed".to_string()),
+            expires_at: Some("2030-01-01T08:00:00+08:00".to_string()),
+            ..Default::default()
+        };
+        let updated = test_ctx()
+            .update(store.as_ref(), short_url.id, "test_user", request)
+            .await
+            .expect("Failed to update short URL");
+        assert_eq!(updated.long_url, "https://example.com");
+        assert_eq!(updated.short_code, "fixed");
+        assert_eq!(
+            updated.expires_at.as_deref(),
+            Some("2030-01-01T00:00:00+00:00")
+        );
+        assert!(matches!(
+            UrlService::get_long_url(store.as_ref(), "typo").await,
+            Err(AppError::NotFound(_))
+        ));
+
+        // Clicks are keyed by link id and still count after the rename
+        let range = StatsRange::new(stats::Granularity::Hour, None, None).unwrap();
+        let stats = UrlService::get_url_stats(&pool, &updated, &range, 10)
+            .await
+            .expect("Failed to get stats");
+        assert_eq!(
+            (stats.short_code.as_str(), stats.total_clicks),
+            ("fixed", 1)
+        );
+
+        // Empty expires_at clears the expiry
+        let request = UpdateShortUrlRequest {
+            expires_at: Some(String::new()),
+            ..Default::default()
+        };
+        let updated = test_ctx()
+            .update(store.as_ref(), short_url.id, "test_user", request)
+            .await
+            .expect("Failed to update short URL");
+        assert_eq!(updated.expires_at, None);
+        assert_eq!(updated.short_code, "fixed");
+    }
+
+    #[tokio::test]
+    async fn test_update_short_url_invalid_requests() {
+        let (store, _pool) = setup_test_db().await;
+
+        for code in ["mine", "taken"] {
+            let request = CreateShortUrlRequest {
+                long_url: "https://example.com".to_string(),
+                custom_code: Some(code.to_string()),
+                timeout: None,
+                user_id: "test_user".to_string(),
+                dedupe: None,
+            };
+            test_ctx()
+                .create(store.as_ref(), request)
+                .await
+                .map(|created| created.url)
+                .expect("Failed to create short URL");
+        }
+        let id = store.get_by_code("mine").await.unwrap().unwrap().id;
+
+        let ctx = test_ctx();
+        let update =
+            |request: UpdateShortUrlRequest| ctx.update(store.as_ref(), id, "test_user", request);
+
+        let result = update(UpdateShortUrlRequest {
+            short_code: Some("taken".to_string()),
+            ..Default::default()
+        })
+        .await;
+        assert!(matches!(result, Err(AppError::CodeTaken)));
+
+        let result = update(UpdateShortUrlRequest {
+            long_url: Some("not a url".to_string()),
+            ..Default::default()
+        })
+        .await;
+        assert!(matches!(result, Err(AppError::InvalidUrl)));
+
+        for request in [
+            UpdateShortUrlRequest {
+                timeout: Some(60),
+                expires_at: Some(String::new()),
+                ..Default::default()
+            },
+            UpdateShortUrlRequest {
+                timeout: Some(0),
+                ..Default::default()
+            },
+            UpdateShortUrlRequest {
+                expires_at: Some("tomorrow".to_string()),
+                ..Default::default()
+            },
+            UpdateShortUrlRequest {
+                timeout: Some(i64::MAX),
+                ..Default::default()
+            },
+        ] {
+            let result = update(request).await;
+            assert!(matches!(result, Err(AppError::InvalidRequest(_))));
+        }
+
+        // New codes follow the custom code rules
+        for short_code in ["", "a/b/c", "API"] {
+            let result = update(UpdateShortUrlRequest {
+                short_code: Some(short_code.to_string()),
+                ..Default::default()
+            })
+            .await;
+            assert!(
+                matches!(
+                    result,
+                    Err(AppError::InvalidField {
+                        field: "short_code",
+                        ..
+                    })
+                ),
+                "{}",
+                short_code
+            );
+        }
+
+        // Keeping the current code is not a conflict
+        let result = update(UpdateShortUrlRequest {
+            short_code: Some("mine".to_string()),
+            ..Default::default()
+        })
+        .await;
+        assert!(result.is_ok());
+
+        let result = test_ctx()
+            .update(store.as_ref(), 9999, "test_user", Default::default())
+            .await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    fn batch_item(long_url: &str, custom_code: Option<&str>) -> CreateShortUrlRequest {
+        CreateShortUrlRequest {
+            long_url: long_url.to_string(),
+            custom_code: custom_code.map(str::to_string),
+            timeout: None,
+            user_id: String::new(),
+            dedupe: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_create_short_urls() {
+        let (store, _pool) = setup_test_db().await;
+        let ctx = test_ctx();
+
+        let request = CreateShortUrlRequest {
+            user_id: "test_user".to_string(),
+            ..batch_item("https://example.com", Some("taken"))
+        };
+        ctx.create(store.as_ref(), request)
+            .await
+            .map(|created| created.url)
+            .expect("Failed to create short URL");
+
+        let items = || {
+            vec![
+                batch_item("https://example.com/1", None),
+                batch_item("not a url", None),
+                batch_item("https://example.com/2", Some("taken")),
+                batch_item("https://example.com/3", Some("dup")),
+                batch_item("https://example.com/4", Some("dup")),
+            ]
+        };
+
+        // All-or-nothing: nothing is created, valid items are reported as aborted
+        let request = BatchCreateShortUrlsRequest {
+            items: items(),
+            mode: String::new(),
+        };
+        let result = ctx
+            .batch(store.as_ref(), "test_user", request)
+            .await
+            .expect("Failed to run batch");
+        assert!(!result.committed);
+        assert_eq!(result.created(), 0);
+        // Taken codes are only detected on insert, which invalid items skip
+        assert_eq!(result.failed(), 2);
+        assert!(matches!(result.items[0], batch::ItemOutcome::Aborted));
+        assert!(matches!(result.items[2], batch::ItemOutcome::Aborted));
+        assert!(matches!(
+            result.items[1],
+            batch::ItemOutcome::Failed(AppError::InvalidUrl)
+        ));
+        assert!(!store.exists("dup").await.unwrap());
+
+        // Best effort creates the valid items for the caller
+        let request = BatchCreateShortUrlsRequest {
+            items: items(),
+            mode: "best_effort".to_string(),
+        };
+        let result = ctx
+            .batch(store.as_ref(), "test_user", request)
+            .await
+            .expect("Failed to run batch");
+        assert!(result.committed);
+        assert_eq!(result.created(), 2);
+        let response = result.to_response("https://sho.rt");
+        let statuses: Vec<&str> = response
+            .results
+            .iter()
+            .map(|item| item.status.as_str())
+            .collect();
+        assert_eq!(
+            statuses,
+            ["created", "failed", "failed", "created", "failed"]
+        );
+        let url = store.get_by_code("dup").await.unwrap().unwrap();
+        assert_eq!(url.long_url, "https://example.com/3");
+        assert_eq!(url.user_id, "test_user");
+
+        assert_eq!(response.results[2].error_code, "CODE_TAKEN");
+        assert_eq!(
+            response.results[3].short_url.as_ref().unwrap().short_url,
+            "https://sho.rt/dup"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_create_invalid_requests() {
+        let (store, _pool) = setup_test_db().await;
+        let ctx = test_ctx();
+
+        for request in [
+            BatchCreateShortUrlsRequest::default(),
+            BatchCreateShortUrlsRequest {
+                items: vec![batch_item("https://example.com", None)],
+                mode: "sometimes".to_string(),
+            },
+            BatchCreateShortUrlsRequest {
+                items: vec![batch_item("https://example.com", None); batch::MAX_BATCH_SIZE + 1],
+                mode: String::new(),
+            },
+        ] {
+            let result = ctx.batch(store.as_ref(), "test_user", request).await;
+            assert!(matches!(result, Err(AppError::InvalidRequest(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_url_policy_is_enforced() {
+        let (store, _pool) = setup_test_db().await;
+        let mut ctx = test_ctx();
+        ctx.config.short_code.dedupe = true;
+        let request = |long_url: &str| CreateShortUrlRequest {
+            user_id: "test_user".to_string(),
+            ..batch_item(long_url, None)
+        };
+
+        // Created before the service moved to sho.rt
+        let old = ctx
+            .create(store.as_ref(), request("https://sho.rt/loop"))
+            .await
+            .expect("Failed to create short URL");
+
+        ctx.config.server.public_base_url = "https://sho.rt".to_string();
+        ctx.url_policy = UrlPolicy::new(&ctx.config);
+        // Dedupe doesn't hand out the existing link either
+        let result = ctx
+            .create(store.as_ref(), request("https://sho.rt/loop"))
+            .await;
+        assert!(matches!(result, Err(AppError::UrlNotAllowed(_))));
+
+        let result = ctx
+            .batch(
+                store.as_ref(),
+                "test_user",
+                BatchCreateShortUrlsRequest {
+                    items: vec![
+                        batch_item("https://example.com", None),
+                        batch_item("ftp://example.com", None),
+                    ],
+                    mode: "best_effort".to_string(),
+                },
+            )
+            .await
+            .expect("Failed to run batch");
+        assert_eq!((result.created(), result.failed()), (1, 1));
+        assert!(matches!(
+            result.items[1],
+            batch::ItemOutcome::Failed(AppError::UrlNotAllowed(_))
+        ));
+
+        let result = ctx
+            .update(
+                store.as_ref(),
+                old.url.id,
+                "test_user",
+                UpdateShortUrlRequest {
+                    long_url: Some("http://10.0.0.1/".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert!(matches!(result, Err(AppError::UrlNotAllowed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_url_service_with_memory_store() {
+        let store = MemoryLinkStore::new();
+
+        let request = CreateShortUrlRequest {
+            long_url: "https://example.com".to_string(),
+            custom_code: Some("memory".to_string()),
+            timeout: None,
+            user_id: "test_user".to_string(),
+            dedupe: None,
+        };
+        let short_url = test_ctx()
+            .create(&store, request)
+            .await
+            .map(|created| created.url)
+            .expect("Failed to create short URL");
+
+        // Generated codes avoid existing ones
+        let request = CreateShortUrlRequest {
+            long_url: "https://example.org".to_string(),
+            custom_code: None,
+            timeout: None,
+            user_id: "test_user".to_string(),
+            dedupe: None,
+        };
+        let generated = test_ctx()
+            .create(&store, request)
+            .await
+            .map(|created| created.url)
+            .expect("Failed to create short URL");
+        assert_ne!(generated.short_code, "memory");
+
+        assert_eq!(
+            UrlService::get_long_url(&store, "memory").await.unwrap(),
+            "https://example.com"
+        );
+        assert_eq!(
+            UrlService::get_user_urls(&store, "test_user")
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let wrong_user = UrlService::delete_short_url(&store, short_url.id, "other").await;
+        assert!(matches!(wrong_user, Err(AppError::Forbidden(_))));
+
+        UrlService::delete_short_url(&store, short_url.id, "test_user")
+            .await
+            .expect("Failed to delete");
+        let result = UrlService::get_long_url(&store, "memory").await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
     }
 }
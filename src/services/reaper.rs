@@ -0,0 +1,223 @@
+use crate::config::{ReaperConfig, ReaperMode};
+use crate::error::AppError;
+use crate::store::LinkStore;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+
+// 清理结果中最多列出的短码数量，其余只计入 removed
+pub const MAX_REPORTED_CODES: usize = 100;
+
+// 一次清理的结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReapReport {
+    pub mode: ReaperMode,
+    // expires_at 早于该时间的链接被清理
+    pub cutoff: String,
+    pub removed: usize,
+    // 最先清理的最多 MAX_REPORTED_CODES 个短码
+    pub short_codes: Vec<String>,
+}
+
+pub struct ReaperService;
+
+impl ReaperService {
+    // 分批清理过期超过 grace_period_secs 的链接，直到没有剩余
+    pub async fn reap(
+        store: &dyn LinkStore,
+        config: &ReaperConfig,
+        now: DateTime<Utc>,
+    ) -> Result<ReapReport, AppError> {
+        let grace_period = chrono::Duration::seconds(config.grace_period_secs as i64);
+        let cutoff = (now - grace_period).to_rfc3339();
+
+        let mut removed_total = 0;
+        let mut short_codes = Vec::new();
+        loop {
+            let removed = store
+                .remove_expired(&cutoff, config.mode, config.batch_size)
+                .await?;
+            if removed.is_empty() {
+                break;
+            }
+
+            log::info!(
+                "Reaper ({}) removed {} links expired before {}",
+                config.mode,
+                removed.len(),
+                cutoff
+            );
+            removed_total += removed.len();
+            let done = removed.len() < config.batch_size;
+            let room = MAX_REPORTED_CODES - short_codes.len();
+            short_codes.extend(removed.into_iter().take(room).map(|url| url.short_code));
+            if done {
+                break;
+            }
+        }
+
+        Ok(ReapReport {
+            mode: config.mode,
+            cutoff,
+            removed: removed_total,
+            short_codes,
+        })
+    }
+
+    // 后台任务，启动时立即执行一次，之后每 interval_secs 执行一次
+    pub async fn run(store: Arc<dyn LinkStore>, config: ReaperConfig) {
+        let mut interval = tokio::time::interval(Duration::from_secs(config.interval_secs));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = Self::reap(store.as_ref(), &config, Utc::now()).await {
+                log::error!("Failed to reap expired links: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::store::{MemoryLinkStore, NewLink, SqliteLinkStore};
+    use sqlx::SqlitePool;
+
+    fn expiring_link(short_code: &str, expires_at: &str) -> NewLink {
+        NewLink {
+            id: None,
+            long_url: "https://example.com".to_string(),
+            short_code: short_code.to_string(),
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            expires_at: Some(expires_at.to_string()),
+            user_id: "alice".to_string(),
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        "2024-06-10T00:00:00Z".parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reap_in_batches_after_grace_period() {
+        let store = MemoryLinkStore::new();
+        for i in 0..5 {
+            store
+                .insert(expiring_link(
+                    &format!("old{}", i),
+                    "2024-06-01T00:00:00+00:00",
+                ))
+                .await
+                .unwrap();
+        }
+        // Expired, but still within the grace period
+        store
+            .insert(expiring_link("recent", "2024-06-09T00:00:00+00:00"))
+            .await
+            .unwrap();
+        store
+            .insert(NewLink {
+                expires_at: None,
+                ..expiring_link("forever", "")
+            })
+            .await
+            .unwrap();
+
+        let config = ReaperConfig {
+            grace_period_secs: 3 * 24 * 3600,
+            batch_size: 2,
+            ..ReaperConfig::default()
+        };
+        let report = ReaperService::reap(&store, &config, now()).await.unwrap();
+        assert_eq!(report.removed, 5);
+        assert_eq!(report.cutoff, "2024-06-07T00:00:00+00:00");
+        assert!(report
+            .short_codes
+            .iter()
+            .all(|code| code.starts_with("old")));
+
+        let mut codes = store.all_codes().await.unwrap();
+        codes.sort();
+        assert_eq!(codes, ["forever", "recent"]);
+
+        // Nothing left to do
+        let report = ReaperService::reap(&store, &config, now()).await.unwrap();
+        assert_eq!(report.removed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_report_lists_limited_codes() {
+        let store = MemoryLinkStore::new();
+        for i in 0..MAX_REPORTED_CODES + 50 {
+            store
+                .insert(expiring_link(
+                    &format!("old{}", i),
+                    "2024-06-01T00:00:00+00:00",
+                ))
+                .await
+                .unwrap();
+        }
+
+        let config = ReaperConfig {
+            batch_size: 40,
+            ..ReaperConfig::default()
+        };
+        let report = ReaperService::reap(&store, &config, now()).await.unwrap();
+        assert_eq!(report.removed, MAX_REPORTED_CODES + 50);
+        assert_eq!(report.short_codes.len(), MAX_REPORTED_CODES);
+        assert!(store.all_codes().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_archive_and_delete_modes() {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+        db::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+        let store = SqliteLinkStore::new(pool.clone());
+
+        let archived_count = || async {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM short_urls_archive")
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        };
+
+        let expired = store
+            .insert(expiring_link("archived", "2024-01-01T00:00:00+00:00"))
+            .await
+            .unwrap();
+        let config = ReaperConfig::default();
+        let report = ReaperService::reap(&store, &config, now()).await.unwrap();
+        assert_eq!(report.mode, ReaperMode::Archive);
+        assert_eq!(report.short_codes, ["archived"]);
+        assert_eq!(archived_count().await, 1);
+
+        let (id, short_code): (i64, String) =
+            sqlx::query_as("SELECT id, short_code FROM short_urls_archive")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!((id, short_code.as_str()), (expired.id, "archived"));
+
+        // The code can be used again once the link has been reaped
+        store
+            .insert(expiring_link("archived", "2024-01-01T00:00:00+00:00"))
+            .await
+            .unwrap();
+        let config = ReaperConfig {
+            mode: ReaperMode::Delete,
+            ..ReaperConfig::default()
+        };
+        let report = ReaperService::reap(&store, &config, now()).await.unwrap();
+        assert_eq!(report.removed, 1);
+        assert_eq!(archived_count().await, 1);
+        assert!(!store.exists("archived").await.unwrap());
+    }
+}
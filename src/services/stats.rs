@@ -0,0 +1,380 @@
+use crate::pb::abi::{BreakdownEntry, ShortUrl, TimeBucket, UrlStats};
+use crate::utils::user_agent::parse_user_agent;
+use chrono::{DateTime, Datelike, Duration, DurationRound, NaiveDate, Utc};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+// 单次查询最多返回的时间桶数量
+pub const MAX_BUCKETS: i64 = 1000;
+// 各维度默认返回的条目数量
+pub const DEFAULT_TOP: usize = 10;
+// 各维度最多返回的条目数量
+pub const MAX_TOP: usize = 100;
+
+const DIRECT_REFERRER: &str = "(direct)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    Hour,
+    Day,
+    Week,
+}
+
+impl Granularity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Granularity::Hour => "hour",
+            Granularity::Day => "day",
+            Granularity::Week => "week",
+        }
+    }
+
+    fn step(&self) -> Duration {
+        match self {
+            Granularity::Hour => Duration::hours(1),
+            Granularity::Day => Duration::days(1),
+            Granularity::Week => Duration::weeks(1),
+        }
+    }
+
+    // 未指定 from 时默认查询的时间跨度
+    fn default_span(&self) -> Duration {
+        match self {
+            Granularity::Hour => Duration::hours(24),
+            Granularity::Day => Duration::days(30),
+            Granularity::Week => Duration::weeks(12),
+        }
+    }
+
+    // SQLite 中计算 clicked_at 所在桶起始时间（Unix 秒）的表达式，与 truncate 一致。
+    // 1970-01-01 是周四，按周分桶时先偏移三天对齐到周一
+    pub fn bucket_sql(&self) -> &'static str {
+        match self {
+            Granularity::Hour => "CAST(strftime('%s', clicked_at) AS INTEGER) / 3600 * 3600",
+            Granularity::Day => "CAST(strftime('%s', clicked_at) AS INTEGER) / 86400 * 86400",
+            Granularity::Week => {
+                "(CAST(strftime('%s', clicked_at) AS INTEGER) + 259200) / 604800 * 604800 - 259200"
+            }
+        }
+    }
+
+    // 截断到桶的起始时间，周以周一 00:00 (UTC) 为起点
+    fn truncate(&self, ts: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Granularity::Hour => ts.duration_trunc(Duration::hours(1)).unwrap_or(ts),
+            Granularity::Day => ts.duration_trunc(Duration::days(1)).unwrap_or(ts),
+            Granularity::Week => {
+                let day = ts.duration_trunc(Duration::days(1)).unwrap_or(ts);
+                day - Duration::days(day.weekday().num_days_from_monday() as i64)
+            }
+        }
+    }
+}
+
+impl FromStr for Granularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "hour" => Ok(Granularity::Hour),
+            "day" => Ok(Granularity::Day),
+            "week" => Ok(Granularity::Week),
+            other => Err(format!("Invalid granularity: {}", other)),
+        }
+    }
+}
+
+// 统计查询区间 [from, to)
+#[derive(Debug, Clone)]
+pub struct StatsRange {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub granularity: Granularity,
+}
+
+impl StatsRange {
+    pub fn new(
+        granularity: Granularity,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Self, String> {
+        let to = to.unwrap_or_else(Utc::now);
+        let from = from.unwrap_or(to - granularity.default_span());
+
+        if from >= to {
+            return Err("'from' must be earlier than 'to'".to_string());
+        }
+
+        let range = Self {
+            from,
+            to,
+            granularity,
+        };
+
+        if range.bucket_starts().len() as i64 > MAX_BUCKETS {
+            return Err(format!(
+                "Requested range spans more than {} {} buckets",
+                MAX_BUCKETS,
+                granularity.as_str()
+            ));
+        }
+
+        Ok(range)
+    }
+
+    fn bucket_starts(&self) -> Vec<DateTime<Utc>> {
+        let step = self.granularity.step();
+        let mut starts = Vec::new();
+        let mut current = self.granularity.truncate(self.from);
+        while current < self.to && starts.len() as i64 <= MAX_BUCKETS {
+            starts.push(current);
+            current += step;
+        }
+        starts
+    }
+}
+
+// 解析 RFC3339 时间或 YYYY-MM-DD 日期（按 UTC 零点）
+pub fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|naive| naive.and_utc())
+        .ok_or_else(|| format!("Invalid timestamp: {}", value))
+}
+
+// 区间内按维度分组的访问数量，由 clicks 表聚合得到
+#[derive(Debug, Clone, Default)]
+pub struct ClickCounts {
+    // 桶起始时间（Unix 秒）和访问数
+    pub buckets: Vec<(i64, i64)>,
+    pub referrers: Vec<(Option<String>, i64)>,
+    pub user_agents: Vec<(Option<String>, i64)>,
+}
+
+// 将分组后的访问数量整理为统计结果，来源按域名、User-Agent 按解析结果再次合并
+pub fn build_stats(
+    url: &ShortUrl,
+    total_clicks: i64,
+    counts: &ClickCounts,
+    range: &StatsRange,
+    top: usize,
+) -> UrlStats {
+    let starts = range.bucket_starts();
+    let mut series = vec![0i64; starts.len()];
+    let mut range_clicks = 0;
+    for &(bucket, clicks) in &counts.buckets {
+        let Some(start) = DateTime::from_timestamp(bucket, 0) else {
+            continue;
+        };
+        if let Ok(index) = starts.binary_search(&start) {
+            series[index] += clicks;
+            range_clicks += clicks;
+        }
+    }
+
+    let mut referrers = HashMap::new();
+    for (referrer, clicks) in &counts.referrers {
+        *referrers
+            .entry(referrer_key(referrer.as_deref()))
+            .or_insert(0) += clicks;
+    }
+
+    let mut browsers = HashMap::new();
+    let mut systems = HashMap::new();
+    let mut devices = HashMap::new();
+    for (user_agent, clicks) in &counts.user_agents {
+        let agent = parse_user_agent(user_agent.as_deref().unwrap_or_default());
+        *browsers.entry(agent.browser).or_insert(0) += clicks;
+        *systems.entry(agent.os).or_insert(0) += clicks;
+        *devices.entry(agent.device).or_insert(0) += clicks;
+    }
+
+    UrlStats {
+        id: url.id,
+        short_code: url.short_code.clone(),
+        total_clicks,
+        range_clicks,
+        granularity: range.granularity.as_str().to_string(),
+        from: range.from.to_rfc3339(),
+        to: range.to.to_rfc3339(),
+        series: starts
+            .into_iter()
+            .zip(series)
+            .map(|(start, clicks)| TimeBucket {
+                start: start.to_rfc3339(),
+                clicks,
+            })
+            .collect(),
+        top_referrers: top_entries(referrers, top),
+        browsers: top_entries(browsers, top),
+        operating_systems: top_entries(systems, top),
+        devices: top_entries(devices, top),
+    }
+}
+
+// 来源按域名归并，没有 Referer 的视为直接访问
+fn referrer_key(referrer: Option<&str>) -> String {
+    match referrer {
+        None => DIRECT_REFERRER.to_string(),
+        Some(value) => url::Url::parse(value)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_else(|| value.to_string()),
+    }
+}
+
+fn top_entries(counts: HashMap<String, i64>, top: usize) -> Vec<BreakdownEntry> {
+    let mut entries: Vec<BreakdownEntry> = counts
+        .into_iter()
+        .map(|(key, clicks)| BreakdownEntry { key, clicks })
+        .collect();
+    entries.sort_by(|a, b| b.clicks.cmp(&a.clicks).then_with(|| a.key.cmp(&b.key)));
+    entries.truncate(top);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn test_url() -> ShortUrl {
+        ShortUrl {
+            id: 1,
+            long_url: "https://example.com".to_string(),
+            short_code: "stats".to_string(),
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            expires_at: None,
+            user_id: "test_user".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_week_truncates_to_monday() {
+        // 2024-01-04 is a Thursday
+        let ts = Utc.with_ymd_and_hms(2024, 1, 4, 15, 30, 0).unwrap();
+        let monday = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(Granularity::Week.truncate(ts), monday);
+    }
+
+    #[test]
+    fn test_range_validation() {
+        let from = Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        assert!(StatsRange::new(Granularity::Day, Some(from), Some(to)).is_err());
+
+        // Two years of hourly buckets is too many
+        let from = Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        assert!(StatsRange::new(Granularity::Hour, Some(from), Some(to)).is_err());
+        assert!(StatsRange::new(Granularity::Week, Some(from), Some(to)).is_ok());
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let expected = Utc.with_ymd_and_hms(2024, 3, 5, 0, 0, 0).unwrap();
+        assert_eq!(parse_timestamp("2024-03-05").unwrap(), expected);
+        assert_eq!(
+            parse_timestamp("2024-03-05T08:00:00+08:00").unwrap(),
+            expected
+        );
+        assert!(parse_timestamp("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_build_stats_buckets_and_breakdowns() {
+        let range = StatsRange::new(
+            Granularity::Day,
+            Some(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()),
+            Some(Utc.with_ymd_and_hms(2024, 1, 4, 0, 0, 0).unwrap()),
+        )
+        .unwrap();
+
+        let chrome = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+        let iphone = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_1 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1 Mobile/15E148 Safari/604.1";
+        let day = |d| {
+            Utc.with_ymd_and_hms(2024, 1, d, 0, 0, 0)
+                .unwrap()
+                .timestamp()
+        };
+
+        let counts = ClickCounts {
+            buckets: vec![(day(1), 2), (day(3), 1)],
+            referrers: vec![
+                (Some("https://news.example.org/a".to_string()), 1),
+                (Some("https://news.example.org/b".to_string()), 1),
+                (None, 1),
+            ],
+            user_agents: vec![(Some(chrome.to_string()), 2), (Some(iphone.to_string()), 1)],
+        };
+
+        let stats = build_stats(&test_url(), 4, &counts, &range, DEFAULT_TOP);
+
+        assert_eq!(stats.total_clicks, 4);
+        assert_eq!(stats.range_clicks, 3);
+        assert_eq!(stats.granularity, "day");
+        assert_eq!(
+            stats.series.iter().map(|b| b.clicks).collect::<Vec<_>>(),
+            vec![2, 0, 1]
+        );
+
+        assert_eq!(stats.top_referrers[0].key, "news.example.org");
+        assert_eq!(stats.top_referrers[0].clicks, 2);
+        assert_eq!(stats.top_referrers[1].key, DIRECT_REFERRER);
+
+        assert_eq!(stats.browsers[0].key, "Chrome");
+        assert_eq!(stats.browsers[0].clicks, 2);
+        assert_eq!(stats.operating_systems[0].key, "Windows");
+        assert_eq!(stats.devices[0].key, "Desktop");
+        assert_eq!(stats.devices[1].key, "Mobile");
+    }
+
+    #[tokio::test]
+    async fn test_bucket_sql_matches_truncate() {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        for granularity in [Granularity::Hour, Granularity::Day, Granularity::Week] {
+            for clicked_at in [
+                "2024-01-04T15:30:00+00:00",
+                // Sunday evening in UTC+8 is still Sunday in UTC
+                "2024-01-08T07:59:59.123456789+08:00",
+                "2024-01-08T00:00:00+00:00",
+            ] {
+                let sql = format!(
+                    "SELECT {} FROM (SELECT ? AS clicked_at)",
+                    granularity.bucket_sql()
+                );
+                let bucket: i64 = sqlx::query_scalar(&sql)
+                    .bind(clicked_at)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+                let expected = granularity.truncate(parse_timestamp(clicked_at).unwrap());
+                assert_eq!(
+                    bucket,
+                    expected.timestamp(),
+                    "{:?} {}",
+                    granularity,
+                    clicked_at
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_top_entries_limit() {
+        let counts = HashMap::from([
+            ("a".to_string(), 1),
+            ("b".to_string(), 3),
+            ("c".to_string(), 2),
+        ]);
+        let entries = top_entries(counts, 2);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key, "b");
+        assert_eq!(entries[1].key, "c");
+    }
+}
@@ -0,0 +1,663 @@
+use crate::config::{AppConfig, CustomCodeConfig};
+use crate::error::AppError;
+use crate::pb::abi::ShortUrl;
+use crate::services::url_policy::UrlPolicy;
+use crate::store::{LinkStore, LinkUpdate, NewLink};
+use crate::utils::url_validator::{is_valid_url, normalize_url};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+
+// 导出时每次从存储中读取的条数
+pub const EXPORT_BATCH_SIZE: usize = 500;
+pub const MAX_IMPORT_BYTES: usize = 10 * 1024 * 1024;
+
+const CSV_HEADER: [&str; 5] = [
+    "short_code",
+    "long_url",
+    "created_at",
+    "expires_at",
+    "user_id",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferFormat {
+    Csv,
+    Ndjson,
+}
+
+impl TransferFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            TransferFormat::Csv => "text/csv; charset=utf-8",
+            TransferFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TransferFormat::Csv => "csv",
+            TransferFormat::Ndjson => "ndjson",
+        }
+    }
+
+    // 按上传文件的扩展名判断格式
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let (_, extension) = filename.rsplit_once('.')?;
+        extension.to_ascii_lowercase().parse().ok()
+    }
+}
+
+impl FromStr for TransferFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(TransferFormat::Csv),
+            "ndjson" | "jsonl" => Ok(TransferFormat::Ndjson),
+            _ => Err(format!("Invalid format: {}", s)),
+        }
+    }
+}
+
+// 导入的短码已存在时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    #[default]
+    Skip,
+    Overwrite,
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(ConflictPolicy::Skip),
+            "overwrite" => Ok(ConflictPolicy::Overwrite),
+            _ => Err(format!("Invalid on_conflict: {}", s)),
+        }
+    }
+}
+
+// 导出文件中的一行，导入时 created_at、expires_at 和 user_id 可以为空
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkRecord {
+    pub short_code: String,
+    pub long_url: String,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub expires_at: Option<String>,
+    #[serde(default)]
+    pub user_id: Option<String>,
+}
+
+impl From<ShortUrl> for LinkRecord {
+    fn from(url: ShortUrl) -> Self {
+        Self {
+            short_code: url.short_code,
+            long_url: url.long_url,
+            created_at: Some(url.created_at),
+            expires_at: url.expires_at,
+            user_id: Some(url.user_id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    Created,
+    Overwritten,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportRowResult {
+    // 行号从 1 开始，CSV 包含表头行
+    pub line: u64,
+    pub short_code: String,
+    pub status: ImportStatus,
+    pub error_code: Option<&'static str>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct ImportReport {
+    pub created: usize,
+    pub overwritten: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+impl ImportReport {
+    fn push(&mut self, line: u64, short_code: String, outcome: Result<ImportStatus, AppError>) {
+        let (status, error_code, message) = match outcome {
+            Ok(status) => (status, None, None),
+            Err(err) => {
+                let error = err.to_error_response();
+                (
+                    ImportStatus::Failed,
+                    Some(err.error_code()),
+                    Some(error.message),
+                )
+            }
+        };
+        match status {
+            ImportStatus::Created => self.created += 1,
+            ImportStatus::Overwritten => self.overwritten += 1,
+            ImportStatus::Skipped => self.skipped += 1,
+            ImportStatus::Failed => self.failed += 1,
+        }
+        self.rows.push(ImportRowResult {
+            line,
+            short_code,
+            status,
+            error_code,
+            message,
+        });
+    }
+}
+
+// 按 id 分批读取并编码，user_id 为 None 时导出所有用户的链接。CSV 的第一块包含表头
+pub fn export(
+    store: Arc<dyn LinkStore>,
+    user_id: Option<String>,
+    format: TransferFormat,
+) -> impl Stream<Item = Result<Vec<u8>, AppError>> {
+    // None 表示已经读完
+    let state = (store, user_id, Some(0), true);
+    stream::unfold(state, move |(store, user_id, after_id, first)| async move {
+        let after_id = after_id?;
+        let urls = match store
+            .list_after(user_id.as_deref(), after_id, EXPORT_BATCH_SIZE)
+            .await
+        {
+            Ok(urls) => urls,
+            Err(e) => return Some((Err(e), (store, user_id, None, false))),
+        };
+        if urls.is_empty() && !first {
+            return None;
+        }
+
+        let next = match urls.last() {
+            Some(last) if urls.len() == EXPORT_BATCH_SIZE => Some(last.id),
+            _ => None,
+        };
+        let records: Vec<LinkRecord> = urls.into_iter().map(LinkRecord::from).collect();
+        let chunk = encode(format, &records, first);
+        Some((chunk, (store, user_id, next, false)))
+    })
+}
+
+fn encode(
+    format: TransferFormat,
+    records: &[LinkRecord],
+    header: bool,
+) -> Result<Vec<u8>, AppError> {
+    let internal = |e: &dyn std::fmt::Display| AppError::Internal(e.to_string());
+    match format {
+        TransferFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(Vec::new());
+            if header {
+                writer.write_record(CSV_HEADER).map_err(|e| internal(&e))?;
+            }
+            for record in records {
+                writer.serialize(record).map_err(|e| internal(&e))?;
+            }
+            writer.into_inner().map_err(|e| internal(&e))
+        }
+        TransferFormat::Ndjson => {
+            let mut buf = Vec::new();
+            for record in records {
+                serde_json::to_writer(&mut buf, record).map_err(|e| internal(&e))?;
+                buf.push(b'\n');
+            }
+            Ok(buf)
+        }
+    }
+}
+
+// 解析上传的文件，返回每条记录及其行号，无法解析的行返回错误信息
+pub fn parse(format: TransferFormat, data: &[u8]) -> Vec<(u64, Result<LinkRecord, String>)> {
+    match format {
+        TransferFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(data);
+            reader
+                .deserialize::<LinkRecord>()
+                .enumerate()
+                .map(|(i, row)| {
+                    // 出错时没有位置信息，按表头之后的第 i 行计算
+                    let line = match &row {
+                        Err(e) => e.position().map_or(i as u64 + 2, |pos| pos.line()),
+                        Ok(_) => i as u64 + 2,
+                    };
+                    (line, row.map_err(|e| format!("Invalid CSV row: {}", e)))
+                })
+                .collect()
+        }
+        TransferFormat::Ndjson => String::from_utf8_lossy(data)
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                let row = serde_json::from_str::<LinkRecord>(line)
+                    .map_err(|e| format!("Invalid JSON line: {}", e));
+                (i as u64 + 1, row)
+            })
+            .collect(),
+    }
+}
+
+// 校验一行记录并转换为待插入的链接。短码总是按自定义短码的规则校验，
+// 普通用户导入的链接归属于自己，管理员导入时保留记录中的 user_id
+fn to_new_link(
+    record: LinkRecord,
+    codes: &CustomCodeConfig,
+    url_policy: &UrlPolicy,
+    importer: &str,
+    is_admin: bool,
+    now: DateTime<Utc>,
+) -> Result<NewLink, AppError> {
+    let short_code = super::check_code(codes, "short_code", &record.short_code)?;
+    if !is_valid_url(&record.long_url) && !is_valid_url(&normalize_url(&record.long_url)) {
+        return Err(AppError::InvalidUrl);
+    }
+    let long_url = normalize_url(&record.long_url);
+    url_policy.check(&long_url)?;
+
+    let timestamp = |field: &str, value: Option<String>| -> Result<Option<String>, AppError> {
+        value
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                DateTime::parse_from_rfc3339(&value)
+                    .map(|ts| ts.with_timezone(&Utc).to_rfc3339())
+                    .map_err(|_| AppError::InvalidRequest(format!("Invalid {}: {}", field, value)))
+            })
+            .transpose()
+    };
+    let created_at = timestamp("created_at", record.created_at)?;
+    let expires_at = timestamp("expires_at", record.expires_at)?;
+
+    let user_id = match record.user_id.filter(|user_id| !user_id.is_empty()) {
+        Some(user_id) if is_admin => user_id,
+        _ => importer.to_string(),
+    };
+
+    Ok(NewLink {
+        id: None,
+        long_url,
+        short_code,
+        created_at: created_at.unwrap_or_else(|| now.to_rfc3339()),
+        expires_at,
+        user_id,
+    })
+}
+
+// 导入 parse 的结果。新链接在一个事务中插入；覆盖已有链接时只修改 long_url 和 expires_at，
+// 普通用户不能覆盖其他用户的链接
+pub async fn import(
+    store: &dyn LinkStore,
+    config: &AppConfig,
+    url_policy: &UrlPolicy,
+    importer: &str,
+    rows: Vec<(u64, Result<LinkRecord, String>)>,
+    on_conflict: ConflictPolicy,
+    now: DateTime<Utc>,
+) -> Result<ImportReport, AppError> {
+    let is_admin = config.is_admin(importer);
+    let mut report = ImportReport::default();
+    let mut seen = HashSet::new();
+    let mut pending = Vec::new();
+
+    for (line, row) in rows {
+        let record = match row {
+            Ok(record) => record,
+            Err(message) => {
+                report.push(line, String::new(), Err(AppError::InvalidRequest(message)));
+                continue;
+            }
+        };
+        let short_code = record.short_code.clone();
+        let link = match to_new_link(
+            record,
+            &config.custom_code,
+            url_policy,
+            importer,
+            is_admin,
+            now,
+        ) {
+            Ok(link) => link,
+            Err(err) => {
+                report.push(line, short_code, Err(err));
+                continue;
+            }
+        };
+        let short_code = link.short_code.clone();
+        if !seen.insert(short_code.clone()) {
+            let err = AppError::InvalidRequest("Duplicate short_code in file".to_string());
+            report.push(line, short_code, Err(err));
+            continue;
+        }
+
+        let outcome = match (store.get_by_code(&short_code).await?, on_conflict) {
+            (None, _) => {
+                pending.push((line, link));
+                continue;
+            }
+            (Some(_), ConflictPolicy::Skip) => Ok(ImportStatus::Skipped),
+            (Some(url), ConflictPolicy::Overwrite) if url.user_id != importer && !is_admin => {
+                Err(AppError::CodeTaken)
+            }
+            (Some(url), ConflictPolicy::Overwrite) => {
+                let update = LinkUpdate {
+                    long_url: Some(link.long_url),
+                    short_code: None,
+                    expires_at: Some(link.expires_at),
+                };
+                store
+                    .update(url.id, &url.user_id, &update)
+                    .await
+                    .map(|_| ImportStatus::Overwritten)
+            }
+        };
+        report.push(line, short_code, outcome);
+    }
+
+    let (lines, links): (Vec<u64>, Vec<NewLink>) = pending.into_iter().unzip();
+    let codes: Vec<String> = links.iter().map(|link| link.short_code.clone()).collect();
+    let results = store.insert_batch(links, false).await?;
+    for ((line, short_code), result) in lines.into_iter().zip(codes).zip(results) {
+        report.push(line, short_code, result.map(|_| ImportStatus::Created));
+    }
+    report.rows.sort_by_key(|row| row.line);
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryLinkStore;
+    use futures::TryStreamExt;
+
+    fn now() -> DateTime<Utc> {
+        "2024-06-01T00:00:00Z".parse().unwrap()
+    }
+
+    fn link(short_code: &str, user_id: &str) -> NewLink {
+        NewLink {
+            id: None,
+            long_url: format!("https://example.com/{}", short_code),
+            short_code: short_code.to_string(),
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            expires_at: None,
+            user_id: user_id.to_string(),
+        }
+    }
+
+    async fn export_to_string(
+        store: Arc<dyn LinkStore>,
+        user_id: Option<&str>,
+        format: TransferFormat,
+    ) -> String {
+        let chunks: Vec<Vec<u8>> = export(store, user_id.map(str::to_string), format)
+            .try_collect()
+            .await
+            .unwrap();
+        String::from_utf8(chunks.concat()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_export_formats() {
+        let store = Arc::new(MemoryLinkStore::new());
+        for i in 0..EXPORT_BATCH_SIZE + 1 {
+            store
+                .insert(link(&format!("a{}", i), "alice"))
+                .await
+                .unwrap();
+        }
+        store
+            .insert(NewLink {
+                expires_at: Some("2025-01-01T00:00:00+00:00".to_string()),
+                ..link("b0", "bob")
+            })
+            .await
+            .unwrap();
+
+        // Spans two batches, header only once
+        let csv = export_to_string(store.clone(), Some("alice"), TransferFormat::Csv).await;
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), EXPORT_BATCH_SIZE + 2);
+        assert_eq!(
+            lines[0],
+            "short_code,long_url,created_at,expires_at,user_id"
+        );
+        assert_eq!(
+            lines[1],
+            "a0,https://example.com/a0,2024-01-01T00:00:00+00:00,,alice"
+        );
+
+        let ndjson = export_to_string(store.clone(), None, TransferFormat::Ndjson).await;
+        assert_eq!(ndjson.lines().count(), EXPORT_BATCH_SIZE + 2);
+        let last: LinkRecord = serde_json::from_str(ndjson.lines().last().unwrap()).unwrap();
+        assert_eq!(last.user_id.as_deref(), Some("bob"));
+        assert_eq!(
+            last.expires_at.as_deref(),
+            Some("2025-01-01T00:00:00+00:00")
+        );
+
+        // Nothing to export still yields a CSV header
+        let empty = export_to_string(store, Some("carol"), TransferFormat::Csv).await;
+        assert_eq!(empty, "short_code,long_url,created_at,expires_at,user_id\n");
+    }
+
+    #[tokio::test]
+    async fn test_export_then_import_round_trip() {
+        let source: Arc<dyn LinkStore> = Arc::new(MemoryLinkStore::new());
+        source.insert(link("one", "alice")).await.unwrap();
+        source.insert(link("two", "alice")).await.unwrap();
+
+        for format in [TransferFormat::Csv, TransferFormat::Ndjson] {
+            let data = export_to_string(source.clone(), Some("alice"), format).await;
+            let target = MemoryLinkStore::new();
+            let report = import(
+                &target,
+                &AppConfig::default(),
+                &UrlPolicy::new(&AppConfig::default()),
+                "alice",
+                parse(format, data.as_bytes()),
+                ConflictPolicy::Skip,
+                now(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(report.created, 2, "{:?}", format);
+            assert_eq!(
+                target.get_by_code("two").await.unwrap().unwrap().created_at,
+                "2024-01-01T00:00:00+00:00"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import_validation_and_conflicts() {
+        let store = MemoryLinkStore::new();
+        store.insert(link("mine", "alice")).await.unwrap();
+        store.insert(link("theirs", "bob")).await.unwrap();
+
+        let data = "\
+short_code,long_url,created_at,expires_at,user_id
+new,example.com/new,,,bob
+mine,https://example.com/changed,,2030-01-01T00:00:00Z,
+theirs,https://example.com/stolen,,,
+bad,not a url,,,
+new,https://example.com/again,,,
+late,https://example.com/late,,tomorrow,
+";
+        let config = AppConfig::default();
+        let url_policy = UrlPolicy::new(&config);
+        let import_with = |on_conflict| {
+            import(
+                &store,
+                &config,
+                &url_policy,
+                "alice",
+                parse(TransferFormat::Csv, data.as_bytes()),
+                on_conflict,
+                now(),
+            )
+        };
+
+        let report = import_with(ConflictPolicy::Skip).await.unwrap();
+        let statuses: Vec<(u64, ImportStatus)> = report
+            .rows
+            .iter()
+            .map(|row| (row.line, row.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                (2, ImportStatus::Created),
+                (3, ImportStatus::Skipped),
+                (4, ImportStatus::Skipped),
+                (5, ImportStatus::Failed),
+                (6, ImportStatus::Failed),
+                (7, ImportStatus::Failed),
+            ]
+        );
+        assert_eq!(report.rows[3].error_code, Some("INVALID_URL"));
+        // The importer owns the link regardless of the user_id column
+        let created = store.get_by_code("new").await.unwrap().unwrap();
+        assert_eq!(created.user_id, "alice");
+        assert_eq!(created.long_url, "http://example.com/new");
+        assert_eq!(created.created_at, "2024-06-01T00:00:00+00:00");
+
+        let report = import_with(ConflictPolicy::Overwrite).await.unwrap();
+        assert_eq!(report.rows[0].status, ImportStatus::Overwritten);
+        assert_eq!(report.rows[1].status, ImportStatus::Overwritten);
+        // Other users' links can't be overwritten
+        assert_eq!(report.rows[2].status, ImportStatus::Failed);
+        assert_eq!(report.rows[2].error_code, Some("CODE_TAKEN"));
+        assert_eq!((report.overwritten, report.failed), (2, 4));
+
+        let mine = store.get_by_code("mine").await.unwrap().unwrap();
+        assert_eq!(mine.long_url, "https://example.com/changed");
+        assert_eq!(
+            mine.expires_at.as_deref(),
+            Some("2030-01-01T00:00:00+00:00")
+        );
+        assert_eq!(
+            store.get_by_code("theirs").await.unwrap().unwrap().long_url,
+            "https://example.com/theirs"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_admin_import_keeps_owner() {
+        let store = MemoryLinkStore::new();
+        let data =
+            "{\"short_code\":\"xxx\",\"long_url\":\"https://example.com\",\"user_id\":\"bob\"}\n\n\
+                    {\"short_code\":\"yyy\",\"long_url\":\"https://example.com\"}\n\
+                    not json\n";
+        let mut config = AppConfig::default();
+        config.auth.admin_users = vec!["admin".to_string()];
+        let report = import(
+            &store,
+            &config,
+            &UrlPolicy::new(&config),
+            "admin",
+            parse(TransferFormat::Ndjson, data.as_bytes()),
+            ConflictPolicy::Skip,
+            now(),
+        )
+        .await
+        .unwrap();
+        assert_eq!((report.created, report.failed), (2, 1));
+        assert_eq!(report.rows[2].line, 4);
+        assert_eq!(
+            store.get_by_code("xxx").await.unwrap().unwrap().user_id,
+            "bob"
+        );
+        assert_eq!(
+            store.get_by_code("yyy").await.unwrap().unwrap().user_id,
+            "admin"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_checks_custom_codes() {
+        let data = "short_code,long_url\napi,https://example.com\nok-code,https://example.com\n";
+        let mut config = AppConfig::default();
+        config.auth.admin_users = vec!["admin".to_string()];
+        // Applies to admins restoring backups as well
+        for importer in ["alice", "admin"] {
+            let store = MemoryLinkStore::new();
+            let report = import(
+                &store,
+                &config,
+                &UrlPolicy::new(&config),
+                importer,
+                parse(TransferFormat::Csv, data.as_bytes()),
+                ConflictPolicy::Skip,
+                now(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(report.created, 1, "{}", importer);
+            assert_eq!(report.rows[0].error_code, Some("INVALID_FIELD"));
+            assert_eq!(
+                report.rows[0].message.as_deref(),
+                Some("short_code 'api' is reserved")
+            );
+            assert!(store.get_by_code("api").await.unwrap().is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import_checks_url_policy() {
+        let data = "short_code,long_url\none,https://example.com\ntwo,ftp://example.com\n\
+                    three,127.0.0.1/admin\n";
+        let mut config = AppConfig::default();
+        config.auth.admin_users = vec!["admin".to_string()];
+        let store = MemoryLinkStore::new();
+        // Applies to admins restoring backups as well
+        let report = import(
+            &store,
+            &config,
+            &UrlPolicy::new(&config),
+            "admin",
+            parse(TransferFormat::Csv, data.as_bytes()),
+            ConflictPolicy::Skip,
+            now(),
+        )
+        .await
+        .unwrap();
+        assert_eq!((report.created, report.failed), (1, 2));
+        assert_eq!(report.rows[1].error_code, Some("URL_NOT_ALLOWED"));
+        assert_eq!(report.rows[2].error_code, Some("URL_NOT_ALLOWED"));
+        assert!(store.get_by_code("three").await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_format_names() {
+        assert_eq!("jsonl".parse(), Ok(TransferFormat::Ndjson));
+        assert_eq!(
+            TransferFormat::from_filename("backup.CSV"),
+            Some(TransferFormat::Csv)
+        );
+        assert_eq!(TransferFormat::from_filename("backup"), None);
+        assert!("xml".parse::<TransferFormat>().is_err());
+        assert!("replace".parse::<ConflictPolicy>().is_err());
+    }
+}
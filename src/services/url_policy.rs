@@ -0,0 +1,264 @@
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::utils::url_validator::is_internal_ip;
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::sync::RwLock;
+use url::{Host, Url};
+
+// 目标 URL 的安全策略，创建、修改和导入链接时检查。屏蔽列表从 url_policy.blocklist_file
+// 读取，可以在运行时重新加载，在 HTTP 和 gRPC 之间共享
+#[derive(Debug)]
+pub struct UrlPolicy {
+    allowed_schemes: Vec<String>,
+    block_private_ips: bool,
+    blocklist_file: Option<String>,
+    // server.public_base_url 的主机名，指向它的链接会造成重定向循环
+    own_host: Option<String>,
+    blocklist: RwLock<Blocklist>,
+}
+
+// 屏蔽的主机名。每行一个，"#" 之后为注释，"*.example.com" 屏蔽 example.com 及其所有子域名
+#[derive(Debug, Default, PartialEq)]
+pub struct Blocklist {
+    hosts: HashSet<String>,
+    domains: HashSet<String>,
+}
+
+impl Blocklist {
+    pub fn parse(content: &str) -> Self {
+        let mut blocklist = Self::default();
+        for line in content.lines() {
+            let entry = line.split('#').next().unwrap_or_default().trim();
+            let entry = entry.trim_end_matches('.').to_ascii_lowercase();
+            if entry.is_empty() {
+                continue;
+            }
+            match entry.strip_prefix("*.") {
+                Some(domain) => blocklist.domains.insert(domain.to_string()),
+                None => blocklist.hosts.insert(entry),
+            };
+        }
+        blocklist
+    }
+
+    pub fn len(&self) -> usize {
+        self.hosts.len() + self.domains.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // host 为小写、不带末尾 "." 的主机名
+    fn matches(&self, host: &str) -> bool {
+        self.hosts.contains(host)
+            || std::iter::successors(Some(host), |host| {
+                host.split_once('.').map(|(_, rest)| rest)
+            })
+            .any(|domain| self.domains.contains(domain))
+    }
+}
+
+impl UrlPolicy {
+    // 不读取屏蔽列表文件，见 load
+    pub fn new(config: &AppConfig) -> Self {
+        let own_host = Url::parse(config.base_url())
+            .ok()
+            .and_then(|url| url.host_str().map(normalize_host));
+        Self {
+            allowed_schemes: config.url_policy.allowed_schemes.clone(),
+            block_private_ips: config.url_policy.block_private_ips,
+            blocklist_file: config.url_policy.blocklist_file.clone(),
+            own_host,
+            blocklist: RwLock::new(Blocklist::default()),
+        }
+    }
+
+    pub fn load(config: &AppConfig) -> std::io::Result<Self> {
+        let policy = Self::new(config);
+        policy.reload()?;
+        Ok(policy)
+    }
+
+    // 重新读取屏蔽列表文件，返回条目数。读取失败时保留原来的列表，未配置文件时为空
+    pub fn reload(&self) -> std::io::Result<usize> {
+        let blocklist = match &self.blocklist_file {
+            Some(path) => Blocklist::parse(&std::fs::read_to_string(path)?),
+            None => Blocklist::default(),
+        };
+        let len = blocklist.len();
+        *self.blocklist.write().unwrap() = blocklist;
+        Ok(len)
+    }
+
+    pub fn blocklist_len(&self) -> usize {
+        self.blocklist.read().unwrap().len()
+    }
+
+    // url 应已通过 is_valid_url 校验
+    pub fn check(&self, url: &str) -> Result<(), AppError> {
+        let url = Url::parse(url).map_err(|_| AppError::InvalidUrl)?;
+        if !self
+            .allowed_schemes
+            .iter()
+            .any(|scheme| scheme == url.scheme())
+        {
+            return Err(AppError::UrlNotAllowed(format!(
+                "URL scheme '{}' is not allowed",
+                url.scheme()
+            )));
+        }
+
+        let ip = match url.host() {
+            Some(Host::Ipv4(ip)) => Some(IpAddr::V4(ip)),
+            Some(Host::Ipv6(ip)) => Some(IpAddr::V6(ip)),
+            Some(Host::Domain(_)) => None,
+            None => return Err(AppError::InvalidUrl),
+        };
+        if self.block_private_ips && ip.is_some_and(is_internal_ip) {
+            return Err(AppError::UrlNotAllowed(
+                "URLs pointing to private or local addresses are not allowed".to_string(),
+            ));
+        }
+
+        let host = url.host_str().map(normalize_host).unwrap_or_default();
+        if self.own_host.as_deref() == Some(host.as_str()) {
+            return Err(AppError::UrlNotAllowed(
+                "URLs pointing to this service are not allowed".to_string(),
+            ));
+        }
+        if self.blocklist.read().unwrap().matches(&host) {
+            return Err(AppError::UrlNotAllowed(format!(
+                "Host '{}' is blocked",
+                host
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reason(policy: &UrlPolicy, url: &str) -> String {
+        match policy.check(url) {
+            Err(AppError::UrlNotAllowed(reason)) => reason,
+            other => panic!("{} should not be allowed: {:?}", url, other),
+        }
+    }
+
+    #[test]
+    fn test_blocklist_parse() {
+        let blocklist = Blocklist::parse(
+            "# phishing\nEvil.example\n\n*.bad.example  # and subdomains\nTrailing.example.\n",
+        );
+        assert_eq!(blocklist.len(), 3);
+        assert!(blocklist.matches("evil.example"));
+        assert!(!blocklist.matches("www.evil.example"));
+        assert!(blocklist.matches("bad.example"));
+        assert!(blocklist.matches("a.b.bad.example"));
+        assert!(!blocklist.matches("notbad.example"));
+        assert!(blocklist.matches("trailing.example"));
+        assert!(Blocklist::parse("# nothing\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_check() {
+        let mut config = AppConfig::default();
+        config.server.public_base_url = "https://sho.rt".to_string();
+        let policy = UrlPolicy::new(&config);
+        *policy.blocklist.write().unwrap() = Blocklist::parse("*.evil.example");
+
+        for url in [
+            "https://example.com/path",
+            "http://8.8.8.8/",
+            "https://[2001:4860::8888]/",
+            "https://sho.rt.example.com/",
+        ] {
+            assert!(policy.check(url).is_ok(), "{}", url);
+        }
+
+        assert_eq!(
+            reason(&policy, "ftp://example.com/file"),
+            "URL scheme 'ftp' is not allowed"
+        );
+        assert_eq!(
+            reason(&policy, "javascript://example.com/%0Aalert(1)"),
+            "URL scheme 'javascript' is not allowed"
+        );
+        for url in [
+            "http://127.0.0.1:8080/",
+            "http://10.0.0.1/",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/",
+            "http://[fe80::1]/",
+            "http://[::ffff:192.168.0.1]/",
+            // Alternative IPv4 notations are parsed as IP addresses
+            "http://0x7f.1/",
+            "http://2130706433/",
+        ] {
+            assert_eq!(
+                reason(&policy, url),
+                "URLs pointing to private or local addresses are not allowed",
+                "{}",
+                url
+            );
+        }
+        for url in ["https://sho.rt/abc123", "http://SHO.RT./abc123"] {
+            assert_eq!(
+                reason(&policy, url),
+                "URLs pointing to this service are not allowed"
+            );
+        }
+        assert_eq!(
+            reason(&policy, "https://login.Evil.example/"),
+            "Host 'login.evil.example' is blocked"
+        );
+    }
+
+    #[test]
+    fn test_check_with_config() {
+        let mut config = AppConfig::default();
+        config.url_policy.allowed_schemes = vec!["https".to_string(), "ftp".to_string()];
+        config.url_policy.block_private_ips = false;
+        let policy = UrlPolicy::new(&config);
+
+        assert!(policy.check("ftp://example.com/file").is_ok());
+        assert!(policy.check("https://192.168.1.1/").is_ok());
+        assert!(policy.check("http://example.com/").is_err());
+        // The default public_base_url is http://localhost:8080
+        assert!(policy.check("https://localhost/").is_err());
+    }
+
+    #[test]
+    fn test_reload() {
+        let path = std::env::temp_dir().join(format!("url-blocklist-{}.txt", std::process::id()));
+        std::fs::write(&path, "evil.example\n").unwrap();
+
+        let mut config = AppConfig::default();
+        config.url_policy.blocklist_file = Some(path.to_string_lossy().into_owned());
+        let policy = UrlPolicy::load(&config).unwrap();
+        assert_eq!(policy.blocklist_len(), 1);
+        assert!(policy.check("https://evil.example/").is_err());
+        assert!(policy.check("https://other.example/").is_ok());
+
+        std::fs::write(&path, "other.example\n*.phish.example\n").unwrap();
+        assert_eq!(policy.reload().unwrap(), 2);
+        assert!(policy.check("https://evil.example/").is_ok());
+        assert!(policy.check("https://other.example/").is_err());
+
+        // A failed reload keeps the current list
+        std::fs::remove_file(&path).unwrap();
+        assert!(policy.reload().is_err());
+        assert_eq!(policy.blocklist_len(), 2);
+
+        config.url_policy.blocklist_file = None;
+        assert_eq!(UrlPolicy::load(&config).unwrap().blocklist_len(), 0);
+    }
+}
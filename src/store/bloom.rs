@@ -0,0 +1,416 @@
+use super::{batch_committed, LinkChange, LinkPage, LinkStore, LinkUpdate, ListQuery, NewLink};
+use crate::config::{BloomFilterConfig, ReaperMode};
+use crate::error::AppError;
+use crate::pb::abi::ShortUrl;
+use crate::utils::bloom_filter::CountingBloomFilter;
+use async_trait::async_trait;
+use std::sync::{Arc, RwLock};
+
+// 布隆过滤器判定不存在的短码直接返回，不访问内部存储（包括缓存）。
+// 过滤器只在当前进程内维护，多实例部署时其他实例新建的短码在重启前会被误判为不存在
+pub struct BloomLinkStore {
+    inner: Arc<dyn LinkStore>,
+    filter: RwLock<CountingBloomFilter>,
+}
+
+impl BloomLinkStore {
+    // 从内部存储加载全部短码构建过滤器，容量至少为现有短码数量的两倍
+    pub async fn build(
+        inner: Arc<dyn LinkStore>,
+        config: &BloomFilterConfig,
+    ) -> Result<Self, AppError> {
+        let codes = inner.all_codes().await?;
+        let expected_items = config.expected_items.max(codes.len() * 2);
+
+        let mut filter = CountingBloomFilter::new(expected_items, config.false_positive_rate);
+        for code in &codes {
+            filter.insert(code);
+        }
+        log::info!(
+            "Bloom filter built with {} codes ({} counters, {} hashes)",
+            codes.len(),
+            filter.size(),
+            filter.hashes()
+        );
+
+        Ok(Self {
+            inner,
+            filter: RwLock::new(filter),
+        })
+    }
+
+    fn might_contain(&self, short_code: &str) -> bool {
+        self.filter.read().unwrap().contains(short_code)
+    }
+}
+
+#[async_trait]
+impl LinkStore for BloomLinkStore {
+    // 短码在写入前加入过滤器，写入失败后再移除，避免新链接在提交后短暂被判定为不存在
+    async fn insert(&self, link: NewLink) -> Result<ShortUrl, AppError> {
+        let short_code = link.short_code.clone();
+        self.filter.write().unwrap().insert(&short_code);
+        let result = self.inner.insert(link).await;
+        if result.is_err() {
+            self.filter.write().unwrap().remove(&short_code);
+        }
+        result
+    }
+
+    async fn insert_batch(
+        &self,
+        links: Vec<NewLink>,
+        atomic: bool,
+    ) -> Result<Vec<Result<ShortUrl, AppError>>, AppError> {
+        let codes: Vec<String> = links.iter().map(|link| link.short_code.clone()).collect();
+        {
+            let mut filter = self.filter.write().unwrap();
+            for code in &codes {
+                filter.insert(code);
+            }
+        }
+
+        let result = self.inner.insert_batch(links, atomic).await;
+        let mut filter = self.filter.write().unwrap();
+        match &result {
+            Ok(results) if batch_committed(results, atomic) => {
+                for (code, result) in codes.iter().zip(results) {
+                    if result.is_err() {
+                        filter.remove(code);
+                    }
+                }
+            }
+            _ => {
+                for code in &codes {
+                    filter.remove(code);
+                }
+            }
+        }
+        result
+    }
+
+    async fn reserve_ids(&self, count: usize) -> Result<Vec<i64>, AppError> {
+        self.inner.reserve_ids(count).await
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<ShortUrl>, AppError> {
+        self.inner.get_by_id(id).await
+    }
+
+    async fn get_by_code(&self, short_code: &str) -> Result<Option<ShortUrl>, AppError> {
+        if !self.might_contain(short_code) {
+            return Ok(None);
+        }
+        self.inner.get_by_code(short_code).await
+    }
+
+    async fn list_by_user(&self, user_id: &str) -> Result<Vec<ShortUrl>, AppError> {
+        self.inner.list_by_user(user_id).await
+    }
+
+    async fn list_after(
+        &self,
+        user_id: Option<&str>,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<ShortUrl>, AppError> {
+        self.inner.list_after(user_id, after_id, limit).await
+    }
+
+    async fn find_by_url(
+        &self,
+        user_id: &str,
+        long_url: &str,
+        now: &str,
+    ) -> Result<Option<ShortUrl>, AppError> {
+        self.inner.find_by_url(user_id, long_url, now).await
+    }
+
+    async fn list_page(&self, user_id: &str, query: &ListQuery) -> Result<LinkPage, AppError> {
+        self.inner.list_page(user_id, query).await
+    }
+
+    async fn update(
+        &self,
+        id: i64,
+        user_id: &str,
+        update: &LinkUpdate,
+    ) -> Result<Option<LinkChange>, AppError> {
+        // 新短码同样先加入过滤器，修改失败或短码未变时再移除
+        if let Some(short_code) = &update.short_code {
+            self.filter.write().unwrap().insert(short_code);
+        }
+        let result = self.inner.update(id, user_id, update).await;
+        if let Some(short_code) = &update.short_code {
+            let mut filter = self.filter.write().unwrap();
+            match &result {
+                Ok(Some(LinkChange { before, .. })) if &before.short_code != short_code => {
+                    filter.remove(&before.short_code);
+                }
+                _ => filter.remove(short_code),
+            }
+        }
+        result
+    }
+
+    async fn delete(&self, id: i64, user_id: &str) -> Result<Option<ShortUrl>, AppError> {
+        let deleted = self.inner.delete(id, user_id).await?;
+        if let Some(url) = &deleted {
+            self.filter.write().unwrap().remove(&url.short_code);
+        }
+        Ok(deleted)
+    }
+
+    // 生成短码时判断是否冲突，多数候选短码在这里就能确定不存在
+    async fn exists(&self, short_code: &str) -> Result<bool, AppError> {
+        if !self.might_contain(short_code) {
+            return Ok(false);
+        }
+        self.inner.exists(short_code).await
+    }
+
+    async fn all_codes(&self) -> Result<Vec<String>, AppError> {
+        self.inner.all_codes().await
+    }
+
+    async fn count_by_code_length(&self, length: usize) -> Result<u64, AppError> {
+        self.inner.count_by_code_length(length).await
+    }
+
+    async fn backfill(&self, limit: usize) -> Result<usize, AppError> {
+        self.inner.backfill(limit).await
+    }
+
+    async fn remove_expired(
+        &self,
+        before: &str,
+        mode: ReaperMode,
+        limit: usize,
+    ) -> Result<Vec<ShortUrl>, AppError> {
+        let removed = self.inner.remove_expired(before, mode, limit).await?;
+        let mut filter = self.filter.write().unwrap();
+        for url in &removed {
+            filter.remove(&url.short_code);
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryLinkStore;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // 记录 get_by_code / exists 的调用次数
+    struct CountingStore {
+        inner: MemoryLinkStore,
+        lookups: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LinkStore for CountingStore {
+        async fn insert(&self, link: NewLink) -> Result<ShortUrl, AppError> {
+            self.inner.insert(link).await
+        }
+
+        async fn insert_batch(
+            &self,
+            links: Vec<NewLink>,
+            atomic: bool,
+        ) -> Result<Vec<Result<ShortUrl, AppError>>, AppError> {
+            self.inner.insert_batch(links, atomic).await
+        }
+
+        async fn reserve_ids(&self, count: usize) -> Result<Vec<i64>, AppError> {
+            self.inner.reserve_ids(count).await
+        }
+
+        async fn get_by_id(&self, id: i64) -> Result<Option<ShortUrl>, AppError> {
+            self.inner.get_by_id(id).await
+        }
+
+        async fn get_by_code(&self, short_code: &str) -> Result<Option<ShortUrl>, AppError> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            self.inner.get_by_code(short_code).await
+        }
+
+        async fn list_by_user(&self, user_id: &str) -> Result<Vec<ShortUrl>, AppError> {
+            self.inner.list_by_user(user_id).await
+        }
+
+        async fn list_after(
+            &self,
+            user_id: Option<&str>,
+            after_id: i64,
+            limit: usize,
+        ) -> Result<Vec<ShortUrl>, AppError> {
+            self.inner.list_after(user_id, after_id, limit).await
+        }
+
+        async fn find_by_url(
+            &self,
+            user_id: &str,
+            long_url: &str,
+            now: &str,
+        ) -> Result<Option<ShortUrl>, AppError> {
+            self.inner.find_by_url(user_id, long_url, now).await
+        }
+
+        async fn list_page(&self, user_id: &str, query: &ListQuery) -> Result<LinkPage, AppError> {
+            self.inner.list_page(user_id, query).await
+        }
+
+        async fn update(
+            &self,
+            id: i64,
+            user_id: &str,
+            update: &LinkUpdate,
+        ) -> Result<Option<LinkChange>, AppError> {
+            self.inner.update(id, user_id, update).await
+        }
+
+        async fn delete(&self, id: i64, user_id: &str) -> Result<Option<ShortUrl>, AppError> {
+            self.inner.delete(id, user_id).await
+        }
+
+        async fn exists(&self, short_code: &str) -> Result<bool, AppError> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            self.inner.exists(short_code).await
+        }
+
+        async fn all_codes(&self) -> Result<Vec<String>, AppError> {
+            self.inner.all_codes().await
+        }
+
+        async fn count_by_code_length(&self, length: usize) -> Result<u64, AppError> {
+            self.inner.count_by_code_length(length).await
+        }
+
+        async fn backfill(&self, limit: usize) -> Result<usize, AppError> {
+            self.inner.backfill(limit).await
+        }
+
+        async fn remove_expired(
+            &self,
+            before: &str,
+            mode: ReaperMode,
+            limit: usize,
+        ) -> Result<Vec<ShortUrl>, AppError> {
+            self.inner.remove_expired(before, mode, limit).await
+        }
+    }
+
+    fn new_link(short_code: &str) -> NewLink {
+        NewLink {
+            id: None,
+            long_url: "https://example.com".to_string(),
+            short_code: short_code.to_string(),
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            expires_at: None,
+            user_id: "alice".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bloom_store_behaves_like_store() {
+        let store = BloomLinkStore::build(
+            Arc::new(MemoryLinkStore::new()),
+            &BloomFilterConfig::default(),
+        )
+        .await
+        .unwrap();
+        crate::store::tests::exercise_store(&store).await;
+    }
+
+    #[tokio::test]
+    async fn test_unknown_codes_skip_inner_store() {
+        let inner = Arc::new(CountingStore {
+            inner: MemoryLinkStore::new(),
+            lookups: AtomicUsize::new(0),
+        });
+        inner.insert(new_link("existing")).await.unwrap();
+
+        // Existing codes are loaded at build time
+        let store = BloomLinkStore::build(inner.clone(), &BloomFilterConfig::default())
+            .await
+            .unwrap();
+        assert!(store.get_by_code("existing").await.unwrap().is_some());
+        assert_eq!(inner.lookups.load(Ordering::SeqCst), 1);
+
+        for i in 0..100 {
+            let code = format!("scan{}", i);
+            assert!(store.get_by_code(&code).await.unwrap().is_none());
+            assert!(!store.exists(&code).await.unwrap());
+        }
+        assert!(inner.lookups.load(Ordering::SeqCst) < 10);
+
+        // Created and deleted codes update the filter
+        let created = store.insert(new_link("created")).await.unwrap();
+        assert!(store.get_by_code("created").await.unwrap().is_some());
+        assert!(store.delete(created.id, "alice").await.unwrap().is_some());
+
+        let before = inner.lookups.load(Ordering::SeqCst);
+        assert!(store.get_by_code("created").await.unwrap().is_none());
+        assert_eq!(inner.lookups.load(Ordering::SeqCst), before);
+    }
+
+    #[tokio::test]
+    async fn test_failed_writes_leave_filter_unchanged() {
+        let store = BloomLinkStore::build(
+            Arc::new(MemoryLinkStore::new()),
+            &BloomFilterConfig::default(),
+        )
+        .await
+        .unwrap();
+        let url = store.insert(new_link("kept")).await.unwrap();
+
+        // Taken code: the filter still counts it once, so a delete clears it
+        assert!(store.insert(new_link("kept")).await.is_err());
+
+        // A rolled back batch removes every code it added
+        let results = store
+            .insert_batch(vec![new_link("rolled"), new_link("kept")], true)
+            .await
+            .unwrap();
+        assert!(results[1].is_err());
+        assert!(!store.might_contain("rolled"));
+
+        // A partial batch keeps only the saved codes
+        let results = store
+            .insert_batch(vec![new_link("saved"), new_link("kept")], false)
+            .await
+            .unwrap();
+        assert!(results[0].is_ok() && results[1].is_err());
+        assert!(store.might_contain("saved"));
+
+        // Renaming moves the code, a failed rename changes nothing
+        let rename = |short_code: &str| LinkUpdate {
+            short_code: Some(short_code.to_string()),
+            ..LinkUpdate::default()
+        };
+        assert!(store
+            .update(url.id, "alice", &rename("saved"))
+            .await
+            .is_err());
+        assert!(store
+            .update(url.id, "bob", &rename("other"))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(!store.might_contain("other"));
+        store
+            .update(url.id, "alice", &rename("kept"))
+            .await
+            .unwrap();
+        store
+            .update(url.id, "alice", &rename("moved"))
+            .await
+            .unwrap();
+        assert!(store.might_contain("moved"));
+
+        store.delete(url.id, "alice").await.unwrap();
+        assert!(!store.might_contain("moved"));
+        assert!(!store.might_contain("kept"));
+    }
+}
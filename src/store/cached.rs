@@ -0,0 +1,434 @@
+use super::{LinkChange, LinkPage, LinkStore, LinkUpdate, ListQuery, NewLink};
+use crate::config::{CacheConfig, ReaperMode};
+use crate::error::AppError;
+use crate::pb::abi::ShortUrl;
+use async_trait::async_trait;
+use lru::LruCache;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// 缓存的查询结果，None 表示短码不存在
+struct Entry {
+    url: Option<ShortUrl>,
+    expires_at: Instant,
+}
+
+// 失效计数的分组数，短码按哈希分组，同组短码共用一个计数
+const GENERATION_SLOTS: usize = 256;
+
+// 短码 -> 短链接的 LRU 缓存，只在当前进程内有效
+pub struct LinkCache {
+    entries: Option<Mutex<LruCache<String, Entry>>>,
+    // 每次 invalidate 时加一。未命中后先记下计数再查询内部存储，写入缓存时计数已变化
+    // 说明期间链接被修改或删除，查询到的结果可能已过时，不再写入
+    generations: Vec<AtomicU64>,
+    ttl: Duration,
+    negative_ttl: Duration,
+    hits: AtomicU64,
+    negative_hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CacheStats {
+    pub enabled: bool,
+    pub capacity: usize,
+    pub entries: usize,
+    pub hits: u64,
+    pub negative_hits: u64,
+    pub misses: u64,
+}
+
+impl LinkCache {
+    pub fn new(config: &CacheConfig) -> Self {
+        let entries = NonZeroUsize::new(config.capacity)
+            .filter(|_| config.enabled)
+            .map(|capacity| Mutex::new(LruCache::new(capacity)));
+
+        Self {
+            entries,
+            generations: (0..GENERATION_SLOTS).map(|_| AtomicU64::new(0)).collect(),
+            ttl: Duration::from_secs(config.ttl_secs),
+            negative_ttl: Duration::from_secs(config.negative_ttl_secs),
+            hits: AtomicU64::new(0),
+            negative_hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    // 外层 None 表示未命中，内层 None 表示命中了“不存在”
+    fn get(&self, short_code: &str) -> Option<Option<ShortUrl>> {
+        let mut entries = self.entries.as_ref()?.lock().unwrap();
+
+        let cached = match entries.get(short_code) {
+            Some(entry) if entry.expires_at > Instant::now() => Some(entry.url.clone()),
+            Some(_) => {
+                entries.pop(short_code);
+                None
+            }
+            None => None,
+        };
+
+        match &cached {
+            Some(Some(_)) => self.hits.fetch_add(1, Ordering::Relaxed),
+            Some(None) => self.negative_hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        cached
+    }
+
+    fn slot(&self, short_code: &str) -> &AtomicU64 {
+        let mut hasher = DefaultHasher::new();
+        short_code.hash(&mut hasher);
+        &self.generations[hasher.finish() as usize % GENERATION_SLOTS]
+    }
+
+    // 查询内部存储之前调用，结果传给 put
+    fn generation(&self, short_code: &str) -> u64 {
+        self.slot(short_code).load(Ordering::SeqCst)
+    }
+
+    fn put(&self, short_code: &str, url: Option<ShortUrl>, generation: u64) {
+        let Some(entries) = &self.entries else {
+            return;
+        };
+
+        let ttl = if url.is_some() {
+            self.ttl
+        } else {
+            self.negative_ttl
+        };
+        if ttl.is_zero() {
+            return;
+        }
+
+        // 在锁内比较，与 invalidate 的加一和清除互斥
+        let mut entries = entries.lock().unwrap();
+        if self.generation(short_code) != generation {
+            return;
+        }
+        entries.put(
+            short_code.to_string(),
+            Entry {
+                url,
+                expires_at: Instant::now() + ttl,
+            },
+        );
+    }
+
+    pub fn invalidate(&self, short_code: &str) {
+        if let Some(entries) = &self.entries {
+            let mut entries = entries.lock().unwrap();
+            self.slot(short_code).fetch_add(1, Ordering::SeqCst);
+            entries.pop(short_code);
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let (capacity, entries) = match &self.entries {
+            Some(entries) => {
+                let entries = entries.lock().unwrap();
+                (entries.cap().get(), entries.len())
+            }
+            None => (0, 0),
+        };
+
+        CacheStats {
+            enabled: self.entries.is_some(),
+            capacity,
+            entries,
+            hits: self.hits.load(Ordering::Relaxed),
+            negative_hits: self.negative_hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// 为 get_by_code 加上缓存，其余操作直接交给内部存储，修改时清除对应缓存
+pub struct CachedLinkStore {
+    inner: Arc<dyn LinkStore>,
+    cache: Arc<LinkCache>,
+}
+
+impl CachedLinkStore {
+    pub fn new(inner: Arc<dyn LinkStore>, cache: Arc<LinkCache>) -> Self {
+        Self { inner, cache }
+    }
+}
+
+#[async_trait]
+impl LinkStore for CachedLinkStore {
+    async fn insert(&self, link: NewLink) -> Result<ShortUrl, AppError> {
+        let short_code = link.short_code.clone();
+        let result = self.inner.insert(link).await;
+        // 清除“不存在”的缓存
+        self.cache.invalidate(&short_code);
+        result
+    }
+
+    async fn insert_batch(
+        &self,
+        links: Vec<NewLink>,
+        atomic: bool,
+    ) -> Result<Vec<Result<ShortUrl, AppError>>, AppError> {
+        let codes: Vec<String> = links.iter().map(|link| link.short_code.clone()).collect();
+        let result = self.inner.insert_batch(links, atomic).await;
+        for code in &codes {
+            self.cache.invalidate(code);
+        }
+        result
+    }
+
+    async fn reserve_ids(&self, count: usize) -> Result<Vec<i64>, AppError> {
+        self.inner.reserve_ids(count).await
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<ShortUrl>, AppError> {
+        self.inner.get_by_id(id).await
+    }
+
+    async fn get_by_code(&self, short_code: &str) -> Result<Option<ShortUrl>, AppError> {
+        if let Some(cached) = self.cache.get(short_code) {
+            return Ok(cached);
+        }
+
+        let generation = self.cache.generation(short_code);
+        let url = self.inner.get_by_code(short_code).await?;
+        self.cache.put(short_code, url.clone(), generation);
+        Ok(url)
+    }
+
+    async fn list_by_user(&self, user_id: &str) -> Result<Vec<ShortUrl>, AppError> {
+        self.inner.list_by_user(user_id).await
+    }
+
+    async fn list_after(
+        &self,
+        user_id: Option<&str>,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<ShortUrl>, AppError> {
+        self.inner.list_after(user_id, after_id, limit).await
+    }
+
+    async fn find_by_url(
+        &self,
+        user_id: &str,
+        long_url: &str,
+        now: &str,
+    ) -> Result<Option<ShortUrl>, AppError> {
+        self.inner.find_by_url(user_id, long_url, now).await
+    }
+
+    async fn list_page(&self, user_id: &str, query: &ListQuery) -> Result<LinkPage, AppError> {
+        self.inner.list_page(user_id, query).await
+    }
+
+    async fn update(
+        &self,
+        id: i64,
+        user_id: &str,
+        update: &LinkUpdate,
+    ) -> Result<Option<LinkChange>, AppError> {
+        let change = self.inner.update(id, user_id, update).await?;
+        if let Some(change) = &change {
+            self.cache.invalidate(&change.before.short_code);
+            // 新短码可能缓存了“不存在”
+            self.cache.invalidate(&change.after.short_code);
+        }
+        Ok(change)
+    }
+
+    async fn delete(&self, id: i64, user_id: &str) -> Result<Option<ShortUrl>, AppError> {
+        let deleted = self.inner.delete(id, user_id).await?;
+        if let Some(url) = &deleted {
+            self.cache.invalidate(&url.short_code);
+        }
+        Ok(deleted)
+    }
+
+    async fn exists(&self, short_code: &str) -> Result<bool, AppError> {
+        self.inner.exists(short_code).await
+    }
+
+    async fn all_codes(&self) -> Result<Vec<String>, AppError> {
+        self.inner.all_codes().await
+    }
+
+    async fn count_by_code_length(&self, length: usize) -> Result<u64, AppError> {
+        self.inner.count_by_code_length(length).await
+    }
+
+    async fn backfill(&self, limit: usize) -> Result<usize, AppError> {
+        self.inner.backfill(limit).await
+    }
+
+    async fn remove_expired(
+        &self,
+        before: &str,
+        mode: ReaperMode,
+        limit: usize,
+    ) -> Result<Vec<ShortUrl>, AppError> {
+        let removed = self.inner.remove_expired(before, mode, limit).await?;
+        for url in &removed {
+            self.cache.invalidate(&url.short_code);
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryLinkStore;
+
+    fn cached_store(config: &CacheConfig) -> (CachedLinkStore, Arc<LinkCache>) {
+        let cache = Arc::new(LinkCache::new(config));
+        let store = CachedLinkStore::new(Arc::new(MemoryLinkStore::new()), cache.clone());
+        (store, cache)
+    }
+
+    fn new_link(short_code: &str) -> NewLink {
+        NewLink {
+            id: None,
+            long_url: "https://example.com".to_string(),
+            short_code: short_code.to_string(),
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            expires_at: None,
+            user_id: "alice".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_store_behaves_like_store() {
+        let (store, _) = cached_store(&CacheConfig::default());
+        crate::store::tests::exercise_store(&store).await;
+    }
+
+    #[tokio::test]
+    async fn test_hits_and_misses() {
+        let (store, cache) = cached_store(&CacheConfig::default());
+        store.insert(new_link("hot")).await.unwrap();
+
+        for _ in 0..3 {
+            let url = store.get_by_code("hot").await.unwrap();
+            assert_eq!(url.unwrap().short_code, "hot");
+        }
+        let stats = cache.stats();
+        assert_eq!((stats.misses, stats.hits), (1, 2));
+        assert_eq!(stats.entries, 1);
+    }
+
+    #[tokio::test]
+    async fn test_negative_cache_cleared_on_insert() {
+        let (store, cache) = cached_store(&CacheConfig::default());
+
+        assert!(store.get_by_code("later").await.unwrap().is_none());
+        assert!(store.get_by_code("later").await.unwrap().is_none());
+        assert_eq!(cache.stats().negative_hits, 1);
+
+        store.insert(new_link("later")).await.unwrap();
+        assert!(store.get_by_code("later").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_invalidated_on_delete() {
+        let (store, _) = cached_store(&CacheConfig::default());
+        let url = store.insert(new_link("gone")).await.unwrap();
+        assert!(store.get_by_code("gone").await.unwrap().is_some());
+
+        // Wrong owner keeps the entry
+        assert_eq!(store.delete(url.id, "bob").await.unwrap(), None);
+        assert!(store.get_by_code("gone").await.unwrap().is_some());
+
+        assert_eq!(store.delete(url.id, "alice").await.unwrap(), Some(url));
+        assert!(store.get_by_code("gone").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_invalidated_on_update() {
+        let (store, _) = cached_store(&CacheConfig::default());
+        let url = store.insert(new_link("before")).await.unwrap();
+        assert!(store.get_by_code("before").await.unwrap().is_some());
+        assert!(store.get_by_code("after").await.unwrap().is_none());
+
+        let update = LinkUpdate {
+            long_url: Some("https://example.com/new".to_string()),
+            short_code: Some("after".to_string()),
+            ..LinkUpdate::default()
+        };
+        store.update(url.id, "alice", &update).await.unwrap();
+        assert!(store.get_by_code("before").await.unwrap().is_none());
+        assert_eq!(
+            store.get_by_code("after").await.unwrap().unwrap().long_url,
+            "https://example.com/new"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stale_read_not_cached_after_invalidation() {
+        let inner = Arc::new(MemoryLinkStore::new());
+        let cache = Arc::new(LinkCache::new(&CacheConfig::default()));
+        let store = CachedLinkStore::new(inner.clone(), cache.clone());
+        let url = store.insert(new_link("racy")).await.unwrap();
+
+        // Replay get_by_code step by step with a delete between the read and the fill
+        assert!(cache.get("racy").is_none());
+        let generation = cache.generation("racy");
+        let stale = inner.get_by_code("racy").await.unwrap();
+        assert!(stale.is_some());
+        store.delete(url.id, "alice").await.unwrap();
+        cache.put("racy", stale, generation);
+
+        assert!(store.get_by_code("racy").await.unwrap().is_none());
+
+        // Without an invalidation in between the fill goes through
+        let generation = cache.generation("fresh");
+        cache.put("fresh", None, generation);
+        assert_eq!(cache.get("fresh"), Some(None));
+    }
+
+    #[tokio::test]
+    async fn test_capacity_and_ttl() {
+        let config = CacheConfig {
+            capacity: 2,
+            negative_ttl_secs: 0,
+            ..CacheConfig::default()
+        };
+        let (store, cache) = cached_store(&config);
+        for code in ["a", "b", "c"] {
+            store.insert(new_link(code)).await.unwrap();
+            store.get_by_code(code).await.unwrap();
+        }
+        assert_eq!(cache.stats().entries, 2);
+
+        // Least recently used entry was evicted
+        store.get_by_code("a").await.unwrap();
+        assert_eq!(cache.stats().misses, 4);
+
+        // Unknown codes are not cached when negative_ttl_secs is 0
+        store.get_by_code("missing").await.unwrap();
+        store.get_by_code("missing").await.unwrap();
+        assert_eq!(cache.stats().negative_hits, 0);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_cache() {
+        let config = CacheConfig {
+            enabled: false,
+            ..CacheConfig::default()
+        };
+        let (store, cache) = cached_store(&config);
+        store.insert(new_link("cold")).await.unwrap();
+        store.get_by_code("cold").await.unwrap();
+
+        let stats = cache.stats();
+        assert!(!stats.enabled);
+        assert_eq!((stats.hits, stats.misses, stats.entries), (0, 0, 0));
+    }
+}
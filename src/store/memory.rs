@@ -0,0 +1,267 @@
+use super::{batch_committed, LinkChange, LinkPage, LinkStore, LinkUpdate, ListQuery, NewLink};
+use crate::config::ReaperMode;
+use crate::error::AppError;
+use crate::pb::abi::ShortUrl;
+use crate::utils::url_validator::url_hash;
+use async_trait::async_trait;
+use std::sync::Mutex;
+
+// 内存存储，用于测试，不持久化
+#[derive(Default)]
+pub struct MemoryLinkStore {
+    state: Mutex<State>,
+}
+
+#[derive(Default, Clone)]
+struct State {
+    next_id: i64,
+    links: Vec<ShortUrl>,
+}
+
+impl State {
+    fn insert(&mut self, link: NewLink) -> Result<ShortUrl, AppError> {
+        if self
+            .links
+            .iter()
+            .any(|url| url.short_code == link.short_code)
+        {
+            return Err(AppError::CodeTaken);
+        }
+
+        let id = match link.id {
+            Some(id) => {
+                self.next_id = self.next_id.max(id);
+                id
+            }
+            None => {
+                self.next_id += 1;
+                self.next_id
+            }
+        };
+        let url = ShortUrl {
+            id,
+            long_url: link.long_url,
+            short_code: link.short_code,
+            created_at: link.created_at,
+            expires_at: link.expires_at,
+            user_id: link.user_id,
+        };
+        self.links.push(url.clone());
+        Ok(url)
+    }
+}
+
+impl MemoryLinkStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl LinkStore for MemoryLinkStore {
+    async fn insert(&self, link: NewLink) -> Result<ShortUrl, AppError> {
+        self.state.lock().unwrap().insert(link)
+    }
+
+    async fn insert_batch(
+        &self,
+        links: Vec<NewLink>,
+        atomic: bool,
+    ) -> Result<Vec<Result<ShortUrl, AppError>>, AppError> {
+        let mut state = self.state.lock().unwrap();
+        let saved = state.clone();
+        let results: Vec<_> = links.into_iter().map(|link| state.insert(link)).collect();
+        if !batch_committed(&results, atomic) {
+            *state = saved;
+        }
+        Ok(results)
+    }
+
+    async fn reserve_ids(&self, count: usize) -> Result<Vec<i64>, AppError> {
+        let mut state = self.state.lock().unwrap();
+        let first = state.next_id + 1;
+        state.next_id += count as i64;
+        Ok((first..=state.next_id).collect())
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<ShortUrl>, AppError> {
+        let state = self.state.lock().unwrap();
+        Ok(state.links.iter().find(|url| url.id == id).cloned())
+    }
+
+    async fn get_by_code(&self, short_code: &str) -> Result<Option<ShortUrl>, AppError> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .links
+            .iter()
+            .find(|url| url.short_code == short_code)
+            .cloned())
+    }
+
+    async fn list_by_user(&self, user_id: &str) -> Result<Vec<ShortUrl>, AppError> {
+        let state = self.state.lock().unwrap();
+        let mut urls: Vec<ShortUrl> = state
+            .links
+            .iter()
+            .filter(|url| url.user_id == user_id)
+            .cloned()
+            .collect();
+        urls.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(urls)
+    }
+
+    async fn list_after(
+        &self,
+        user_id: Option<&str>,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<ShortUrl>, AppError> {
+        let state = self.state.lock().unwrap();
+        let mut urls: Vec<ShortUrl> = state
+            .links
+            .iter()
+            .filter(|url| url.id > after_id && user_id.is_none_or(|user_id| url.user_id == user_id))
+            .cloned()
+            .collect();
+        urls.sort_by_key(|url| url.id);
+        urls.truncate(limit);
+        Ok(urls)
+    }
+
+    async fn find_by_url(
+        &self,
+        user_id: &str,
+        long_url: &str,
+        now: &str,
+    ) -> Result<Option<ShortUrl>, AppError> {
+        let hash = url_hash(long_url);
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .links
+            .iter()
+            .filter(|url| url.user_id == user_id && url_hash(&url.long_url) == hash)
+            .find(|url| url.expires_at.as_deref().is_none_or(|at| at > now))
+            .cloned())
+    }
+
+    async fn list_page(&self, user_id: &str, query: &ListQuery) -> Result<LinkPage, AppError> {
+        let state = self.state.lock().unwrap();
+        Ok(query.apply(
+            state
+                .links
+                .iter()
+                .filter(|url| url.user_id == user_id)
+                .cloned(),
+        ))
+    }
+
+    async fn update(
+        &self,
+        id: i64,
+        user_id: &str,
+        update: &LinkUpdate,
+    ) -> Result<Option<LinkChange>, AppError> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(short_code) = &update.short_code {
+            if state
+                .links
+                .iter()
+                .any(|url| &url.short_code == short_code && url.id != id)
+            {
+                return Err(AppError::CodeTaken);
+            }
+        }
+
+        let Some(url) = state
+            .links
+            .iter_mut()
+            .find(|url| url.id == id && url.user_id == user_id)
+        else {
+            return Ok(None);
+        };
+        let before = url.clone();
+        if let Some(long_url) = &update.long_url {
+            url.long_url = long_url.clone();
+        }
+        if let Some(short_code) = &update.short_code {
+            url.short_code = short_code.clone();
+        }
+        if let Some(expires_at) = &update.expires_at {
+            url.expires_at = expires_at.clone();
+        }
+        Ok(Some(LinkChange {
+            before,
+            after: url.clone(),
+        }))
+    }
+
+    async fn delete(&self, id: i64, user_id: &str) -> Result<Option<ShortUrl>, AppError> {
+        let mut state = self.state.lock().unwrap();
+        let index = state
+            .links
+            .iter()
+            .position(|url| url.id == id && url.user_id == user_id);
+        Ok(index.map(|index| state.links.remove(index)))
+    }
+
+    async fn exists(&self, short_code: &str) -> Result<bool, AppError> {
+        let state = self.state.lock().unwrap();
+        Ok(state.links.iter().any(|url| url.short_code == short_code))
+    }
+
+    async fn all_codes(&self) -> Result<Vec<String>, AppError> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .links
+            .iter()
+            .map(|url| url.short_code.clone())
+            .collect())
+    }
+
+    async fn count_by_code_length(&self, length: usize) -> Result<u64, AppError> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .links
+            .iter()
+            .filter(|url| url.short_code.chars().count() == length)
+            .count() as u64)
+    }
+
+    // 内存存储不保留归档，两种模式都直接删除
+    // 主机名在过滤时计算，没有需要补齐的数据
+    async fn backfill(&self, _limit: usize) -> Result<usize, AppError> {
+        Ok(0)
+    }
+
+    async fn remove_expired(
+        &self,
+        before: &str,
+        _mode: ReaperMode,
+        limit: usize,
+    ) -> Result<Vec<ShortUrl>, AppError> {
+        let mut state = self.state.lock().unwrap();
+        let mut expired: Vec<ShortUrl> = state
+            .links
+            .iter()
+            .filter(|url| url.expires_at.as_deref().is_some_and(|at| at < before))
+            .cloned()
+            .collect();
+        expired.sort_by(|a, b| a.expires_at.cmp(&b.expires_at));
+        expired.truncate(limit);
+
+        state
+            .links
+            .retain(|url| !expired.iter().any(|removed| removed.id == url.id));
+        Ok(expired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_link_store() {
+        crate::store::tests::exercise_store(&MemoryLinkStore::new()).await;
+    }
+}
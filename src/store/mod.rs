@@ -0,0 +1,684 @@
+pub mod bloom;
+pub mod cached;
+pub mod memory;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod query;
+pub mod sqlite;
+
+use crate::config::ReaperMode;
+use crate::db::Backend;
+use crate::error::AppError;
+use crate::pb::abi::ShortUrl;
+use actix_web::web;
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+pub use bloom::BloomLinkStore;
+pub use cached::{CachedLinkStore, LinkCache};
+pub use memory::MemoryLinkStore;
+#[cfg(feature = "postgres")]
+pub use postgres::PgLinkStore;
+pub use query::{Cursor, LinkPage, LinkStatus, ListQuery, Sort, SortField};
+pub use sqlite::SqliteLinkStore;
+
+// 启动时补齐数据每批处理的条数
+pub(crate) const BACKFILL_BATCH_SIZE: usize = 1000;
+
+// 待插入的短链接
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewLink {
+    // 通过 LinkStore::reserve_ids 预先分配的 id，为 None 时由存储层分配
+    pub id: Option<i64>,
+    pub long_url: String,
+    pub short_code: String,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+    pub user_id: String,
+}
+
+// 批量插入的结果是否已保存
+pub fn batch_committed(results: &[Result<ShortUrl, AppError>], atomic: bool) -> bool {
+    !atomic || results.iter().all(Result::is_ok)
+}
+
+// 对已有短链接的修改，None 表示该字段不变
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinkUpdate {
+    pub long_url: Option<String>,
+    pub short_code: Option<String>,
+    // Some(None) 表示清除过期时间
+    pub expires_at: Option<Option<String>>,
+}
+
+// LinkStore::update 的结果，包含修改前后的链接，供装饰器清理旧短码
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkChange {
+    pub before: ShortUrl,
+    pub after: ShortUrl,
+}
+
+// 短链接存储，UrlService 和各接口只依赖该 trait
+#[async_trait]
+pub trait LinkStore: Send + Sync {
+    // 短码已存在时返回 AppError::CodeTaken
+    async fn insert(&self, link: NewLink) -> Result<ShortUrl, AppError>;
+
+    // 在一个事务中依次插入，返回每条的结果。每条使用独立的保存点，失败的条目不影响其他条目；
+    // atomic 为 true 且有条目失败时回滚整个事务，返回的成功结果均未保存
+    async fn insert_batch(
+        &self,
+        links: Vec<NewLink>,
+        atomic: bool,
+    ) -> Result<Vec<Result<ShortUrl, AppError>>, AppError>;
+
+    // 预先分配 count 个行 id，之后不会再分配给其他链接。依赖行 id 的短码生成策略
+    // 先分配 id，插入时就使用最终的短码
+    async fn reserve_ids(&self, count: usize) -> Result<Vec<i64>, AppError>;
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<ShortUrl>, AppError>;
+
+    async fn get_by_code(&self, short_code: &str) -> Result<Option<ShortUrl>, AppError>;
+
+    // 按创建时间倒序
+    async fn list_by_user(&self, user_id: &str) -> Result<Vec<ShortUrl>, AppError>;
+
+    // 按 id 升序列出 id 大于 after_id 的最多 limit 条链接，user_id 为 None 时不限用户，用于导出
+    async fn list_after(
+        &self,
+        user_id: Option<&str>,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<ShortUrl>, AppError>;
+
+    // 用户指向同一规范化 URL（见 url_hash）且在 now 时未过期的链接，有多条时返回最早创建的
+    async fn find_by_url(
+        &self,
+        user_id: &str,
+        long_url: &str,
+        now: &str,
+    ) -> Result<Option<ShortUrl>, AppError>;
+
+    // 按条件分页列出用户的链接
+    async fn list_page(&self, user_id: &str, query: &ListQuery) -> Result<LinkPage, AppError>;
+
+    // 修改属于 user_id 的链接，返回修改前后的链接，链接不存在时返回 None。
+    // 新短码已被占用时返回 AppError::CodeTaken
+    async fn update(
+        &self,
+        id: i64,
+        user_id: &str,
+        update: &LinkUpdate,
+    ) -> Result<Option<LinkChange>, AppError>;
+
+    // 返回被删除的链接，没有删除记录时返回 None
+    async fn delete(&self, id: i64, user_id: &str) -> Result<Option<ShortUrl>, AppError>;
+
+    async fn exists(&self, short_code: &str) -> Result<bool, AppError>;
+
+    // 所有短码，用于启动时重建布隆过滤器
+    async fn all_codes(&self) -> Result<Vec<String>, AppError>;
+
+    // 长度为 length 个字符的短码数，用于统计短码空间的使用率
+    async fn count_by_code_length(&self, length: usize) -> Result<u64, AppError>;
+
+    // 为迁移前创建的链接补齐 host 和 url_hash 列，每次最多处理 limit 条，返回处理的条数
+    async fn backfill(&self, limit: usize) -> Result<usize, AppError>;
+
+    // 在一个事务中移除最多 limit 条 expires_at 早于 before 的链接，返回被移除的链接。
+    // Archive 模式下先复制到 short_urls_archive
+    async fn remove_expired(
+        &self,
+        before: &str,
+        mode: ReaperMode,
+        limit: usize,
+    ) -> Result<Vec<ShortUrl>, AppError>;
+}
+
+// 启动时分批补齐迁移前创建的链接，返回补齐的总条数
+pub async fn backfill_all(store: &dyn LinkStore) -> Result<usize, AppError> {
+    let mut total = 0;
+    loop {
+        match store.backfill(BACKFILL_BATCH_SIZE).await? {
+            0 => return Ok(total),
+            count => total += count,
+        }
+    }
+}
+
+// 按 database.url 的协议选择短链接存储，SQLite 时与其他数据共用连接池
+pub async fn connect(url: &str, sqlite: &SqlitePool) -> Result<Arc<dyn LinkStore>, sqlx::Error> {
+    match Backend::from_url(url) {
+        Some(Backend::Postgres) => connect_postgres(url).await,
+        _ => Ok(Arc::new(SqliteLinkStore::new(sqlite.clone()))),
+    }
+}
+
+#[cfg(feature = "postgres")]
+async fn connect_postgres(url: &str) -> Result<Arc<dyn LinkStore>, sqlx::Error> {
+    let pool = crate::db::postgres::create_pool(url).await?;
+    crate::db::postgres::run_migrations(&pool).await?;
+    Ok(Arc::new(PgLinkStore::new(pool)))
+}
+
+#[cfg(not(feature = "postgres"))]
+async fn connect_postgres(_url: &str) -> Result<Arc<dyn LinkStore>, sqlx::Error> {
+    Err(sqlx::Error::Configuration(
+        "PostgreSQL support requires building with the `postgres` feature".into(),
+    ))
+}
+
+// 以 trait 对象的形式注册到 App::app_data
+pub fn app_data<S: LinkStore + 'static>(store: S) -> web::Data<dyn LinkStore> {
+    web::Data::from(Arc::new(store) as Arc<dyn LinkStore>)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    #[cfg(feature = "postgres")]
+    use crate::db;
+
+    // 设置 TEST_POSTGRES_URL 且启用 postgres feature 时，服务层测试使用 PostgreSQL 存储
+    pub(crate) async fn test_store(sqlite: &SqlitePool) -> Box<dyn LinkStore> {
+        #[cfg(feature = "postgres")]
+        if let Some(store) = test_pg_store().await {
+            return Box::new(store);
+        }
+        Box::new(SqliteLinkStore::new(sqlite.clone()))
+    }
+
+    // 每次使用独立的 schema，测试之间互不影响
+    #[cfg(feature = "postgres")]
+    pub(crate) async fn test_pg_store() -> Option<PgLinkStore> {
+        use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+        use std::str::FromStr;
+
+        let url = std::env::var("TEST_POSTGRES_URL").ok()?;
+        let schema = format!("test_{}", &crate::services::auth::generate_token()[..16]);
+
+        let admin = db::postgres::create_pool(&url)
+            .await
+            .expect("Failed to connect to PostgreSQL");
+        sqlx::query(&format!("CREATE SCHEMA {}", schema))
+            .execute(&admin)
+            .await
+            .expect("Failed to create schema");
+        admin.close().await;
+
+        let options = PgConnectOptions::from_str(&url)
+            .expect("Invalid TEST_POSTGRES_URL")
+            .options([("search_path", schema.as_str())]);
+        let pool = PgPoolOptions::new()
+            .max_connections(2)
+            .connect_with(options)
+            .await
+            .expect("Failed to connect to PostgreSQL");
+        db::postgres::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        Some(PgLinkStore::new(pool))
+    }
+
+    fn new_link(short_code: &str, user_id: &str, created_at: &str) -> NewLink {
+        NewLink {
+            id: None,
+            long_url: format!("https://example.com/{}", short_code),
+            short_code: short_code.to_string(),
+            created_at: created_at.to_string(),
+            expires_at: None,
+            user_id: user_id.to_string(),
+        }
+    }
+
+    // 各实现共用的行为测试
+    pub(crate) async fn exercise_store(store: &dyn LinkStore) {
+        let first = store
+            .insert(new_link("first", "alice", "2024-01-01T00:00:00+00:00"))
+            .await
+            .expect("Failed to insert");
+        assert!(first.id > 0);
+        assert_eq!(first.long_url, "https://example.com/first");
+        assert_eq!(first.user_id, "alice");
+
+        let second = store
+            .insert(new_link("second", "alice", "2024-01-02T00:00:00+00:00"))
+            .await
+            .expect("Failed to insert");
+        store
+            .insert(new_link("other", "bob", "2024-01-03T00:00:00+00:00"))
+            .await
+            .expect("Failed to insert");
+        assert_ne!(first.id, second.id);
+
+        // Duplicate code
+        let duplicate = store
+            .insert(new_link("first", "bob", "2024-01-04T00:00:00+00:00"))
+            .await;
+        assert!(matches!(duplicate, Err(AppError::CodeTaken)));
+
+        // Lookups
+        assert_eq!(
+            store.get_by_id(first.id).await.unwrap(),
+            Some(first.clone())
+        );
+        assert_eq!(
+            store.get_by_code("second").await.unwrap(),
+            Some(second.clone())
+        );
+        assert_eq!(store.get_by_code("missing").await.unwrap(), None);
+        assert!(store.exists("first").await.unwrap());
+        assert!(!store.exists("missing").await.unwrap());
+
+        // Newest first, only the user's own links
+        let codes: Vec<String> = store
+            .list_by_user("alice")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|url| url.short_code)
+            .collect();
+        assert_eq!(codes, ["second", "first"]);
+
+        // Keyset scan used by export, optionally limited to one user
+        let all = store.list_after(None, 0, 100).await.unwrap();
+        assert_eq!(all.len(), 3);
+        assert!(all.windows(2).all(|pair| pair[0].id < pair[1].id));
+        let page = store.list_after(Some("alice"), first.id, 1).await.unwrap();
+        assert_eq!(page, std::slice::from_ref(&second));
+        assert!(store
+            .list_after(Some("alice"), second.id, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Reuse lookup compares canonical URLs and only sees the user's own links
+        let now = "2024-06-01T00:00:00+00:00";
+        assert_eq!(
+            store
+                .find_by_url("alice", "HTTPS://EXAMPLE.com:443/first", now)
+                .await
+                .unwrap(),
+            Some(first.clone())
+        );
+        assert_eq!(
+            store
+                .find_by_url("bob", "https://example.com/first", now)
+                .await
+                .unwrap(),
+            None
+        );
+
+        // Delete checks the owner
+        assert_eq!(store.delete(first.id, "bob").await.unwrap(), None);
+        assert_eq!(
+            store.delete(first.id, "alice").await.unwrap(),
+            Some(first.clone())
+        );
+        assert_eq!(store.delete(first.id, "alice").await.unwrap(), None);
+        assert!(!store.exists("first").await.unwrap());
+        assert_eq!(store.get_by_id(first.id).await.unwrap(), None);
+
+        let mut codes = store.all_codes().await.unwrap();
+        codes.sort();
+        assert_eq!(codes, ["other", "second"]);
+        assert_eq!(store.count_by_code_length(5).await.unwrap(), 1);
+        assert_eq!(store.count_by_code_length(6).await.unwrap(), 1);
+        assert_eq!(store.count_by_code_length(7).await.unwrap(), 0);
+
+        // Only links that expired before the cutoff are removed, oldest first
+        for (code, expires_at) in [
+            ("expired1", "2024-02-01T00:00:00+00:00"),
+            ("expired2", "2024-02-02T00:00:00+00:00"),
+            ("expired3", "2024-02-03T00:00:00+00:00"),
+            ("later", "2024-03-01T00:00:00+00:00"),
+        ] {
+            store
+                .insert(NewLink {
+                    expires_at: Some(expires_at.to_string()),
+                    ..new_link(code, "alice", "2024-01-05T00:00:00+00:00")
+                })
+                .await
+                .expect("Failed to insert");
+        }
+        let cutoff = "2024-02-15T00:00:00+00:00";
+        let removed = store
+            .remove_expired(cutoff, ReaperMode::Archive, 2)
+            .await
+            .unwrap();
+        let codes: Vec<String> = removed.into_iter().map(|url| url.short_code).collect();
+        assert_eq!(codes, ["expired1", "expired2"]);
+        assert!(!store.exists("expired1").await.unwrap());
+
+        let removed = store
+            .remove_expired(cutoff, ReaperMode::Delete, 2)
+            .await
+            .unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(store
+            .remove_expired(cutoff, ReaperMode::Delete, 2)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(store.exists("later").await.unwrap());
+        assert!(store.exists("second").await.unwrap());
+        exercise_update(store).await;
+        exercise_insert_batch(store).await;
+        exercise_list_page(store).await;
+    }
+
+    async fn exercise_insert_batch(store: &dyn LinkStore) {
+        store
+            .insert(new_link("b-existing", "frank", "2024-01-01T00:00:00+00:00"))
+            .await
+            .expect("Failed to insert");
+        let batch = |codes: &[&str]| -> Vec<NewLink> {
+            codes
+                .iter()
+                .map(|code| new_link(code, "frank", "2024-01-02T00:00:00+00:00"))
+                .collect()
+        };
+
+        // All-or-nothing: one conflict rolls back the whole batch
+        let results = store
+            .insert_batch(batch(&["b-1", "b-existing", "b-2"]), true)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(AppError::CodeTaken)));
+        assert!(results[2].is_ok());
+        assert!(!store.exists("b-1").await.unwrap());
+        assert!(!store.exists("b-2").await.unwrap());
+
+        // Best effort keeps the links that could be inserted, duplicates in the batch included
+        let results = store
+            .insert_batch(batch(&["b-1", "b-existing", "b-2", "b-1"]), false)
+            .await
+            .unwrap();
+        let ok: Vec<bool> = results.iter().map(Result::is_ok).collect();
+        assert_eq!(ok, [true, false, true, false]);
+        let created = results[0].as_ref().unwrap();
+        assert_eq!(
+            store.get_by_code("b-1").await.unwrap().as_ref(),
+            Some(created)
+        );
+        assert!(store.exists("b-2").await.unwrap());
+
+        // A clean batch commits in either mode
+        let results = store
+            .insert_batch(batch(&["b-3", "b-4"]), true)
+            .await
+            .unwrap();
+        assert!(results.iter().all(Result::is_ok));
+        assert!(store.exists("b-4").await.unwrap());
+        assert!(store
+            .insert_batch(Vec::new(), true)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Reserved ids are not handed out again and are kept on insert
+        let ids = store.reserve_ids(2).await.unwrap();
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+        let reserved = NewLink {
+            id: Some(ids[1]),
+            ..new_link("b-5", "frank", "2024-01-02T00:00:00+00:00")
+        };
+        let results = store
+            .insert_batch(vec![reserved, batch(&["b-6"]).remove(0)], true)
+            .await
+            .unwrap();
+        assert_eq!(results[0].as_ref().unwrap().id, ids[1]);
+        let next = results[1].as_ref().unwrap().id;
+        assert!(ids.iter().all(|&id| id < next));
+        assert!(store.reserve_ids(0).await.unwrap().is_empty());
+    }
+
+    async fn exercise_update(store: &dyn LinkStore) {
+        let url = store
+            .insert(NewLink {
+                expires_at: Some("2024-06-01T00:00:00+00:00".to_string()),
+                ..new_link("u-old", "dave", "2024-01-01T00:00:00+00:00")
+            })
+            .await
+            .expect("Failed to insert");
+        store
+            .insert(new_link("u-taken", "erin", "2024-01-01T00:00:00+00:00"))
+            .await
+            .expect("Failed to insert");
+
+        // Nothing to change leaves the link as it was
+        assert_eq!(
+            store
+                .update(url.id, "dave", &LinkUpdate::default())
+                .await
+                .unwrap(),
+            Some(LinkChange {
+                before: url.clone(),
+                after: url.clone(),
+            })
+        );
+
+        // Only the owner can update
+        let rename = LinkUpdate {
+            short_code: Some("u-new".to_string()),
+            ..LinkUpdate::default()
+        };
+        assert_eq!(store.update(url.id, "erin", &rename).await.unwrap(), None);
+        assert_eq!(store.update(-1, "dave", &rename).await.unwrap(), None);
+
+        let taken = LinkUpdate {
+            short_code: Some("u-taken".to_string()),
+            ..LinkUpdate::default()
+        };
+        assert!(matches!(
+            store.update(url.id, "dave", &taken).await,
+            Err(AppError::CodeTaken)
+        ));
+
+        let change = store
+            .update(
+                url.id,
+                "dave",
+                &LinkUpdate {
+                    long_url: Some("https://example.com/fixed".to_string()),
+                    expires_at: Some(Some("2025-01-01T00:00:00+00:00".to_string())),
+                    ..rename
+                },
+            )
+            .await
+            .unwrap()
+            .expect("Link not found");
+        // The previous row is returned alongside the updated one
+        assert_eq!(change.before, url);
+        let updated = change.after;
+        assert_eq!(updated.id, url.id);
+        assert_eq!(updated.long_url, "https://example.com/fixed");
+        assert_eq!(updated.short_code, "u-new");
+        assert_eq!(
+            updated.expires_at.as_deref(),
+            Some("2025-01-01T00:00:00+00:00")
+        );
+        assert_eq!(updated.created_at, url.created_at);
+
+        // The URL hash follows the new target and expired links are not reused
+        let found =
+            |long_url: &'static str, now: &'static str| store.find_by_url("dave", long_url, now);
+        assert_eq!(
+            found("https://example.com/fixed", "2024-12-01T00:00:00+00:00")
+                .await
+                .unwrap(),
+            Some(updated.clone())
+        );
+        assert_eq!(
+            found("https://example.com/fixed", "2025-02-01T00:00:00+00:00")
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            found("https://example.com/u-old", "2024-12-01T00:00:00+00:00")
+                .await
+                .unwrap(),
+            None
+        );
+        assert!(!store.exists("u-old").await.unwrap());
+        assert_eq!(store.get_by_code("u-new").await.unwrap(), Some(updated));
+
+        // Clearing the expiry
+        let cleared = store
+            .update(
+                url.id,
+                "dave",
+                &LinkUpdate {
+                    expires_at: Some(None),
+                    ..LinkUpdate::default()
+                },
+            )
+            .await
+            .unwrap()
+            .expect("Link not found")
+            .after;
+        assert_eq!(cleared.expires_at, None);
+        assert_eq!(cleared.long_url, "https://example.com/fixed");
+    }
+
+    async fn exercise_list_page(store: &dyn LinkStore) {
+        for (code, long_url, created_at, expires_at) in [
+            ("p-a1", "https://a.com/1", "2024-01-01T00:00:00+00:00", None),
+            (
+                "p-a2",
+                "https://a.com/2",
+                "2024-01-02T00:00:00+00:00",
+                Some("2024-01-10T00:00:00+00:00"),
+            ),
+            (
+                "p-b1",
+                "https://b.com:8443/x",
+                "2024-01-03T00:00:00+00:00",
+                Some("2024-12-01T00:00:00+00:00"),
+            ),
+            (
+                "q-b2",
+                "https://sub.b.com/",
+                "2024-01-04T00:00:00+00:00",
+                None,
+            ),
+            ("q-a3", "https://a.com/3", "2024-01-04T00:00:00+00:00", None),
+        ] {
+            store
+                .insert(NewLink {
+                    long_url: long_url.to_string(),
+                    expires_at: expires_at.map(str::to_string),
+                    ..new_link(code, "carol", created_at)
+                })
+                .await
+                .expect("Failed to insert");
+        }
+
+        let base = ListQuery {
+            now: "2024-06-01T00:00:00+00:00".to_string(),
+            limit: 10,
+            ..ListQuery::default()
+        };
+        let codes = |page: &LinkPage| -> Vec<String> {
+            page.urls.iter().map(|url| url.short_code.clone()).collect()
+        };
+
+        // Newest first, ties broken by id, walking the cursor to the end
+        let mut query = ListQuery {
+            limit: 2,
+            ..base.clone()
+        };
+        let mut pages = Vec::new();
+        loop {
+            let page = store.list_page("carol", &query).await.unwrap();
+            assert_eq!(page.total, 5);
+            pages.push(codes(&page));
+            match page.next {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(
+            pages,
+            vec![vec!["q-a3", "q-b2"], vec!["p-b1", "p-a2"], vec!["p-a1"]]
+        );
+
+        let filtered = |query: ListQuery| async move {
+            let page = store.list_page("carol", &query).await.unwrap();
+            assert_eq!(page.total as usize, page.urls.len());
+            codes(&page)
+        };
+        let expired = ListQuery {
+            status: LinkStatus::Expired,
+            ..base.clone()
+        };
+        assert_eq!(filtered(expired).await, ["p-a2"]);
+        let active = ListQuery {
+            status: LinkStatus::Active,
+            ..base.clone()
+        };
+        assert_eq!(filtered(active).await.len(), 4);
+
+        let domain = |domain: &str| ListQuery {
+            domain: Some(domain.to_string()),
+            ..base.clone()
+        };
+        assert_eq!(filtered(domain("a.com")).await, ["q-a3", "p-a2", "p-a1"]);
+        assert_eq!(filtered(domain("b.com")).await, ["p-b1"]);
+        assert!(filtered(domain("_.com")).await.is_empty());
+
+        // The host must match exactly, whatever follows it and however it is written
+        for (code, long_url) in [
+            ("h-1", "http://C.com"),
+            ("h-2", "https://c.com?q=1"),
+            ("h-3", "https://evil.test/?next=https://c.com/x"),
+            ("h-4", "https://c.com.evil.test/"),
+        ] {
+            store
+                .insert(NewLink {
+                    long_url: long_url.to_string(),
+                    ..new_link(code, "grace", "2024-01-01T00:00:00+00:00")
+                })
+                .await
+                .expect("Failed to insert");
+        }
+        let page = store.list_page("grace", &domain("c.com")).await.unwrap();
+        assert_eq!(codes(&page), ["h-2", "h-1"]);
+
+        let prefix = |prefix: &str| ListQuery {
+            code_prefix: Some(prefix.to_string()),
+            ..base.clone()
+        };
+        assert_eq!(filtered(prefix("q-")).await, ["q-a3", "q-b2"]);
+        assert!(filtered(prefix("Q-")).await.is_empty());
+
+        let range = ListQuery {
+            created_from: Some("2024-01-02T00:00:00+00:00".to_string()),
+            created_to: Some("2024-01-04T00:00:00+00:00".to_string()),
+            ..base.clone()
+        };
+        assert_eq!(filtered(range).await, ["p-b1", "p-a2"]);
+
+        let by_code = ListQuery {
+            sort: Sort {
+                field: SortField::ShortCode,
+                descending: false,
+            },
+            ..base.clone()
+        };
+        assert_eq!(
+            filtered(by_code).await,
+            ["p-a1", "p-a2", "p-b1", "q-a3", "q-b2"]
+        );
+
+        // Other users' links are never listed
+        let page = store.list_page("nobody", &base).await.unwrap();
+        assert_eq!((page.total, page.urls.len()), (0, 0));
+    }
+}
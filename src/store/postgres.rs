@@ -0,0 +1,352 @@
+use super::{batch_committed, LinkChange, LinkPage, LinkStore, LinkUpdate, ListQuery, NewLink};
+use crate::config::ReaperMode;
+use crate::error::AppError;
+use crate::pb::abi::ShortUrl;
+use crate::utils::url_validator::{url_hash, url_host};
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{Acquire, Executor, PgPool, Postgres, QueryBuilder};
+
+async fn insert_link<'e, E>(executor: E, link: NewLink) -> Result<ShortUrl, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let url = sqlx::query_as::<_, ShortUrl>(
+        r#"
+        INSERT INTO short_urls
+            (id, long_url, short_code, created_at, expires_at, user_id, url_hash, host)
+        VALUES (
+            COALESCE($1, nextval(pg_get_serial_sequence('short_urls', 'id'))),
+            $2, $3, $4, $5, $6, $7, $8
+        )
+        RETURNING *
+        "#,
+    )
+    .bind(link.id)
+    .bind(&link.long_url)
+    .bind(&link.short_code)
+    .bind(&link.created_at)
+    .bind(&link.expires_at)
+    .bind(&link.user_id)
+    .bind(url_hash(&link.long_url))
+    .bind(url_host(&link.long_url))
+    .fetch_one(executor)
+    .await
+    .map_err(|e| match e {
+        // short_code 上有 UNIQUE 约束
+        sqlx::Error::Database(db) if db.is_unique_violation() => AppError::CodeTaken,
+        e => e.into(),
+    })?;
+
+    Ok(url)
+}
+
+#[derive(Clone)]
+pub struct PgLinkStore {
+    pool: PgPool,
+}
+
+impl PgLinkStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+}
+
+#[async_trait]
+impl LinkStore for PgLinkStore {
+    async fn insert(&self, link: NewLink) -> Result<ShortUrl, AppError> {
+        insert_link(&self.pool, link).await
+    }
+
+    async fn insert_batch(
+        &self,
+        links: Vec<NewLink>,
+        atomic: bool,
+    ) -> Result<Vec<Result<ShortUrl, AppError>>, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let mut results = Vec::with_capacity(links.len());
+        for link in links {
+            // 语句失败后 PostgreSQL 的事务不可再用，需回滚到保存点
+            let mut savepoint = (&mut tx).begin().await?;
+            let result = insert_link(&mut *savepoint, link).await;
+            match result {
+                Ok(_) => savepoint.commit().await?,
+                Err(_) => savepoint.rollback().await?,
+            }
+            results.push(result);
+        }
+
+        if batch_committed(&results, atomic) {
+            tx.commit().await?;
+        } else {
+            tx.rollback().await?;
+        }
+        Ok(results)
+    }
+
+    async fn reserve_ids(&self, count: usize) -> Result<Vec<i64>, AppError> {
+        let ids = sqlx::query_scalar(
+            "SELECT nextval(pg_get_serial_sequence('short_urls', 'id')) FROM generate_series(1, $1)",
+        )
+        .bind(count as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<ShortUrl>, AppError> {
+        let url = sqlx::query_as::<_, ShortUrl>("SELECT * FROM short_urls WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(url)
+    }
+
+    async fn get_by_code(&self, short_code: &str) -> Result<Option<ShortUrl>, AppError> {
+        let url = sqlx::query_as::<_, ShortUrl>("SELECT * FROM short_urls WHERE short_code = $1")
+            .bind(short_code)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(url)
+    }
+
+    async fn list_by_user(&self, user_id: &str) -> Result<Vec<ShortUrl>, AppError> {
+        let urls = sqlx::query_as::<_, ShortUrl>(
+            "SELECT * FROM short_urls WHERE user_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(urls)
+    }
+
+    async fn list_after(
+        &self,
+        user_id: Option<&str>,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<ShortUrl>, AppError> {
+        let urls = sqlx::query_as::<_, ShortUrl>(
+            r#"
+            SELECT * FROM short_urls
+            WHERE id > $1 AND ($2::TEXT IS NULL OR user_id = $2)
+            ORDER BY id
+            LIMIT $3
+            "#,
+        )
+        .bind(after_id)
+        .bind(user_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(urls)
+    }
+
+    async fn find_by_url(
+        &self,
+        user_id: &str,
+        long_url: &str,
+        now: &str,
+    ) -> Result<Option<ShortUrl>, AppError> {
+        let url = sqlx::query_as::<_, ShortUrl>(
+            r#"
+            SELECT * FROM short_urls
+            WHERE user_id = $1 AND url_hash = $2 AND (expires_at IS NULL OR expires_at > $3)
+            ORDER BY id
+            LIMIT 1
+            "#,
+        )
+        .bind(user_id)
+        .bind(url_hash(long_url))
+        .bind(now)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(url)
+    }
+
+    async fn list_page(&self, user_id: &str, query: &ListQuery) -> Result<LinkPage, AppError> {
+        let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM short_urls");
+        query.push_conditions(&mut count, user_id, false);
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut select = QueryBuilder::<Postgres>::new("SELECT * FROM short_urls");
+        query.push_conditions(&mut select, user_id, true);
+        query.push_order_and_limit(&mut select);
+        let urls = select
+            .build_query_as::<ShortUrl>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(query.page(urls, total))
+    }
+
+    async fn update(
+        &self,
+        id: i64,
+        user_id: &str,
+        update: &LinkUpdate,
+    ) -> Result<Option<LinkChange>, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let before = sqlx::query_as::<_, ShortUrl>(
+            "SELECT * FROM short_urls WHERE id = $1 AND user_id = $2 FOR UPDATE",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(before) = before else {
+            return Ok(None);
+        };
+
+        let after = sqlx::query_as::<_, ShortUrl>(
+            r#"
+            UPDATE short_urls
+            SET long_url = COALESCE($1, long_url),
+                url_hash = COALESCE($2, url_hash),
+                host = COALESCE($3, host),
+                short_code = COALESCE($4, short_code),
+                expires_at = CASE WHEN $5 THEN $6 ELSE expires_at END
+            WHERE id = $7
+            RETURNING *
+            "#,
+        )
+        .bind(&update.long_url)
+        .bind(update.long_url.as_deref().map(url_hash))
+        .bind(update.long_url.as_deref().map(url_host))
+        .bind(&update.short_code)
+        .bind(update.expires_at.is_some())
+        .bind(update.expires_at.clone().flatten())
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => AppError::CodeTaken,
+            e => e.into(),
+        })?;
+        tx.commit().await?;
+
+        Ok(Some(LinkChange { before, after }))
+    }
+
+    async fn delete(&self, id: i64, user_id: &str) -> Result<Option<ShortUrl>, AppError> {
+        let url = sqlx::query_as::<_, ShortUrl>(
+            "DELETE FROM short_urls WHERE id = $1 AND user_id = $2 RETURNING *",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(url)
+    }
+
+    async fn exists(&self, short_code: &str) -> Result<bool, AppError> {
+        let exists: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM short_urls WHERE short_code = $1)")
+                .bind(short_code)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(exists)
+    }
+
+    async fn all_codes(&self) -> Result<Vec<String>, AppError> {
+        let codes = sqlx::query_scalar("SELECT short_code FROM short_urls")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(codes)
+    }
+
+    async fn count_by_code_length(&self, length: usize) -> Result<u64, AppError> {
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM short_urls WHERE LENGTH(short_code) = $1")
+                .bind(length as i32)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(count as u64)
+    }
+
+    async fn backfill(&self, limit: usize) -> Result<usize, AppError> {
+        let rows: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT id, long_url FROM short_urls WHERE host IS NULL OR url_hash IS NULL LIMIT $1",
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut tx = self.pool.begin().await?;
+        for (id, long_url) in &rows {
+            sqlx::query("UPDATE short_urls SET host = $1, url_hash = $2 WHERE id = $3")
+                .bind(url_host(long_url))
+                .bind(url_hash(long_url))
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(rows.len())
+    }
+
+    // 单条语句完成删除和归档，SKIP LOCKED 避免与并发的清理任务互相等待
+    async fn remove_expired(
+        &self,
+        before: &str,
+        mode: ReaperMode,
+        limit: usize,
+    ) -> Result<Vec<ShortUrl>, AppError> {
+        let removed = sqlx::query_as::<_, ShortUrl>(
+            r#"
+            WITH removed AS (
+                DELETE FROM short_urls WHERE id IN (
+                    SELECT id FROM short_urls
+                    WHERE expires_at IS NOT NULL AND expires_at < $1
+                    ORDER BY expires_at
+                    LIMIT $2
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING *
+            ), archived AS (
+                INSERT INTO short_urls_archive
+                    (id, long_url, short_code, created_at, expires_at, user_id, archived_at)
+                SELECT id, long_url, short_code, created_at, expires_at, user_id, $3
+                FROM removed WHERE $4
+            )
+            SELECT * FROM removed ORDER BY expires_at
+            "#,
+        )
+        .bind(before)
+        .bind(limit as i64)
+        .bind(Utc::now().to_rfc3339())
+        .bind(mode == ReaperMode::Archive)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store::tests::{exercise_store, test_pg_store};
+
+    // 需要设置 TEST_POSTGRES_URL，未设置时跳过
+    #[tokio::test]
+    async fn test_pg_link_store() {
+        match test_pg_store().await {
+            Some(store) => exercise_store(&store).await,
+            None => eprintln!("TEST_POSTGRES_URL not set, skipping"),
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use crate::pb::abi::ShortUrl;
+use crate::utils::url_validator::url_host;
+use sqlx::{Database, Encode, QueryBuilder, Type};
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LinkStatus {
+    #[default]
+    All,
+    // 未设置过期时间或尚未过期
+    Active,
+    Expired,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortField {
+    #[default]
+    CreatedAt,
+    ShortCode,
+    LongUrl,
+}
+
+impl SortField {
+    pub fn column(&self) -> &'static str {
+        match self {
+            SortField::CreatedAt => "created_at",
+            SortField::ShortCode => "short_code",
+            SortField::LongUrl => "long_url",
+        }
+    }
+
+    fn value<'a>(&self, url: &'a ShortUrl) -> &'a str {
+        match self {
+            SortField::CreatedAt => &url.created_at,
+            SortField::ShortCode => &url.short_code,
+            SortField::LongUrl => &url.long_url,
+        }
+    }
+}
+
+// 排序字段相同时按 id 排序，保证游标位置唯一
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl Default for Sort {
+    // 与 list_by_user 一致，最新创建的在前
+    fn default() -> Self {
+        Self {
+            field: SortField::CreatedAt,
+            descending: true,
+        }
+    }
+}
+
+impl Sort {
+    pub fn compare(&self, a: &ShortUrl, b: &ShortUrl) -> Ordering {
+        let ordering = self
+            .field
+            .value(a)
+            .cmp(self.field.value(b))
+            .then(a.id.cmp(&b.id));
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    pub fn cursor(&self, url: &ShortUrl) -> Cursor {
+        Cursor {
+            value: self.field.value(url).to_string(),
+            id: url.id,
+        }
+    }
+
+    // 是否排在游标之后
+    fn after(&self, url: &ShortUrl, cursor: &Cursor) -> bool {
+        let ordering = self
+            .field
+            .value(url)
+            .cmp(&cursor.value)
+            .then(url.id.cmp(&cursor.id));
+        ordering
+            == if self.descending {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+    }
+}
+
+// 上一页最后一条记录的排序字段值和 id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    pub value: String,
+    pub id: i64,
+}
+
+// 列出某个用户的链接时的过滤、排序和分页条件
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListQuery {
+    pub status: LinkStatus,
+    // 判断是否过期的当前时间
+    pub now: String,
+    // created_at >= created_from
+    pub created_from: Option<String>,
+    // created_at < created_to
+    pub created_to: Option<String>,
+    // long_url 的主机名，须为小写，与 url_host 的结果比较
+    pub domain: Option<String>,
+    pub code_prefix: Option<String>,
+    pub sort: Sort,
+    pub cursor: Option<Cursor>,
+    pub limit: usize,
+}
+
+impl ListQuery {
+    // 除游标以外的条件，内存存储使用
+    pub fn matches(&self, url: &ShortUrl) -> bool {
+        let expired = url
+            .expires_at
+            .as_deref()
+            .is_some_and(|expires_at| expires_at <= self.now.as_str());
+        let status = match self.status {
+            LinkStatus::All => true,
+            LinkStatus::Active => !expired,
+            LinkStatus::Expired => expired,
+        };
+
+        status
+            && self
+                .created_from
+                .as_ref()
+                .is_none_or(|from| &url.created_at >= from)
+            && self
+                .created_to
+                .as_ref()
+                .is_none_or(|to| &url.created_at < to)
+            && self
+                .domain
+                .as_ref()
+                .is_none_or(|domain| url_host(&url.long_url) == *domain)
+            && self
+                .code_prefix
+                .as_ref()
+                .is_none_or(|prefix| url.short_code.starts_with(prefix.as_str()))
+    }
+
+    // 在内存中完成过滤、排序和分页
+    pub fn apply(&self, urls: impl Iterator<Item = ShortUrl>) -> LinkPage {
+        let mut urls: Vec<ShortUrl> = urls.filter(|url| self.matches(url)).collect();
+        let total = urls.len() as i64;
+
+        urls.sort_by(|a, b| self.sort.compare(a, b));
+        let urls = urls
+            .into_iter()
+            .filter(|url| {
+                self.cursor
+                    .as_ref()
+                    .is_none_or(|cursor| self.sort.after(url, cursor))
+            })
+            .take(self.limit + 1)
+            .collect();
+        self.page(urls, total)
+    }
+
+    // urls 最多为 limit + 1 条，多出的一条说明还有下一页
+    pub fn page(&self, mut urls: Vec<ShortUrl>, total: i64) -> LinkPage {
+        let next = if urls.len() > self.limit {
+            urls.truncate(self.limit);
+            urls.last().map(|url| self.sort.cursor(url))
+        } else {
+            None
+        };
+
+        LinkPage { urls, total, next }
+    }
+
+    // 追加 WHERE 条件，SQLite 和 PostgreSQL 共用
+    pub fn push_conditions<'a, DB>(
+        &self,
+        builder: &mut QueryBuilder<'a, DB>,
+        user_id: &str,
+        with_cursor: bool,
+    ) where
+        DB: Database,
+        String: Encode<'a, DB> + Type<DB>,
+        i64: Encode<'a, DB> + Type<DB>,
+    {
+        builder.push(" WHERE user_id = ");
+        builder.push_bind(user_id.to_string());
+
+        match self.status {
+            LinkStatus::All => {}
+            LinkStatus::Active => {
+                builder.push(" AND (expires_at IS NULL OR expires_at > ");
+                builder.push_bind(self.now.clone());
+                builder.push(")");
+            }
+            LinkStatus::Expired => {
+                builder.push(" AND expires_at IS NOT NULL AND expires_at <= ");
+                builder.push_bind(self.now.clone());
+            }
+        }
+        if let Some(from) = &self.created_from {
+            builder.push(" AND created_at >= ");
+            builder.push_bind(from.clone());
+        }
+        if let Some(to) = &self.created_to {
+            builder.push(" AND created_at < ");
+            builder.push_bind(to.clone());
+        }
+        if let Some(domain) = &self.domain {
+            // host 列在插入和修改时由 url_host 计算
+            builder.push(" AND host = ");
+            builder.push_bind(domain.clone());
+        }
+        if let Some(prefix) = &self.code_prefix {
+            // 不用 LIKE：SQLite 的 LIKE 不区分大小写
+            builder.push(format!(
+                " AND substr(short_code, 1, {}) = ",
+                prefix.chars().count()
+            ));
+            builder.push_bind(prefix.clone());
+        }
+
+        if let (true, Some(cursor)) = (with_cursor, &self.cursor) {
+            let column = self.sort.field.column();
+            let op = if self.sort.descending { "<" } else { ">" };
+            builder.push(format!(" AND ({} {} ", column, op));
+            builder.push_bind(cursor.value.clone());
+            builder.push(format!(" OR ({} = ", column));
+            builder.push_bind(cursor.value.clone());
+            builder.push(format!(" AND id {} ", op));
+            builder.push_bind(cursor.id);
+            builder.push("))");
+        }
+    }
+
+    pub fn push_order_and_limit<'a, DB>(&self, builder: &mut QueryBuilder<'a, DB>)
+    where
+        DB: Database,
+        i64: Encode<'a, DB> + Type<DB>,
+    {
+        let direction = if self.sort.descending { "DESC" } else { "ASC" };
+        builder.push(format!(
+            " ORDER BY {} {}, id {} LIMIT ",
+            self.sort.field.column(),
+            direction,
+            direction
+        ));
+        builder.push_bind(self.limit as i64 + 1);
+    }
+}
+
+// 一页结果，next 为下一页的游标
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkPage {
+    pub urls: Vec<ShortUrl>,
+    // 符合过滤条件的总数（不考虑游标）
+    pub total: i64,
+    pub next: Option<Cursor>,
+}
@@ -0,0 +1,466 @@
+use super::{batch_committed, LinkChange, LinkPage, LinkStore, LinkUpdate, ListQuery, NewLink};
+use crate::config::ReaperMode;
+use crate::error::AppError;
+use crate::pb::abi::ShortUrl;
+use crate::utils::url_validator::{url_hash, url_host};
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{Acquire, Executor, QueryBuilder, Sqlite, SqlitePool};
+
+async fn insert_link<'e, E>(executor: E, link: NewLink) -> Result<ShortUrl, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let result = sqlx::query(
+        r#"
+        INSERT INTO short_urls
+            (id, long_url, short_code, created_at, expires_at, user_id, url_hash, host)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(link.id)
+    .bind(&link.long_url)
+    .bind(&link.short_code)
+    .bind(&link.created_at)
+    .bind(&link.expires_at)
+    .bind(&link.user_id)
+    .bind(url_hash(&link.long_url))
+    .bind(url_host(&link.long_url))
+    .execute(executor)
+    .await
+    .map_err(|e| match e {
+        // short_code 上有 UNIQUE 约束
+        sqlx::Error::Database(db) if db.is_unique_violation() => AppError::CodeTaken,
+        e => e.into(),
+    })?;
+
+    Ok(ShortUrl {
+        id: result.last_insert_rowid(),
+        long_url: link.long_url,
+        short_code: link.short_code,
+        created_at: link.created_at,
+        expires_at: link.expires_at,
+        user_id: link.user_id,
+    })
+}
+
+#[derive(Clone)]
+pub struct SqliteLinkStore {
+    pool: SqlitePool,
+}
+
+impl SqliteLinkStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+}
+
+#[async_trait]
+impl LinkStore for SqliteLinkStore {
+    async fn insert(&self, link: NewLink) -> Result<ShortUrl, AppError> {
+        insert_link(&self.pool, link).await
+    }
+
+    async fn insert_batch(
+        &self,
+        links: Vec<NewLink>,
+        atomic: bool,
+    ) -> Result<Vec<Result<ShortUrl, AppError>>, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let mut results = Vec::with_capacity(links.len());
+        for link in links {
+            let mut savepoint = (&mut tx).begin().await?;
+            let result = insert_link(&mut *savepoint, link).await;
+            match result {
+                Ok(_) => savepoint.commit().await?,
+                Err(_) => savepoint.rollback().await?,
+            }
+            results.push(result);
+        }
+
+        if batch_committed(&results, atomic) {
+            tx.commit().await?;
+        } else {
+            tx.rollback().await?;
+        }
+        Ok(results)
+    }
+
+    // AUTOINCREMENT 的计数器保存在 sqlite_sequence 中，第一次插入前没有该表的记录
+    async fn reserve_ids(&self, count: usize) -> Result<Vec<i64>, AppError> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let count = count as i64;
+        let last: i64 = loop {
+            let updated = sqlx::query_scalar(
+                "UPDATE sqlite_sequence SET seq = seq + ? WHERE name = 'short_urls' RETURNING seq",
+            )
+            .bind(count)
+            .fetch_optional(&self.pool)
+            .await?;
+            if let Some(last) = updated {
+                break last;
+            }
+            let inserted = sqlx::query_scalar(
+                r#"
+                INSERT INTO sqlite_sequence (name, seq)
+                SELECT 'short_urls', ? WHERE NOT EXISTS (
+                    SELECT 1 FROM sqlite_sequence WHERE name = 'short_urls'
+                )
+                RETURNING seq
+                "#,
+            )
+            .bind(count)
+            .fetch_optional(&self.pool)
+            .await?;
+            // 同时有其他请求插入了记录时重新递增
+            if let Some(last) = inserted {
+                break last;
+            }
+        };
+        Ok((last - count + 1..=last).collect())
+    }
+
+    async fn get_by_id(&self, id: i64) -> Result<Option<ShortUrl>, AppError> {
+        let url = sqlx::query_as::<_, ShortUrl>("SELECT * FROM short_urls WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(url)
+    }
+
+    async fn get_by_code(&self, short_code: &str) -> Result<Option<ShortUrl>, AppError> {
+        let url = sqlx::query_as::<_, ShortUrl>("SELECT * FROM short_urls WHERE short_code = ?")
+            .bind(short_code)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(url)
+    }
+
+    async fn list_by_user(&self, user_id: &str) -> Result<Vec<ShortUrl>, AppError> {
+        let urls = sqlx::query_as::<_, ShortUrl>(
+            "SELECT * FROM short_urls WHERE user_id = ? ORDER BY created_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(urls)
+    }
+
+    async fn list_after(
+        &self,
+        user_id: Option<&str>,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<ShortUrl>, AppError> {
+        let urls = sqlx::query_as::<_, ShortUrl>(
+            r#"
+            SELECT * FROM short_urls
+            WHERE id > ? AND (? IS NULL OR user_id = ?)
+            ORDER BY id
+            LIMIT ?
+            "#,
+        )
+        .bind(after_id)
+        .bind(user_id)
+        .bind(user_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(urls)
+    }
+
+    async fn find_by_url(
+        &self,
+        user_id: &str,
+        long_url: &str,
+        now: &str,
+    ) -> Result<Option<ShortUrl>, AppError> {
+        let url = sqlx::query_as::<_, ShortUrl>(
+            r#"
+            SELECT * FROM short_urls
+            WHERE user_id = ? AND url_hash = ? AND (expires_at IS NULL OR expires_at > ?)
+            ORDER BY id
+            LIMIT 1
+            "#,
+        )
+        .bind(user_id)
+        .bind(url_hash(long_url))
+        .bind(now)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(url)
+    }
+
+    async fn list_page(&self, user_id: &str, query: &ListQuery) -> Result<LinkPage, AppError> {
+        let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM short_urls");
+        query.push_conditions(&mut count, user_id, false);
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut select = QueryBuilder::<Sqlite>::new("SELECT * FROM short_urls");
+        query.push_conditions(&mut select, user_id, true);
+        query.push_order_and_limit(&mut select);
+        let urls = select
+            .build_query_as::<ShortUrl>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(query.page(urls, total))
+    }
+
+    async fn update(
+        &self,
+        id: i64,
+        user_id: &str,
+        update: &LinkUpdate,
+    ) -> Result<Option<LinkChange>, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let before =
+            sqlx::query_as::<_, ShortUrl>("SELECT * FROM short_urls WHERE id = ? AND user_id = ?")
+                .bind(id)
+                .bind(user_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        let Some(before) = before else {
+            return Ok(None);
+        };
+
+        let after = sqlx::query_as::<_, ShortUrl>(
+            r#"
+            UPDATE short_urls
+            SET long_url = COALESCE(?, long_url),
+                url_hash = COALESCE(?, url_hash),
+                host = COALESCE(?, host),
+                short_code = COALESCE(?, short_code),
+                expires_at = CASE WHEN ? THEN ? ELSE expires_at END
+            WHERE id = ?
+            RETURNING *
+            "#,
+        )
+        .bind(&update.long_url)
+        .bind(update.long_url.as_deref().map(url_hash))
+        .bind(update.long_url.as_deref().map(url_host))
+        .bind(&update.short_code)
+        .bind(update.expires_at.is_some())
+        .bind(update.expires_at.clone().flatten())
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => AppError::CodeTaken,
+            e => e.into(),
+        })?;
+        tx.commit().await?;
+
+        Ok(Some(LinkChange { before, after }))
+    }
+
+    async fn delete(&self, id: i64, user_id: &str) -> Result<Option<ShortUrl>, AppError> {
+        let url = sqlx::query_as::<_, ShortUrl>(
+            "DELETE FROM short_urls WHERE id = ? AND user_id = ? RETURNING *",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(url)
+    }
+
+    async fn exists(&self, short_code: &str) -> Result<bool, AppError> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM short_urls WHERE short_code = ?")
+            .bind(short_code)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    async fn all_codes(&self) -> Result<Vec<String>, AppError> {
+        let codes = sqlx::query_scalar("SELECT short_code FROM short_urls")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(codes)
+    }
+
+    async fn count_by_code_length(&self, length: usize) -> Result<u64, AppError> {
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM short_urls WHERE LENGTH(short_code) = ?")
+                .bind(length as i64)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(count as u64)
+    }
+
+    async fn backfill(&self, limit: usize) -> Result<usize, AppError> {
+        let rows: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT id, long_url FROM short_urls WHERE host IS NULL OR url_hash IS NULL LIMIT ?",
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut tx = self.pool.begin().await?;
+        for (id, long_url) in &rows {
+            sqlx::query("UPDATE short_urls SET host = ?, url_hash = ? WHERE id = ?")
+                .bind(url_host(long_url))
+                .bind(url_hash(long_url))
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(rows.len())
+    }
+
+    async fn remove_expired(
+        &self,
+        before: &str,
+        mode: ReaperMode,
+        limit: usize,
+    ) -> Result<Vec<ShortUrl>, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let candidates = sqlx::query_as::<_, ShortUrl>(
+            r#"
+            SELECT * FROM short_urls
+            WHERE expires_at IS NOT NULL AND expires_at < ?
+            ORDER BY expires_at
+            LIMIT ?
+            "#,
+        )
+        .bind(before)
+        .bind(limit as i64)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let archived_at = Utc::now().to_rfc3339();
+        let mut removed = Vec::with_capacity(candidates.len());
+        for url in candidates {
+            // 先删除，确认没有被并发的清理任务处理过再归档
+            let result = sqlx::query("DELETE FROM short_urls WHERE id = ?")
+                .bind(url.id)
+                .execute(&mut *tx)
+                .await?;
+            if result.rows_affected() == 0 {
+                continue;
+            }
+
+            if mode == ReaperMode::Archive {
+                sqlx::query(
+                    r#"
+                    INSERT INTO short_urls_archive
+                        (id, long_url, short_code, created_at, expires_at, user_id, archived_at)
+                    VALUES (?, ?, ?, ?, ?, ?, ?)
+                    "#,
+                )
+                .bind(url.id)
+                .bind(&url.long_url)
+                .bind(&url.short_code)
+                .bind(&url.created_at)
+                .bind(&url.expires_at)
+                .bind(&url.user_id)
+                .bind(&archived_at)
+                .execute(&mut *tx)
+                .await?;
+            }
+            removed.push(url);
+        }
+        tx.commit().await?;
+
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[tokio::test]
+    async fn test_sqlite_link_store() {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+        db::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        crate::store::tests::exercise_store(&SqliteLinkStore::new(pool)).await;
+    }
+
+    #[tokio::test]
+    async fn test_domain_filter_and_backfill() {
+        let pool = SqlitePool::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+        db::run_migrations(&pool)
+            .await
+            .expect("Failed to run migrations");
+        let store = SqliteLinkStore::new(pool.clone());
+
+        // A URL without a path, and one created before the host column existed
+        store
+            .insert(NewLink {
+                id: None,
+                long_url: "http://Example.com".to_string(),
+                short_code: "nopath".to_string(),
+                created_at: "2024-01-01T00:00:00+00:00".to_string(),
+                expires_at: None,
+                user_id: "alice".to_string(),
+            })
+            .await
+            .expect("Failed to insert");
+        sqlx::query(
+            "INSERT INTO short_urls (long_url, short_code, created_at, user_id) \
+             VALUES ('https://example.com?q=1', 'legacy', '2024-01-02T00:00:00+00:00', 'alice')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let query = ListQuery {
+            domain: Some("example.com".to_string()),
+            limit: 10,
+            ..ListQuery::default()
+        };
+        let codes = |page: LinkPage| -> Vec<String> {
+            page.urls.into_iter().map(|url| url.short_code).collect()
+        };
+        assert_eq!(
+            codes(store.list_page("alice", &query).await.unwrap()),
+            ["nopath"]
+        );
+
+        let now = "2024-06-01T00:00:00+00:00";
+        assert!(store
+            .find_by_url("alice", "https://EXAMPLE.com/?q=1", now)
+            .await
+            .unwrap()
+            .is_none());
+
+        assert_eq!(crate::store::backfill_all(&store).await.unwrap(), 1);
+        assert_eq!(store.backfill(10).await.unwrap(), 0);
+        assert_eq!(
+            codes(store.list_page("alice", &query).await.unwrap()),
+            ["legacy", "nopath"]
+        );
+        // The backfilled hash makes the legacy link reusable
+        let reused = store
+            .find_by_url("alice", "https://EXAMPLE.com/?q=1", now)
+            .await
+            .unwrap();
+        assert_eq!(reused.map(|url| url.short_code).as_deref(), Some("legacy"));
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+// 计数布隆过滤器，支持删除。contains 返回 false 时元素一定不存在，
+// 返回 true 时可能存在（误判率由创建时的参数决定）
+pub struct CountingBloomFilter {
+    counters: Vec<u8>,
+    hashes: usize,
+    hasher1: RandomState,
+    hasher2: RandomState,
+}
+
+impl CountingBloomFilter {
+    // 按预计元素数量和期望误判率计算计数器数量和哈希函数个数
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Self {
+        let n = expected_items.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let size = (-n * false_positive_rate.ln() / (ln2 * ln2))
+            .ceil()
+            .max(1.0) as usize;
+        let hashes = ((size as f64 / n) * ln2).round().clamp(1.0, 16.0) as usize;
+
+        Self {
+            counters: vec![0; size],
+            hashes,
+            hasher1: RandomState::new(),
+            hasher2: RandomState::new(),
+        }
+    }
+
+    // 双重哈希：h1 + i * h2
+    fn indexes(&self, item: &str) -> impl Iterator<Item = usize> {
+        let h1 = self.hasher1.hash_one(item);
+        let h2 = self.hasher2.hash_one(item) | 1;
+        let size = self.counters.len() as u64;
+        (0..self.hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % size) as usize)
+    }
+
+    pub fn insert(&mut self, item: &str) {
+        for index in self.indexes(item).collect::<Vec<_>>() {
+            self.counters[index] = self.counters[index].saturating_add(1);
+        }
+    }
+
+    // 只应删除确实插入过的元素；计数器达到上限后不再减少，避免误删其他元素
+    pub fn remove(&mut self, item: &str) {
+        if !self.contains(item) {
+            return;
+        }
+        for index in self.indexes(item).collect::<Vec<_>>() {
+            if self.counters[index] != u8::MAX {
+                self.counters[index] -= 1;
+            }
+        }
+    }
+
+    pub fn contains(&self, item: &str) -> bool {
+        self.indexes(item).all(|index| self.counters[index] > 0)
+    }
+
+    pub fn size(&self) -> usize {
+        self.counters.len()
+    }
+
+    pub fn hashes(&self) -> usize {
+        self.hashes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sizing() {
+        // About 9.6 counters and 7 hashes per item for a 1% false positive rate
+        let filter = CountingBloomFilter::new(1000, 0.01);
+        assert_eq!(filter.size(), 9586);
+        assert_eq!(filter.hashes(), 7);
+    }
+
+    #[test]
+    fn test_insert_contains_remove() {
+        let mut filter = CountingBloomFilter::new(100, 0.01);
+        assert!(!filter.contains("abc123"));
+
+        filter.insert("abc123");
+        filter.insert("xyz789");
+        assert!(filter.contains("abc123"));
+        assert!(filter.contains("xyz789"));
+
+        filter.remove("abc123");
+        assert!(!filter.contains("abc123"));
+        assert!(filter.contains("xyz789"));
+
+        // Inserted twice, removed once
+        filter.insert("twice");
+        filter.insert("twice");
+        filter.remove("twice");
+        assert!(filter.contains("twice"));
+    }
+
+    #[test]
+    fn test_no_false_negatives_and_low_false_positives() {
+        let mut filter = CountingBloomFilter::new(10_000, 0.01);
+        let items: Vec<String> = (0..10_000).map(|i| format!("code{}", i)).collect();
+        for item in &items {
+            filter.insert(item);
+        }
+        assert!(items.iter().all(|item| filter.contains(item)));
+
+        let false_positives = (0..10_000)
+            .filter(|i| filter.contains(&format!("other{}", i)))
+            .count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+    }
+}
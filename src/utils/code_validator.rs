@@ -0,0 +1,85 @@
+use crate::config::CustomCodeConfig;
+
+// 默认保留的短码：顶层路由 /api 以及其下的各资源名，避免自定义短码与路由混淆
+pub const RESERVED_CODES: [&str; 8] = [
+    "api", "admin", "auth", "cache", "qrcode", "shorten", "tokens", "urls",
+];
+
+// 短码中允许的字符
+pub fn is_code_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+// 校验用户指定的短码，返回实际保存的短码，开启 case_insensitive 时转为小写。
+// 保留字总是不区分大小写比较，错误信息不含字段名
+pub fn validate_custom_code(code: &str, config: &CustomCodeConfig) -> Result<String, String> {
+    let length = code.chars().count();
+    if length < config.min_length || length > config.max_length {
+        return Err(format!(
+            "must be between {} and {} characters",
+            config.min_length, config.max_length
+        ));
+    }
+    if !code.chars().all(is_code_char) {
+        return Err("may only contain letters, digits, '-' and '_'".to_string());
+    }
+    if config
+        .reserved
+        .iter()
+        .any(|word| word.eq_ignore_ascii_case(code))
+    {
+        return Err(format!("'{}' is reserved", code));
+    }
+
+    Ok(if config.case_insensitive {
+        code.to_ascii_lowercase()
+    } else {
+        code.to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_custom_code() {
+        let config = CustomCodeConfig::default();
+        assert_eq!(
+            validate_custom_code("Promo_2024", &config).unwrap(),
+            "Promo_2024"
+        );
+        assert_eq!(validate_custom_code("a-b", &config).unwrap(), "a-b");
+
+        for code in ["", "ab", &"x".repeat(33)] {
+            assert_eq!(
+                validate_custom_code(code, &config).unwrap_err(),
+                "must be between 3 and 32 characters"
+            );
+        }
+        for code in ["a/b/c", "has space", "~pending", "ünï", "../x"] {
+            assert!(validate_custom_code(code, &config).is_err(), "{}", code);
+        }
+        for code in ["api", "QRCode", "urls"] {
+            assert_eq!(
+                validate_custom_code(code, &config).unwrap_err(),
+                format!("'{}' is reserved", code)
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_custom_code_with_config() {
+        let config = CustomCodeConfig {
+            min_length: 1,
+            max_length: 8,
+            reserved: vec!["Login".to_string()],
+            case_insensitive: true,
+        };
+        assert_eq!(validate_custom_code("X", &config).unwrap(), "x");
+        assert_eq!(validate_custom_code("Promo", &config).unwrap(), "promo");
+        assert_eq!(validate_custom_code("api", &config).unwrap(), "api");
+        assert!(validate_custom_code("LOGIN", &config).is_err());
+        assert!(validate_custom_code("too-long-code", &config).is_err());
+    }
+}
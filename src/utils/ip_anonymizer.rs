@@ -0,0 +1,58 @@
+use std::net::{IpAddr, SocketAddr};
+
+// IP Anonymizer
+// IPv4 保留前 24 位，IPv6 保留前 48 位，其余清零
+pub fn anonymize_ip(addr: &str) -> Option<String> {
+    let ip = parse_ip(addr)?;
+
+    let anonymized = match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            IpAddr::from([a, b, c, 0])
+        }
+        IpAddr::V6(v6) => {
+            let mut segments = v6.segments();
+            segments[3..].iter_mut().for_each(|s| *s = 0);
+            IpAddr::from(segments)
+        }
+    };
+
+    Some(anonymized.to_string())
+}
+
+// 兼容 "ip" 和 "ip:port" 两种格式
+fn parse_ip(addr: &str) -> Option<IpAddr> {
+    let addr = addr.trim();
+    addr.parse::<IpAddr>()
+        .ok()
+        .or_else(|| addr.parse::<SocketAddr>().ok().map(|s| s.ip()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anonymize_ipv4() {
+        assert_eq!(anonymize_ip("192.168.1.42").as_deref(), Some("192.168.1.0"));
+        assert_eq!(anonymize_ip("8.8.8.8:54321").as_deref(), Some("8.8.8.0"));
+    }
+
+    #[test]
+    fn test_anonymize_ipv6() {
+        assert_eq!(
+            anonymize_ip("2001:db8:85a3:8d3:1319:8a2e:370:7348").as_deref(),
+            Some("2001:db8:85a3::")
+        );
+        assert_eq!(
+            anonymize_ip("[2001:db8:85a3::1]:443").as_deref(),
+            Some("2001:db8:85a3::")
+        );
+    }
+
+    #[test]
+    fn test_anonymize_invalid_ip() {
+        assert!(anonymize_ip("not-an-ip").is_none());
+        assert!(anonymize_ip("").is_none());
+    }
+}
@@ -1,2 +1,8 @@
+pub mod bloom_filter;
+pub mod code_validator;
+pub mod ip_anonymizer;
+pub mod qr_renderer;
+pub mod rate_limiter;
 pub mod short_code_generator;
 pub mod url_validator;
+pub mod user_agent;
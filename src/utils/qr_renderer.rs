@@ -0,0 +1,319 @@
+use image::{ImageOutputFormat, Rgba, RgbaImage};
+use qrcode::{Color, EcLevel, QrCode};
+use std::fmt::Write;
+use std::io::Cursor;
+use std::str::FromStr;
+
+pub const DEFAULT_SIZE: u32 = 256;
+pub const MIN_SIZE: u32 = 64;
+pub const MAX_SIZE: u32 = 2048;
+pub const DEFAULT_MARGIN: u32 = 4;
+pub const MAX_MARGIN: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrFormat {
+    Png,
+    Svg,
+}
+
+impl QrFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            QrFormat::Png => "image/png",
+            QrFormat::Svg => "image/svg+xml",
+        }
+    }
+}
+
+impl FromStr for QrFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "png" => Ok(QrFormat::Png),
+            "svg" => Ok(QrFormat::Svg),
+            other => Err(format!("Unsupported QR code format: {}", other)),
+        }
+    }
+}
+
+// RGBA 颜色，支持 #RRGGBB 和 #RRGGBBAA
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QrColor(pub [u8; 4]);
+
+impl QrColor {
+    pub const BLACK: QrColor = QrColor([0, 0, 0, 255]);
+    pub const WHITE: QrColor = QrColor([255, 255, 255, 255]);
+
+    fn to_svg(self) -> String {
+        let [r, g, b, a] = self.0;
+        if a == 255 {
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+        }
+    }
+}
+
+impl FromStr for QrColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.trim_start_matches('#');
+        let invalid = || format!("Invalid color: {}", s);
+
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+        let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+
+        Ok(QrColor([channel(0)?, channel(2)?, channel(4)?, alpha]))
+    }
+}
+
+pub fn parse_ec_level(value: &str) -> Result<EcLevel, String> {
+    match value.to_ascii_uppercase().as_str() {
+        "L" => Ok(EcLevel::L),
+        "M" => Ok(EcLevel::M),
+        "Q" => Ok(EcLevel::Q),
+        "H" => Ok(EcLevel::H),
+        other => Err(format!("Invalid error correction level: {}", other)),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QrOptions {
+    pub format: QrFormat,
+    pub size: u32,
+    pub margin: u32,
+    pub ec_level: EcLevel,
+    pub foreground: QrColor,
+    pub background: QrColor,
+}
+
+impl Default for QrOptions {
+    fn default() -> Self {
+        Self {
+            format: QrFormat::Png,
+            size: DEFAULT_SIZE,
+            margin: DEFAULT_MARGIN,
+            ec_level: EcLevel::M,
+            foreground: QrColor::BLACK,
+            background: QrColor::WHITE,
+        }
+    }
+}
+
+impl QrOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_SIZE..=MAX_SIZE).contains(&self.size) {
+            return Err(format!(
+                "Size must be between {} and {} pixels",
+                MIN_SIZE, MAX_SIZE
+            ));
+        }
+        if self.margin > MAX_MARGIN {
+            return Err(format!("Margin must be at most {} modules", MAX_MARGIN));
+        }
+        Ok(())
+    }
+}
+
+// 生成二维码图片，返回图片字节
+pub fn render(data: &str, options: &QrOptions) -> Result<Vec<u8>, String> {
+    let code = encode(data, options)?;
+    render_code(&code, options)
+}
+
+// 编码二维码并检查选项，返回的错误都由请求参数导致。PNG 每个模块至少占一个像素，
+// size 小于模块数（含静区）时无法在该尺寸内绘制，返回所需的最小尺寸
+pub fn encode(data: &str, options: &QrOptions) -> Result<QrCode, String> {
+    options.validate()?;
+
+    let code = QrCode::with_error_correction_level(data, options.ec_level)
+        .map_err(|e| format!("Failed to encode QR code: {}", e))?;
+
+    let modules = code.width() as u32 + options.margin * 2;
+    if options.format == QrFormat::Png && options.size < modules {
+        return Err(format!(
+            "Size must be at least {} pixels for this QR code",
+            modules
+        ));
+    }
+    Ok(code)
+}
+
+// 绘制 encode 返回的二维码
+pub fn render_code(code: &QrCode, options: &QrOptions) -> Result<Vec<u8>, String> {
+    match options.format {
+        QrFormat::Png => render_png(code, options),
+        QrFormat::Svg => Ok(render_svg(code, options).into_bytes()),
+    }
+}
+
+// 每个模块的像素大小取整，实际图片尺寸不超过请求的 size（encode 保证 size 不小于模块数）
+fn render_png(code: &QrCode, options: &QrOptions) -> Result<Vec<u8>, String> {
+    let width = code.width() as u32;
+    let modules = width + options.margin * 2;
+    let scale = options.size / modules;
+    let pixels = modules * scale;
+
+    let colors = code.to_colors();
+    let foreground = Rgba(options.foreground.0);
+    let image = RgbaImage::from_fn(pixels, pixels, |x, y| {
+        let (mx, my) = (x / scale, y / scale);
+        let inside = (options.margin..options.margin + width).contains(&mx)
+            && (options.margin..options.margin + width).contains(&my);
+        if inside {
+            let index = ((my - options.margin) * width + (mx - options.margin)) as usize;
+            if colors[index] == Color::Dark {
+                return foreground;
+            }
+        }
+        Rgba(options.background.0)
+    });
+
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok(bytes)
+}
+
+// SVG 使用模块坐标作为 viewBox，按 size 缩放
+fn render_svg(code: &QrCode, options: &QrOptions) -> String {
+    let width = code.width();
+    let modules = width as u32 + options.margin * 2;
+
+    let mut path = String::new();
+    for (index, color) in code.to_colors().iter().enumerate() {
+        if *color == Color::Dark {
+            let x = (index % width) as u32 + options.margin;
+            let y = (index / width) as u32 + options.margin;
+            let _ = write!(path, "M{},{}h1v1h-1z", x, y);
+        }
+    }
+
+    format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{size}" height="{size}" viewBox="0 0 {modules} {modules}" shape-rendering="crispEdges">"#,
+            r#"<rect width="100%" height="100%" fill="{bg}"/>"#,
+            r#"<path d="{path}" fill="{fg}"/>"#,
+            "</svg>"
+        ),
+        size = options.size,
+        modules = modules,
+        bg = options.background.to_svg(),
+        fg = options.foreground.to_svg(),
+        path = path,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!("#000000".parse::<QrColor>(), Ok(QrColor::BLACK));
+        assert_eq!("ffffff".parse::<QrColor>(), Ok(QrColor::WHITE));
+        assert_eq!(
+            "#ff000080".parse::<QrColor>(),
+            Ok(QrColor([255, 0, 0, 128]))
+        );
+        assert!("#fff".parse::<QrColor>().is_err());
+        assert!("#gggggg".parse::<QrColor>().is_err());
+    }
+
+    #[test]
+    fn test_parse_ec_level() {
+        assert_eq!(parse_ec_level("l"), Ok(EcLevel::L));
+        assert_eq!(parse_ec_level("H"), Ok(EcLevel::H));
+        assert!(parse_ec_level("X").is_err());
+    }
+
+    #[test]
+    fn test_render_png() {
+        let options = QrOptions::default();
+        let bytes = render("http://localhost:8080/abc123", &options).unwrap();
+
+        // PNG signature
+        assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        assert!(image.width() <= options.size);
+        assert_eq!(image.width(), image.height());
+        // The top left corner is in the quiet zone
+        assert_eq!(image.get_pixel(0, 0), &Rgba(QrColor::WHITE.0));
+    }
+
+    #[test]
+    fn test_render_svg_with_colors() {
+        let options = QrOptions {
+            format: QrFormat::Svg,
+            margin: 2,
+            foreground: "#112233".parse().unwrap(),
+            background: "#ffeedd".parse().unwrap(),
+            ..QrOptions::default()
+        };
+        let svg =
+            String::from_utf8(render("http://localhost:8080/abc123", &options).unwrap()).unwrap();
+
+        assert!(svg.contains("<svg"));
+        assert!(svg.contains(r#"width="256""#));
+        assert!(svg.contains(r##"fill="#112233""##));
+        assert!(svg.contains(r##"fill="#ffeedd""##));
+    }
+
+    #[test]
+    fn test_render_rejects_invalid_options() {
+        let too_small = QrOptions {
+            size: 10,
+            ..QrOptions::default()
+        };
+        assert!(render("http://localhost:8080/abc123", &too_small).is_err());
+
+        let too_wide = QrOptions {
+            margin: 100,
+            ..QrOptions::default()
+        };
+        assert!(render("http://localhost:8080/abc123", &too_wide).is_err());
+    }
+
+    #[test]
+    fn test_png_size_must_fit_modules() {
+        // A long URL at level H needs far more than 64 modules
+        let data = format!("http://localhost:8080/{}", "x".repeat(100));
+        let dense = QrOptions {
+            size: MIN_SIZE,
+            ec_level: EcLevel::H,
+            margin: MAX_MARGIN,
+            ..QrOptions::default()
+        };
+        let err = render(&data, &dense).unwrap_err();
+        assert!(err.starts_with("Size must be at least"), "{}", err);
+
+        // The reported minimum renders within the requested size
+        let modules = QrCode::with_error_correction_level(&data, EcLevel::H)
+            .unwrap()
+            .width() as u32
+            + MAX_MARGIN * 2;
+        let fits = QrOptions {
+            size: modules,
+            ..dense.clone()
+        };
+        let bytes = render(&data, &fits).unwrap();
+        let image = image::load_from_memory(&bytes).unwrap();
+        assert_eq!(image.width(), modules);
+
+        // SVG scales to any size
+        let svg = QrOptions {
+            format: QrFormat::Svg,
+            ..dense
+        };
+        assert!(render(&data, &svg).is_ok());
+    }
+}
@@ -0,0 +1,151 @@
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// 最多保留的令牌桶数量。超过时丢弃最久未访问的桶，该 key 再次访问时按新桶（满令牌）处理，
+// 即有意放行而不是拒绝；大量轮换来源 IP 时每次请求的开销仍为 O(1)
+const MAX_BUCKETS: usize = 100_000;
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+// 一次请求的限流结果，用于生成 X-RateLimit-* 响应头
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    // 令牌桶回满所需时间
+    pub reset_after: Duration,
+    // 被拒绝时，下一个令牌可用前需要等待的时间
+    pub retry_after: Duration,
+}
+
+// 按 key 分桶的令牌桶限流器：容量为 burst，每分钟补充 per_minute 个令牌
+pub struct TokenBucketLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    buckets: Mutex<LruCache<String, Bucket>>,
+}
+
+impl TokenBucketLimiter {
+    pub fn new(burst: u32, per_minute: u32) -> Self {
+        Self::with_max_buckets(burst, per_minute, MAX_BUCKETS)
+    }
+
+    fn with_max_buckets(burst: u32, per_minute: u32, max_buckets: usize) -> Self {
+        let max_buckets = NonZeroUsize::new(max_buckets).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            capacity: burst.max(1) as f64,
+            refill_per_sec: per_minute.max(1) as f64 / 60.0,
+            buckets: Mutex::new(LruCache::new(max_buckets)),
+        }
+    }
+
+    pub fn check(&self, key: &str) -> Decision {
+        self.check_at(key, Instant::now())
+    }
+
+    pub fn check_at(&self, key: &str, now: Instant) -> Decision {
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.get_or_insert_mut(key.to_string(), || Bucket {
+            tokens: self.capacity,
+            updated_at: now,
+        });
+
+        let elapsed = now
+            .saturating_duration_since(bucket.updated_at)
+            .as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.updated_at = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+
+        let retry_after = if allowed {
+            Duration::ZERO
+        } else {
+            self.time_for(1.0 - bucket.tokens)
+        };
+
+        Decision {
+            allowed,
+            limit: self.capacity as u32,
+            remaining: bucket.tokens.floor() as u32,
+            reset_after: self.time_for(self.capacity - bucket.tokens),
+            retry_after,
+        }
+    }
+
+    fn time_for(&self, tokens: f64) -> Duration {
+        Duration::from_secs_f64(tokens.max(0.0) / self.refill_per_sec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_reject() {
+        let limiter = TokenBucketLimiter::new(3, 60);
+        let now = Instant::now();
+
+        for remaining in [2, 1, 0] {
+            let decision = limiter.check_at("ip:1.2.3.4", now);
+            assert!(decision.allowed);
+            assert_eq!(decision.limit, 3);
+            assert_eq!(decision.remaining, remaining);
+        }
+
+        let decision = limiter.check_at("ip:1.2.3.4", now);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Duration::from_secs(1));
+        assert_eq!(decision.reset_after, Duration::from_secs(3));
+
+        // Other keys have their own bucket
+        assert!(limiter.check_at("ip:5.6.7.8", now).allowed);
+    }
+
+    #[test]
+    fn test_refill() {
+        let limiter = TokenBucketLimiter::new(2, 60);
+        let now = Instant::now();
+
+        assert!(limiter.check_at("user:1", now).allowed);
+        assert!(limiter.check_at("user:1", now).allowed);
+        assert!(!limiter.check_at("user:1", now).allowed);
+
+        // One token per second
+        let later = now + Duration::from_millis(1500);
+        assert!(limiter.check_at("user:1", later).allowed);
+        assert!(!limiter.check_at("user:1", later).allowed);
+
+        // Never refills past the burst size
+        let much_later = now + Duration::from_secs(3600);
+        let decision = limiter.check_at("user:1", much_later);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 1);
+    }
+
+    #[test]
+    fn test_bucket_count_is_bounded() {
+        let limiter = TokenBucketLimiter::with_max_buckets(1, 60, 2);
+        let now = Instant::now();
+
+        assert!(limiter.check_at("ip:1", now).allowed);
+        assert!(!limiter.check_at("ip:1", now).allowed);
+        assert!(limiter.check_at("ip:2", now).allowed);
+        assert!(limiter.check_at("ip:3", now).allowed);
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 2);
+
+        // The least recently used bucket was dropped and starts full again
+        assert!(limiter.check_at("ip:1", now).allowed);
+        assert!(!limiter.check_at("ip:3", now).allowed);
+    }
+}
@@ -1,6 +1,291 @@
+use chrono::Utc;
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 
+// 按 ASCII 顺序排列，time 策略的短码按字符串排序即按时间排序
+pub const BASE62_ALPHABET: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+// time 策略中毫秒时间戳的位数，62^7 毫秒约为 111 年
+const TIME_WIDTH: usize = 7;
+const TIME_SUFFIX_LENGTH: usize = 3;
+
+// 自动生成短码的策略
+pub trait CodeGenerator: Send + Sync {
+    // 为 true 时短码由行 id 决定，插入前先通过 LinkStore::reserve_ids 分配 id
+    fn uses_row_id(&self) -> bool {
+        false
+    }
+
+    // id 为行 id，不依赖行 id 的策略忽略该参数。attempt 从 0 开始，短码已被占用时加一后重试
+    fn generate(&self, id: i64, attempt: u32) -> String;
+}
+
+// 从字符集中随机选取 length 个字符
+pub struct RandomGenerator {
+    alphabet: Vec<char>,
+    length: usize,
+}
+
+impl RandomGenerator {
+    pub fn new(alphabet: &str, length: usize) -> Self {
+        Self {
+            alphabet: alphabet.chars().collect(),
+            length,
+        }
+    }
+}
+
+impl CodeGenerator for RandomGenerator {
+    fn generate(&self, _id: i64, _attempt: u32) -> String {
+        random_code(&self.alphabet, self.length)
+    }
+}
+
+// 行 id 的 base62 编码，id 递增时短码最短。冲突时追加 "-" 和重试次数，
+// "-" 不在 base62 字符集中，不会与其他 id 的短码冲突
+pub struct Base62Generator;
+
+impl CodeGenerator for Base62Generator {
+    fn uses_row_id(&self) -> bool {
+        true
+    }
+
+    fn generate(&self, id: i64, attempt: u32) -> String {
+        let alphabet: Vec<char> = BASE62_ALPHABET.chars().collect();
+        let code = encode_number(id as u64, &alphabet);
+        match attempt {
+            0 => code,
+            attempt => format!("{}-{}", code, encode_number(attempt as u64, &alphabet)),
+        }
+    }
+}
+
+// 参照 Sqids 算法编码行 id，字符集先按 salt 打乱（参照 Hashids），
+// 不知道 salt 时无法从短码推出 id 或相邻的短码。冲突时编码 [id, attempt]
+pub struct SqidsGenerator {
+    alphabet: Vec<char>,
+    min_length: usize,
+}
+
+impl SqidsGenerator {
+    // alphabet 中的字符不能重复，至少 3 个
+    pub fn new(alphabet: &str, salt: &str, min_length: usize) -> Self {
+        let mut alphabet: Vec<char> = alphabet.chars().collect();
+        salt_shuffle(&mut alphabet, salt);
+        Self {
+            alphabet: shuffle(&alphabet),
+            min_length,
+        }
+    }
+
+    pub fn encode(&self, numbers: &[u64]) -> String {
+        let len = self.alphabet.len();
+        let offset = numbers
+            .iter()
+            .enumerate()
+            .fold(numbers.len(), |acc, (i, &n)| {
+                self.alphabet[(n % len as u64) as usize] as usize + i + acc
+            })
+            % len;
+
+        let mut alphabet = rotate(&self.alphabet, offset);
+        let prefix = alphabet[0];
+        alphabet.reverse();
+
+        let mut code = String::from(prefix);
+        for (i, &n) in numbers.iter().enumerate() {
+            code.push_str(&encode_number(n, &alphabet[1..]));
+            if i + 1 < numbers.len() {
+                code.push(alphabet[0]);
+                alphabet = shuffle(&alphabet);
+            }
+        }
+
+        if code.len() < self.min_length {
+            code.push(alphabet[0]);
+            while code.len() < self.min_length {
+                alphabet = shuffle(&alphabet);
+                let take = (self.min_length - code.len()).min(alphabet.len());
+                code.extend(&alphabet[..take]);
+            }
+        }
+        code
+    }
+
+    // 不是由该生成器产生的短码返回空
+    pub fn decode(&self, code: &str) -> Vec<u64> {
+        let mut chars = code.chars();
+        let Some(prefix) = chars.next() else {
+            return Vec::new();
+        };
+        let Some(offset) = self.alphabet.iter().position(|&c| c == prefix) else {
+            return Vec::new();
+        };
+        let mut alphabet = rotate(&self.alphabet, offset);
+        alphabet.reverse();
+
+        let mut numbers = Vec::new();
+        let mut rest: String = chars.collect();
+        while !rest.is_empty() {
+            let separator = alphabet[0];
+            let (chunk, tail) = rest.split_once(separator).unwrap_or((&rest, ""));
+            // 分隔符之后是 min_length 的填充
+            if chunk.is_empty() {
+                break;
+            }
+            match decode_number(chunk, &alphabet[1..]) {
+                Some(n) => numbers.push(n),
+                None => return Vec::new(),
+            }
+            if rest.contains(separator) {
+                alphabet = shuffle(&alphabet);
+            }
+            rest = tail.to_string();
+        }
+        numbers
+    }
+}
+
+impl CodeGenerator for SqidsGenerator {
+    fn uses_row_id(&self) -> bool {
+        true
+    }
+
+    fn generate(&self, id: i64, attempt: u32) -> String {
+        match attempt {
+            0 => self.encode(&[id as u64]),
+            attempt => self.encode(&[id as u64, attempt as u64]),
+        }
+    }
+}
+
+// 7 位 base62 毫秒时间戳加 3 位随机字符，后创建的短码排序在后
+pub struct TimeOrderedGenerator;
+
+impl TimeOrderedGenerator {
+    fn code_at(&self, millis: u64) -> String {
+        let alphabet: Vec<char> = BASE62_ALPHABET.chars().collect();
+        let time = encode_number(millis, &alphabet);
+        format!(
+            "{:0>width$}{}",
+            time,
+            random_code(&alphabet, TIME_SUFFIX_LENGTH),
+            width = TIME_WIDTH
+        )
+    }
+}
+
+impl CodeGenerator for TimeOrderedGenerator {
+    fn generate(&self, _id: i64, _attempt: u32) -> String {
+        self.code_at(Utc::now().timestamp_millis() as u64)
+    }
+}
+
+// 跳过保留字（不区分大小写）的包装，生成的短码是保留字时以 attempt + 1 重新生成。
+// 行 id 策略的不同 attempt 生成不同的短码，最多重试保留字个数次
+pub struct SkipReserved {
+    inner: Box<dyn CodeGenerator>,
+    reserved: Vec<String>,
+}
+
+impl SkipReserved {
+    pub fn new(inner: Box<dyn CodeGenerator>, reserved: &[String]) -> Self {
+        Self {
+            inner,
+            reserved: reserved.to_vec(),
+        }
+    }
+
+    fn is_reserved(&self, code: &str) -> bool {
+        self.reserved
+            .iter()
+            .any(|word| word.eq_ignore_ascii_case(code))
+    }
+}
+
+impl CodeGenerator for SkipReserved {
+    fn uses_row_id(&self) -> bool {
+        self.inner.uses_row_id()
+    }
+
+    fn generate(&self, id: i64, attempt: u32) -> String {
+        let mut code = self.inner.generate(id, attempt);
+        for attempt in (attempt + 1..).take(self.reserved.len()) {
+            if !self.is_reserved(&code) {
+                break;
+            }
+            code = self.inner.generate(id, attempt);
+        }
+        code
+    }
+}
+
+fn random_code(alphabet: &[char], length: usize) -> String {
+    let mut rng = thread_rng();
+    (0..length)
+        .map(|_| alphabet[rng.gen_range(0..alphabet.len())])
+        .collect()
+}
+
+// 按字符集进制编码，最高位在前
+fn encode_number(mut n: u64, alphabet: &[char]) -> String {
+    let base = alphabet.len() as u64;
+    let mut digits = Vec::new();
+    loop {
+        digits.push(alphabet[(n % base) as usize]);
+        n /= base;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
+fn decode_number(code: &str, alphabet: &[char]) -> Option<u64> {
+    code.chars().try_fold(0u64, |acc, c| {
+        let digit = alphabet.iter().position(|&a| a == c)?;
+        acc.checked_mul(alphabet.len() as u64)?
+            .checked_add(digit as u64)
+    })
+}
+
+fn rotate(alphabet: &[char], offset: usize) -> Vec<char> {
+    let mut rotated = alphabet.to_vec();
+    rotated.rotate_left(offset);
+    rotated
+}
+
+// Sqids 的确定性打乱
+fn shuffle(alphabet: &[char]) -> Vec<char> {
+    let mut chars = alphabet.to_vec();
+    let len = chars.len();
+    let (mut i, mut j) = (0, len - 1);
+    while j > 0 {
+        let r = (i * j + chars[i] as usize + chars[j] as usize) % len;
+        chars.swap(i, r);
+        i += 1;
+        j -= 1;
+    }
+    chars
+}
+
+// Hashids 的按 salt 打乱，salt 为空时不变
+fn salt_shuffle(alphabet: &mut [char], salt: &str) {
+    let salt: Vec<usize> = salt.chars().map(|c| c as usize).collect();
+    if salt.is_empty() {
+        return;
+    }
+    let (mut v, mut p) = (0, 0);
+    for i in (1..alphabet.len()).rev() {
+        v %= salt.len();
+        let n = salt[v];
+        p += n;
+        let j = (n + v + p) % i;
+        alphabet.swap(i, j);
+        v += 1;
+    }
+}
+
 pub fn generate_short_code() -> String {
     thread_rng()
         .sample_iter(&Alphanumeric)
@@ -70,4 +355,70 @@ mod tests {
         let code = generate_custom_code(0);
         assert_eq!(code, "");
     }
+
+    #[test]
+    fn test_random_generator() {
+        let generator = RandomGenerator::new("abc", 12);
+        let code = generator.generate(0, 0);
+        assert_eq!(code.len(), 12);
+        assert!(code.chars().all(|c| "abc".contains(c)));
+        assert!(!generator.uses_row_id());
+    }
+
+    #[test]
+    fn test_base62_generator() {
+        let generator = Base62Generator;
+        assert!(generator.uses_row_id());
+        assert_eq!(generator.generate(0, 0), "0");
+        assert_eq!(generator.generate(61, 0), "z");
+        assert_eq!(generator.generate(62, 0), "10");
+        assert_eq!(generator.generate(62, 1), "10-1");
+    }
+
+    #[test]
+    fn test_skip_reserved() {
+        let reserved = ["api".to_string(), "Auth".to_string()];
+        let generator = SkipReserved::new(Box::new(Base62Generator), &reserved);
+        assert!(generator.uses_row_id());
+        // 141590 is "api" and 8798525 is "auth" in base62
+        assert_eq!(Base62Generator.generate(141590, 0), "api");
+        assert_eq!(generator.generate(141590, 0), "api-1");
+        assert_eq!(generator.generate(8798525, 0), "auth-1");
+        assert_eq!(generator.generate(141591, 0), "apj");
+
+        // The whole keyspace is reserved: give up after one try per reserved word
+        let generator = SkipReserved::new(Box::new(RandomGenerator::new("a", 3)), &["aaa".into()]);
+        assert_eq!(generator.generate(0, 0), "aaa");
+    }
+
+    #[test]
+    fn test_sqids_generator() {
+        let generator = SqidsGenerator::new(BASE62_ALPHABET, "secret", 6);
+        let codes: HashSet<String> = (1..=1000).map(|id| generator.generate(id, 0)).collect();
+        assert_eq!(codes.len(), 1000);
+        for id in [1, 2, 1000, i64::MAX] {
+            let code = generator.generate(id, 0);
+            assert!(code.len() >= 6, "{}", code);
+            assert_eq!(generator.decode(&code), [id as u64]);
+        }
+        // Retries encode the attempt too, so they never collide with another id
+        assert_eq!(generator.decode(&generator.generate(7, 2)), [7, 2]);
+        assert!(generator.decode("not-from-here").is_empty());
+
+        // The salt changes every code
+        let other = SqidsGenerator::new(BASE62_ALPHABET, "other", 6);
+        assert_ne!(other.generate(1, 0), generator.generate(1, 0));
+        assert_eq!(other.decode(&other.generate(1, 0)), [1]);
+    }
+
+    #[test]
+    fn test_time_ordered_generator() {
+        let generator = TimeOrderedGenerator;
+        let earlier = generator.code_at(1_700_000_000_000);
+        let later = generator.code_at(1_700_000_000_001);
+        assert_eq!(earlier.len(), TIME_WIDTH + TIME_SUFFIX_LENGTH);
+        assert!(earlier < later);
+        assert!(generator.code_at(1) < earlier);
+        assert_eq!(generator.generate(0, 0).len(), 10);
+    }
 }
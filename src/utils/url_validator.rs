@@ -1,3 +1,5 @@
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use url::Url;
 
 // URL Validator
@@ -25,6 +27,48 @@ pub fn normalize_url(url: &str) -> String {
     }
 }
 
+// 用于去重比较的规范形式：协议和主机名小写、去掉默认端口、空路径补 "/"，无法解析时原样返回
+pub fn canonical_url(url: &str) -> String {
+    Url::parse(url).map_or_else(|_| url.to_string(), String::from)
+}
+
+// 规范形式的 SHA-256，保存在 short_urls.url_hash 列
+pub fn url_hash(url: &str) -> String {
+    hex::encode(Sha256::digest(canonical_url(url).as_bytes()))
+}
+
+// 小写、不带末尾 "." 的主机名，保存在 short_urls.host 列用于按域名过滤，无法解析时为空
+pub fn url_host(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            url.host_str()
+                .map(|host| host.trim_end_matches('.').to_ascii_lowercase())
+        })
+        .unwrap_or_default()
+}
+
+// 私有、回环、链路本地和未指定地址，IPv4 映射的 IPv6 地址按其 IPv4 地址判断
+pub fn is_internal_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_internal_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_internal_ipv4(ip),
+            None => is_internal_ipv6(ip),
+        },
+    }
+}
+
+fn is_internal_ipv4(ip: Ipv4Addr) -> bool {
+    ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified()
+}
+
+fn is_internal_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    // fc00::/7 唯一本地地址，fe80::/10 链路本地地址
+    ip.is_loopback() || ip.is_unspecified() || first & 0xfe00 == 0xfc00 || first & 0xffc0 == 0xfe80
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,4 +108,57 @@ mod tests {
         // Edge cases
         assert_eq!(normalize_url(""), "http://");
     }
+
+    #[test]
+    fn test_url_host() {
+        assert_eq!(url_host("http://Example.COM"), "example.com");
+        assert_eq!(url_host("https://example.com.:8443/a"), "example.com");
+        assert_eq!(url_host("https://example.com?q=1"), "example.com");
+        assert_eq!(
+            url_host("https://evil.test/?next=https://example.com/x"),
+            "evil.test"
+        );
+        assert_eq!(url_host("http://[::1]/"), "[::1]");
+        assert_eq!(url_host("not a url"), "");
+    }
+
+    #[test]
+    fn test_url_hash() {
+        assert_eq!(
+            canonical_url("HTTPS://Example.COM:443"),
+            "https://example.com/"
+        );
+        assert_eq!(
+            url_hash("http://example.com:80/a?b=1"),
+            url_hash("HTTP://EXAMPLE.com/a?b=1")
+        );
+        // Path and query stay case-sensitive
+        assert_ne!(
+            url_hash("https://example.com/A"),
+            url_hash("https://example.com/a")
+        );
+        assert_eq!(url_hash("https://example.com").len(), 64);
+    }
+
+    #[test]
+    fn test_is_internal_ip() {
+        for ip in [
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "127.0.0.1",
+            "169.254.169.254",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(is_internal_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["8.8.8.8", "172.32.0.1", "2001:4860::8888", "::ffff:8.8.8.8"] {
+            assert!(!is_internal_ip(ip.parse().unwrap()), "{}", ip);
+        }
+    }
 }
@@ -0,0 +1,157 @@
+// User-Agent Parser
+// 基于关键字的轻量解析，只用于访问统计的维度划分
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserAgentInfo {
+    pub browser: String,
+    pub os: String,
+    pub device: String,
+}
+
+const UNKNOWN: &str = "Unknown";
+
+pub fn parse_user_agent(user_agent: &str) -> UserAgentInfo {
+    let ua = user_agent.to_ascii_lowercase();
+
+    if ua.trim().is_empty() {
+        return UserAgentInfo {
+            browser: UNKNOWN.to_string(),
+            os: UNKNOWN.to_string(),
+            device: UNKNOWN.to_string(),
+        };
+    }
+
+    UserAgentInfo {
+        browser: detect_browser(&ua).to_string(),
+        os: detect_os(&ua).to_string(),
+        device: detect_device(&ua).to_string(),
+    }
+}
+
+fn is_bot(ua: &str) -> bool {
+    ["bot", "crawler", "spider", "slurp", "curl/", "wget/"]
+        .iter()
+        .any(|k| ua.contains(k))
+}
+
+// 顺序很重要：Edge、Opera 等基于 Chromium 的浏览器也会带上 "chrome"
+fn detect_browser(ua: &str) -> &'static str {
+    if is_bot(ua) {
+        "Bot"
+    } else if ua.contains("edg/") || ua.contains("edge/") {
+        "Edge"
+    } else if ua.contains("opr/") || ua.contains("opera") {
+        "Opera"
+    } else if ua.contains("samsungbrowser") {
+        "Samsung Internet"
+    } else if ua.contains("micromessenger") {
+        "WeChat"
+    } else if ua.contains("firefox/") || ua.contains("fxios") {
+        "Firefox"
+    } else if ua.contains("chrome/") || ua.contains("crios") {
+        "Chrome"
+    } else if ua.contains("safari/") {
+        "Safari"
+    } else if ua.contains("msie") || ua.contains("trident/") {
+        "Internet Explorer"
+    } else {
+        UNKNOWN
+    }
+}
+
+fn detect_os(ua: &str) -> &'static str {
+    if ua.contains("windows") {
+        "Windows"
+    } else if ua.contains("android") {
+        "Android"
+    } else if ua.contains("iphone") || ua.contains("ipad") || ua.contains("ipod") {
+        "iOS"
+    } else if ua.contains("cros") {
+        "Chrome OS"
+    } else if ua.contains("mac os x") || ua.contains("macintosh") {
+        "macOS"
+    } else if ua.contains("linux") {
+        "Linux"
+    } else {
+        UNKNOWN
+    }
+}
+
+fn detect_device(ua: &str) -> &'static str {
+    if is_bot(ua) {
+        "Bot"
+    } else if ua.contains("ipad")
+        || ua.contains("tablet")
+        || (ua.contains("android") && !ua.contains("mobile"))
+    {
+        "Tablet"
+    } else if ua.contains("mobi") || ua.contains("iphone") || ua.contains("ipod") {
+        "Mobile"
+    } else {
+        "Desktop"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_desktop_browsers() {
+        let chrome = parse_user_agent(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+        );
+        assert_eq!(chrome.browser, "Chrome");
+        assert_eq!(chrome.os, "Windows");
+        assert_eq!(chrome.device, "Desktop");
+
+        let edge = parse_user_agent(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.0.0",
+        );
+        assert_eq!(edge.browser, "Edge");
+
+        let firefox = parse_user_agent(
+            "Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0",
+        );
+        assert_eq!(firefox.browser, "Firefox");
+        assert_eq!(firefox.os, "Linux");
+
+        let safari = parse_user_agent(
+            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1 Safari/605.1.15",
+        );
+        assert_eq!(safari.browser, "Safari");
+        assert_eq!(safari.os, "macOS");
+    }
+
+    #[test]
+    fn test_parse_mobile_devices() {
+        let iphone = parse_user_agent(
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_1 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1 Mobile/15E148 Safari/604.1",
+        );
+        assert_eq!(iphone.os, "iOS");
+        assert_eq!(iphone.device, "Mobile");
+
+        let android_phone = parse_user_agent(
+            "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+        );
+        assert_eq!(android_phone.os, "Android");
+        assert_eq!(android_phone.device, "Mobile");
+
+        let android_tablet = parse_user_agent(
+            "Mozilla/5.0 (Linux; Android 13; SM-X700) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+        );
+        assert_eq!(android_tablet.device, "Tablet");
+    }
+
+    #[test]
+    fn test_parse_bots_and_unknown() {
+        let bot = parse_user_agent("Mozilla/5.0 (compatible; Googlebot/2.1)");
+        assert_eq!(bot.browser, "Bot");
+        assert_eq!(bot.device, "Bot");
+
+        let empty = parse_user_agent("");
+        assert_eq!(empty.browser, "Unknown");
+        assert_eq!(empty.os, "Unknown");
+        assert_eq!(empty.device, "Unknown");
+    }
+}